@@ -3,18 +3,38 @@
 OCR常驻服务
 使用RapidOCR进行文字识别，通过stdin/stdout通信
 模型只加载一次，大幅降低CPU占用
+
+握手：启动后首行读取 {"init": {...}}（语言、模型路径、线程数），加载模型后回复就绪
 """
 
 import sys
 import json
 import os
 
-# 限制ONNX线程数
-os.environ['OMP_NUM_THREADS'] = '1'
-os.environ['MKL_NUM_THREADS'] = '1'
-os.environ['OPENBLAS_NUM_THREADS'] = '1'
-os.environ['VECLIB_MAXIMUM_THREADS'] = '1'
-os.environ['NUMEXPR_NUM_THREADS'] = '1'
+# 默认模型支持的语言（tesseract 语言包名）
+DEFAULT_MODEL_LANGUAGES = {"chi_sim", "chi_tra", "eng"}
+
+
+def read_init():
+    """读取握手参数，旧版调用方不发送时使用默认设置"""
+    line = sys.stdin.readline().strip()
+    if not line:
+        return {}
+    try:
+        return json.loads(line).get("init") or {}
+    except (json.JSONDecodeError, AttributeError):
+        return {}
+
+
+init = read_init()
+threads = str(max(1, int(init.get("threads") or 1)))
+
+# 限制ONNX线程数（需在加载 onnxruntime 之前设置）
+os.environ['OMP_NUM_THREADS'] = threads
+os.environ['MKL_NUM_THREADS'] = threads
+os.environ['OPENBLAS_NUM_THREADS'] = threads
+os.environ['VECLIB_MAXIMUM_THREADS'] = threads
+os.environ['NUMEXPR_NUM_THREADS'] = threads
 
 # 降低进程优先级
 try:
@@ -24,13 +44,41 @@ try:
 except:
     pass
 
+def model_kwargs():
+    """由握手参数生成 RapidOCR 参数，未填写的模型路径使用内置模型"""
+    kwargs = {
+        "intra_op_num_threads": int(threads),
+        "inter_op_num_threads": 1,
+    }
+    for key in ("det_model_path", "rec_model_path", "rec_keys_path"):
+        path = init.get(key) or ""
+        if path:
+            if not os.path.exists(path):
+                raise FileNotFoundError(f"模型文件不存在: {path}")
+            kwargs[key] = path
+    return kwargs
+
+
+def language_warning():
+    """默认识别模型只支持中英文，选择其他语言时提示更换模型"""
+    languages = [lang for lang in (init.get("languages") or "").split("+") if lang]
+    unsupported = [lang for lang in languages if lang not in DEFAULT_MODEL_LANGUAGES]
+    if unsupported and not init.get("rec_model_path"):
+        return f"内置识别模型不支持 {'+'.join(unsupported)}，请指定对应语言的识别模型和字典"
+    return None
+
+
 def main():
     # 初始化OCR引擎（只加载一次）
     try:
         from rapidocr_onnxruntime import RapidOCR
-        ocr = RapidOCR(intra_op_num_threads=1, inter_op_num_threads=1)
-        # 发送就绪信号
-        print(json.dumps({"status": "ready"}), flush=True)
+        ocr = RapidOCR(**model_kwargs())
+        # 发送就绪信号，回传生效的设置
+        ready = {"status": "ready", "init": init}
+        warning = language_warning()
+        if warning:
+            ready["warning"] = warning
+        print(json.dumps(ready, ensure_ascii=False), flush=True)
     except ImportError:
         print(json.dumps({"error": "RapidOCR未安装，请运行: pip install rapidocr_onnxruntime"}), flush=True)
         sys.exit(1)
@@ -59,16 +107,23 @@ def main():
             # 执行OCR识别
             result, _ = ocr(image_path)
             
-            # 提取文本
+            # 提取文本及每行的四边形框和置信度
             texts = []
+            lines = []
             if result:
                 for item in result:
                     if item and len(item) >= 2:
                         texts.append(item[1])
+                        lines.append({
+                            "text": item[1],
+                            "box": [[float(x), float(y)] for x, y in item[0]],
+                            "score": float(item[2]) if len(item) >= 3 else 0.0,
+                        })
             
             print(json.dumps({
                 "success": True,
-                "text": "\n".join(texts)
+                "text": "\n".join(texts),
+                "lines": lines
             }, ensure_ascii=False), flush=True)
             
         except json.JSONDecodeError:
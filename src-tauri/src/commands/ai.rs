@@ -2,36 +2,15 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 use crate::models::AppConfig;
+use crate::services::{LlmProvider, LlmRequest, create_llm_provider};
 
 /// 获取基础数据目录（data_dir的父目录，用于存放配置文件）
-fn get_base_data_dir() -> PathBuf {
+pub(crate) fn get_base_data_dir() -> PathBuf {
     let config = AppConfig::load();
     // data_dir是data/data，我们需要data
     config.data_dir.parent().unwrap_or(&config.data_dir).to_path_buf()
 }
 
-fn get_chat_history_path() -> PathBuf {
-    let base_dir = get_base_data_dir();
-    fs::create_dir_all(&base_dir).ok();
-    base_dir.join("chat_history.json")
-}
-
-#[tauri::command]
-pub fn save_chat_history(sessions: String) -> Result<(), String> {
-    let path = get_chat_history_path();
-    fs::write(&path, sessions).map_err(|e| format!("保存对话历史失败: {}", e))
-}
-
-#[tauri::command]
-pub fn load_chat_history() -> Result<String, String> {
-    let path = get_chat_history_path();
-    if path.exists() {
-        fs::read_to_string(&path).map_err(|e| format!("加载对话历史失败: {}", e))
-    } else {
-        Ok("[]".to_string())
-    }
-}
-
 fn get_notes_path() -> PathBuf {
     let base_dir = get_base_data_dir();
     fs::create_dir_all(&base_dir).ok();
@@ -59,6 +38,20 @@ pub struct AIConfig {
     pub api_key: String,
     pub model: String,
     pub base_url: String,
+    /// 接口格式：openai（兼容接口）/ anthropic / ollama
+    #[serde(default = "default_api_format")]
+    pub api_format: String,
+    /// 对话时附带的历史消息上限（估算 token 数）
+    #[serde(default = "default_history_token_budget")]
+    pub history_token_budget: usize,
+}
+
+fn default_api_format() -> String {
+    "openai".to_string()
+}
+
+fn default_history_token_budget() -> usize {
+    4000
 }
 
 impl Default for AIConfig {
@@ -67,30 +60,26 @@ impl Default for AIConfig {
             api_key: String::new(),
             model: "qwen-plus".to_string(),
             base_url: "https://dashscope.aliyuncs.com/compatible-mode/v1".to_string(),
+            api_format: default_api_format(),
+            history_token_budget: default_history_token_budget(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatChoice {
-    message: ChatMessage,
+impl AIConfig {
+    /// 按配置创建大模型接口（未配置 API Key 时报错）
+    pub fn provider(&self) -> Result<Box<dyn LlmProvider>, String> {
+        create_llm_provider(&self.api_format, &self.base_url, &self.api_key, &self.model)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatResponse {
-    choices: Vec<ChatChoice>,
+/// 生成日记时发给模型的用户消息
+pub(crate) fn diary_request(activities_json: &str, prompt: &str) -> LlmRequest {
+    LlmRequest::user(format!(
+        "{}\n\n以下是今日的活动记录数据：\n{}",
+        prompt,
+        activities_json
+    ))
 }
 
 fn get_ai_config_path() -> PathBuf {
@@ -124,51 +113,8 @@ pub async fn save_ai_config(config: AIConfig) -> Result<(), String> {
 
 #[tauri::command]
 pub async fn generate_diary(activities_json: String, prompt: String) -> Result<String, String> {
-    let config = get_ai_config().await?;
-    
-    if config.api_key.is_empty() {
-        return Err("请先配置API Key".to_string());
-    }
-    
-    let full_prompt = format!(
-        "{}\n\n以下是今日的活动记录数据：\n{}",
-        prompt,
-        activities_json
-    );
-    
-    let request = ChatRequest {
-        model: config.model,
-        messages: vec![ChatMessage {
-            role: "user".to_string(),
-            content: full_prompt,
-        }],
-    };
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .post(format!("{}/chat/completions", config.base_url))
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API请求失败: {}", error_text));
-    }
-    
-    let chat_response: ChatResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
-    
-    chat_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .ok_or_else(|| "AI未返回内容".to_string())
+    let provider = get_ai_config().await?.provider()?;
+    provider.chat(&diary_request(&activities_json, &prompt)).await
 }
 
 #[tauri::command]
@@ -177,95 +123,17 @@ pub async fn ai_chat_stream(
     system_prompt: String,
     user_message: String
 ) -> Result<(), String> {
-    use futures_util::StreamExt;
     use tauri::Emitter;
     
-    let config = get_ai_config().await?;
-    
-    if config.api_key.is_empty() {
-        return Err("请先配置API Key".to_string());
-    }
-    
-    #[derive(serde::Serialize)]
-    struct StreamChatRequest {
-        model: String,
-        messages: Vec<ChatMessage>,
-        stream: bool,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct Delta {
-        content: Option<String>,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct StreamChoice {
-        delta: Delta,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct StreamChunk {
-        choices: Vec<StreamChoice>,
-    }
+    let provider = get_ai_config().await?.provider()?;
+    let request = LlmRequest::user(user_message).with_system(system_prompt);
     
-    let request = StreamChatRequest {
-        model: config.model,
-        messages: vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: user_message,
-            },
-        ],
-        stream: true,
+    let mut on_chunk = |content: &str| {
+        let _ = app.emit("chat-chunk", content);
     };
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .post(format!("{}/chat/completions", config.base_url))
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        let _ = app.emit("chat-error", error_text.clone());
-        return Err(format!("API请求失败: {}", error_text));
-    }
-    
-    let mut stream = response.bytes_stream();
-    
-    while let Some(chunk_result) = stream.next().await {
-        match chunk_result {
-            Ok(chunk) => {
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    if line.starts_with("data: ") {
-                        let data = &line[6..];
-                        if data == "[DONE]" {
-                            break;
-                        }
-                        if let Ok(chunk_data) = serde_json::from_str::<StreamChunk>(data) {
-                            if let Some(choice) = chunk_data.choices.first() {
-                                if let Some(content) = &choice.delta.content {
-                                    let _ = app.emit("chat-chunk", content.clone());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                let _ = app.emit("chat-error", e.to_string());
-                return Err(e.to_string());
-            }
-        }
+    if let Err(e) = provider.chat_stream(&request, &mut on_chunk).await {
+        let _ = app.emit("chat-error", e.clone());
+        return Err(e);
     }
     
     let _ = app.emit("chat-complete", ());
@@ -274,49 +142,6 @@ pub async fn ai_chat_stream(
 
 #[tauri::command]
 pub async fn ai_chat(system_prompt: String, user_message: String) -> Result<String, String> {
-    let config = get_ai_config().await?;
-    
-    if config.api_key.is_empty() {
-        return Err("请先配置API Key".to_string());
-    }
-    
-    let request = ChatRequest {
-        model: config.model,
-        messages: vec![
-            ChatMessage {
-                role: "system".to_string(),
-                content: system_prompt,
-            },
-            ChatMessage {
-                role: "user".to_string(),
-                content: user_message,
-            },
-        ],
-    };
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .post(format!("{}/chat/completions", config.base_url))
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API请求失败: {}", error_text));
-    }
-    
-    let chat_response: ChatResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
-    
-    chat_response
-        .choices
-        .first()
-        .map(|c| c.message.content.clone())
-        .ok_or_else(|| "AI未返回内容".to_string())
+    let provider = get_ai_config().await?.provider()?;
+    provider.chat(&LlmRequest::user(user_message).with_system(system_prompt)).await
 }
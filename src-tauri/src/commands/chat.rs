@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use crate::services::{LlmMessage, LlmRequest, estimate_tokens};
+use super::ai::{get_ai_config, get_base_data_dir};
+
+/// 新会话的默认标题，发送第一条消息后改为消息开头
+const DEFAULT_TITLE: &str = "新对话";
+/// 自动标题截取的字数
+const AUTO_TITLE_CHARS: usize = 20;
+/// 每条消息在请求中的额外开销（角色等），估算 token 时计入
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// 会话文件的读写锁（发送消息时先读历史，回复完成后再写回同一会话）
+static SESSIONS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub id: String,
+    pub role: ChatRole,
+    pub content: String,
+    pub timestamp: String,
+}
+
+impl ChatMessage {
+    fn new(role: ChatRole, content: String) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            role,
+            content,
+            timestamp: chrono::Local::now().to_rfc3339(),
+        }
+    }
+}
+
+/// 对话会话（每个会话保存为 chat_sessions 目录下的一个文件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub id: String,
+    pub title: String,
+    /// 对话查询的日期
+    pub date: String,
+    // 旧版前端保存的字段名为驼峰
+    #[serde(alias = "createdAt")]
+    pub created_at: String,
+    #[serde(alias = "updatedAt")]
+    pub updated_at: String,
+    pub messages: Vec<ChatMessage>,
+}
+
+/// 会话列表项（不含消息内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatSessionSummary {
+    pub id: String,
+    pub title: String,
+    pub date: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: usize,
+}
+
+impl From<&ChatSession> for ChatSessionSummary {
+    fn from(session: &ChatSession) -> Self {
+        Self {
+            id: session.id.clone(),
+            title: session.title.clone(),
+            date: session.date.clone(),
+            created_at: session.created_at.clone(),
+            updated_at: session.updated_at.clone(),
+            message_count: session.messages.len(),
+        }
+    }
+}
+
+fn sessions_dir() -> PathBuf {
+    let dir = get_base_data_dir().join("chat_sessions");
+    fs::create_dir_all(&dir).ok();
+    dir
+}
+
+fn session_path(id: &str) -> Result<PathBuf, String> {
+    // id 会拼进文件名，只允许 uuid / 时间戳这类字符
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("无效的会话ID: {}", id));
+    }
+    Ok(sessions_dir().join(format!("{}.json", id)))
+}
+
+fn load_session(id: &str) -> Result<ChatSession, String> {
+    let path = session_path(id)?;
+    if !path.exists() {
+        return Err("会话不存在".to_string());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取会话失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析会话失败: {}", e))
+}
+
+fn save_session(session: &ChatSession) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(session).map_err(|e| format!("序列化会话失败: {}", e))?;
+    fs::write(session_path(&session.id)?, content).map_err(|e| format!("保存会话失败: {}", e))
+}
+
+/// 把旧版前端整体保存的 chat_history.json 拆成单独的会话文件，完成后改名保留
+fn migrate_legacy_history() {
+    let legacy_path = get_base_data_dir().join("chat_history.json");
+    let Ok(content) = fs::read_to_string(&legacy_path) else {
+        return;
+    };
+    let sessions: Vec<ChatSession> = match serde_json::from_str(&content) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            eprintln!("解析旧版对话历史失败: {}", e);
+            return;
+        }
+    };
+    for session in &sessions {
+        if session_path(&session.id).is_ok_and(|path| !path.exists())
+            && let Err(e) = save_session(session)
+        {
+            eprintln!("迁移对话 {} 失败: {}", session.id, e);
+            return;
+        }
+    }
+    if let Err(e) = fs::rename(&legacy_path, legacy_path.with_extension("json.migrated")) {
+        eprintln!("重命名旧版对话历史失败: {}", e);
+    }
+}
+
+/// 新建会话
+#[tauri::command]
+pub fn create_chat_session(date: String, title: Option<String>) -> Result<ChatSession, String> {
+    let _guard = SESSIONS_LOCK.lock().unwrap();
+    let now = chrono::Local::now().to_rfc3339();
+    let session = ChatSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: title.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| DEFAULT_TITLE.to_string()),
+        date,
+        created_at: now.clone(),
+        updated_at: now,
+        messages: Vec::new(),
+    };
+    save_session(&session)?;
+    Ok(session)
+}
+
+/// 会话列表，最近更新的在前
+#[tauri::command]
+pub fn list_chat_sessions() -> Result<Vec<ChatSessionSummary>, String> {
+    let _guard = SESSIONS_LOCK.lock().unwrap();
+    migrate_legacy_history();
+
+    let entries = fs::read_dir(sessions_dir()).map_err(|e| format!("读取会话目录失败: {}", e))?;
+    let mut sessions: Vec<ChatSessionSummary> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let content = fs::read_to_string(entry.path()).ok()?;
+            match serde_json::from_str::<ChatSession>(&content) {
+                Ok(session) => Some(ChatSessionSummary::from(&session)),
+                Err(e) => {
+                    eprintln!("解析会话 {} 失败: {}", entry.path().display(), e);
+                    None
+                }
+            }
+        })
+        .collect();
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    Ok(sessions)
+}
+
+/// 获取会话及全部消息
+#[tauri::command]
+pub fn get_chat_session(id: String) -> Result<ChatSession, String> {
+    let _guard = SESSIONS_LOCK.lock().unwrap();
+    load_session(&id)
+}
+
+#[tauri::command]
+pub fn rename_chat_session(id: String, title: String) -> Result<ChatSessionSummary, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("标题不能为空".to_string());
+    }
+    let _guard = SESSIONS_LOCK.lock().unwrap();
+    let mut session = load_session(&id)?;
+    session.title = title.to_string();
+    session.updated_at = chrono::Local::now().to_rfc3339();
+    save_session(&session)?;
+    Ok(ChatSessionSummary::from(&session))
+}
+
+#[tauri::command]
+pub fn delete_chat_session(id: String) -> Result<(), String> {
+    let _guard = SESSIONS_LOCK.lock().unwrap();
+    let path = session_path(&id)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除会话失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 导出会话为 Markdown（format = "markdown"）或 JSON（format = "json"），返回导出文件路径
+#[tauri::command]
+pub fn export_chat_session(id: String, format: String) -> Result<String, String> {
+    let session = {
+        let _guard = SESSIONS_LOCK.lock().unwrap();
+        load_session(&id)?
+    };
+    let (content, extension) = match format.as_str() {
+        "markdown" | "md" => (session_to_markdown(&session), "md"),
+        "json" => (
+            serde_json::to_string_pretty(&session).map_err(|e| format!("序列化会话失败: {}", e))?,
+            "json",
+        ),
+        _ => return Err(format!("不支持的导出格式: {}", format)),
+    };
+
+    let export_dir = get_base_data_dir().join("exports");
+    fs::create_dir_all(&export_dir).map_err(|e| format!("创建导出目录失败: {}", e))?;
+    let path = export_dir.join(format!("chat_{}_{}.{}", session.date, session.id, extension));
+    fs::write(&path, content).map_err(|e| format!("导出会话失败: {}", e))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+fn session_to_markdown(session: &ChatSession) -> String {
+    let mut markdown = format!(
+        "# {}\n\n- 查询日期：{}\n- 创建时间：{}\n",
+        session.title,
+        session.date,
+        display_time(&session.created_at)
+    );
+    for message in &session.messages {
+        let speaker = match message.role {
+            ChatRole::User => "我",
+            ChatRole::Assistant => "AI",
+        };
+        markdown.push_str(&format!(
+            "\n## {} · {}\n\n{}\n",
+            speaker,
+            display_time(&message.timestamp),
+            message.content.trim()
+        ));
+    }
+    markdown
+}
+
+/// RFC 3339 时间转为本地可读格式，旧数据（前端的本地时间字符串）原样返回
+fn display_time(timestamp: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// 从最新的消息往前取，直到超出 token 预算
+///
+/// 结果以用户消息开头（部分接口要求），空消息跳过；没有得到回复的用户消息（旧版在请求失败时留下）也跳过，
+/// 保证用户和助手消息交替出现。
+fn fit_history(history: &[ChatMessage], budget: usize) -> Vec<LlmMessage> {
+    let messages: Vec<&ChatMessage> = history.iter().filter(|m| !m.content.trim().is_empty()).collect();
+    let answered = messages
+        .iter()
+        .enumerate()
+        .filter(|(i, m)| {
+            m.role == ChatRole::Assistant || messages.get(i + 1).is_some_and(|next| next.role == ChatRole::Assistant)
+        })
+        .map(|(_, m)| *m);
+
+    let mut used = 0;
+    let mut fitted: Vec<&ChatMessage> = Vec::new();
+    for message in answered.rev() {
+        used += estimate_tokens(&message.content) + MESSAGE_OVERHEAD_TOKENS;
+        if used > budget {
+            break;
+        }
+        fitted.push(message);
+    }
+    while fitted.last().is_some_and(|m| m.role == ChatRole::Assistant) {
+        fitted.pop();
+    }
+    fitted
+        .into_iter()
+        .rev()
+        .map(|m| match m.role {
+            ChatRole::User => LlmMessage::user(&m.content),
+            ChatRole::Assistant => LlmMessage::assistant(&m.content),
+        })
+        .collect()
+}
+
+/// 在会话中发送消息：附带预算内的历史消息，回复通过 chat-chunk 事件流式推送
+///
+/// 回复完成后用户消息和回复一起追加到会话，并通过 chat-complete 事件返回；请求失败时会话保持不变。
+#[tauri::command]
+pub async fn send_chat_message(
+    app: AppHandle,
+    session_id: String,
+    system_prompt: Option<String>,
+    content: String,
+) -> Result<ChatMessage, String> {
+    let content = content.trim().to_string();
+    if content.is_empty() {
+        return Err("消息不能为空".to_string());
+    }
+    let config = get_ai_config().await?;
+    let provider = config.provider()?;
+
+    let history = {
+        let _guard = SESSIONS_LOCK.lock().unwrap();
+        load_session(&session_id)?.messages
+    };
+    let user_message = ChatMessage::new(ChatRole::User, content.clone());
+
+    // 预算只限制历史消息，系统提示词（活动数据）和本次消息总是完整发送
+    let mut messages = fit_history(&history, config.history_token_budget);
+    messages.push(LlmMessage::user(content.clone()));
+    let request = LlmRequest {
+        system: system_prompt.filter(|s| !s.trim().is_empty()),
+        messages,
+        max_tokens: None,
+    };
+
+    let mut on_chunk = |chunk: &str| {
+        let _ = app.emit("chat-chunk", chunk);
+    };
+    let reply = match provider.chat_stream(&request, &mut on_chunk).await {
+        Ok(reply) => reply,
+        Err(e) => {
+            let _ = app.emit("chat-error", e.clone());
+            return Err(e);
+        }
+    };
+
+    let message = ChatMessage::new(ChatRole::Assistant, reply);
+    {
+        let _guard = SESSIONS_LOCK.lock().unwrap();
+        // 生成期间会话可能已被删除，此时只返回回复
+        if let Ok(mut session) = load_session(&session_id) {
+            if session.title == DEFAULT_TITLE && !session.messages.iter().any(|m| m.role == ChatRole::User) {
+                let mut title: String = content.chars().take(AUTO_TITLE_CHARS).collect();
+                if content.chars().count() > AUTO_TITLE_CHARS {
+                    title.push_str("...");
+                }
+                session.title = title;
+            }
+            session.messages.push(user_message);
+            session.messages.push(message.clone());
+            session.updated_at = chrono::Local::now().to_rfc3339();
+            save_session(&session)?;
+        }
+    }
+    let _ = app.emit("chat-complete", &message);
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: ChatRole, content: &str) -> ChatMessage {
+        ChatMessage::new(role, content.to_string())
+    }
+
+    fn roles(messages: &[LlmMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.role.as_str()).collect()
+    }
+
+    fn contents(messages: &[LlmMessage]) -> Vec<&str> {
+        messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn fit_history_keeps_newest_within_budget() {
+        let history = [
+            message(ChatRole::User, "第一问"),
+            message(ChatRole::Assistant, "第一答"),
+            message(ChatRole::User, "第二问"),
+            message(ChatRole::Assistant, "第二答"),
+        ];
+        // 每条 3 + 4 = 7 个 token
+        assert_eq!(contents(&fit_history(&history, 28)), ["第一问", "第一答", "第二问", "第二答"]);
+        assert_eq!(contents(&fit_history(&history, 27)), ["第二问", "第二答"]);
+        assert!(fit_history(&history, 6).is_empty());
+    }
+
+    #[test]
+    fn fit_history_starts_with_user_and_skips_empty() {
+        let history = [
+            message(ChatRole::User, "第一问"),
+            message(ChatRole::Assistant, "第一答"),
+            message(ChatRole::Assistant, "  "),
+            message(ChatRole::User, "第二问"),
+            message(ChatRole::Assistant, "第二答"),
+        ];
+        // 预算只够最后三条时，开头的助手消息被丢掉
+        let fitted = fit_history(&history, 21);
+        assert_eq!(roles(&fitted), ["user", "assistant"]);
+        assert_eq!(contents(&fitted), ["第二问", "第二答"]);
+    }
+
+    #[test]
+    fn fit_history_drops_unanswered_user_turns() {
+        let history = [
+            message(ChatRole::User, "第一问"),
+            message(ChatRole::Assistant, "第一答"),
+            message(ChatRole::User, "失败的问题"),
+            message(ChatRole::User, "第二问"),
+            message(ChatRole::Assistant, "第二答"),
+            message(ChatRole::User, "又失败了"),
+        ];
+        let fitted = fit_history(&history, 1000);
+        assert_eq!(roles(&fitted), ["user", "assistant", "user", "assistant"]);
+        assert_eq!(contents(&fitted), ["第一问", "第一答", "第二问", "第二答"]);
+    }
+}
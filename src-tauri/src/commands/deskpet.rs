@@ -1,5 +1,5 @@
 use serde_json::json;
-use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
 
 /// 获取全局鼠标位置（因为桌宠窗口设置了鼠标穿透，JS无法获取鼠标事件）
 #[tauri::command]
@@ -66,6 +66,13 @@ pub async fn create_pet_window(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// 显示或隐藏桌宠窗口（全屏时使用，不销毁窗口）
+pub fn set_pet_visible(app: &AppHandle, visible: bool) {
+    if let Some(window) = app.get_webview_window("deskpet") {
+        let _ = if visible { window.show() } else { window.hide() };
+    }
+}
+
 /// 关闭桌宠窗口
 #[tauri::command]
 pub async fn close_pet_window(app: tauri::AppHandle) -> Result<(), String> {
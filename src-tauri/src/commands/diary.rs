@@ -6,6 +6,7 @@ use std::sync::Mutex;
 use once_cell::sync::Lazy;
 use tauri::{AppHandle, Emitter};
 use crate::models::AppConfig;
+use crate::services::LlmProvider;
 
 static IS_GENERATING: AtomicBool = AtomicBool::new(false);
 static CURRENT_DIARY: Lazy<Mutex<DiaryState>> = Lazy::new(|| Mutex::new(DiaryState::default()));
@@ -50,11 +51,7 @@ pub async fn start_diary_generation(
         return Err("正在生成中，请稍候".to_string());
     }
     
-    let config = super::ai::get_ai_config().await?;
-    
-    if config.api_key.is_empty() {
-        return Err("请先配置API Key".to_string());
-    }
+    let provider = super::ai::get_ai_config().await?.provider()?;
     
     IS_GENERATING.store(true, Ordering::SeqCst);
     
@@ -70,10 +67,9 @@ pub async fn start_diary_generation(
     }
     
     // 在后台线程中执行流式生成
-    let config_clone = config.clone();
     let date_clone = date.clone();
     tokio::spawn(async move {
-        let result = generate_diary_stream(app.clone(), activities_json, prompt, config_clone).await;
+        let result = generate_diary_stream(app.clone(), activities_json, prompt, provider).await;
         
         let mut state = CURRENT_DIARY.lock().unwrap();
         state.is_generating = false;
@@ -103,109 +99,20 @@ async fn generate_diary_stream(
     app: AppHandle,
     activities_json: String,
     prompt: String,
-    config: super::ai::AIConfig,
+    provider: Box<dyn LlmProvider>,
 ) -> Result<String, String> {
-    use futures_util::StreamExt;
-    
-    #[derive(serde::Serialize)]
-    struct ChatMessage {
-        role: String,
-        content: String,
-    }
-    
-    #[derive(serde::Serialize)]
-    struct ChatRequest {
-        model: String,
-        messages: Vec<ChatMessage>,
-        stream: bool,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct Delta {
-        content: Option<String>,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct StreamChoice {
-        delta: Delta,
-        finish_reason: Option<String>,
-    }
-    
-    #[derive(serde::Deserialize)]
-    struct StreamChunk {
-        choices: Vec<StreamChoice>,
-    }
-    
-    let full_prompt = format!(
-        "{}\n\n以下是今日的活动记录数据：\n{}",
-        prompt,
-        activities_json
-    );
-    
-    let request = ChatRequest {
-        model: config.model,
-        messages: vec![ChatMessage {
-            role: "user".to_string(),
-            content: full_prompt,
-        }],
-        stream: true,
-    };
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .post(format!("{}/chat/completions", config.base_url))
-        .header("Authorization", format!("Bearer {}", config.api_key))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await
-        .map_err(|e| format!("请求失败: {}", e))?;
-    
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("API请求失败: {}", error_text));
-    }
-    
+    let request = super::ai::diary_request(&activities_json, &prompt);
     let mut full_content = String::new();
-    let mut stream = response.bytes_stream();
-    
-    let mut buffer = String::new();
-    
-    while let Some(chunk_result) = stream.next().await {
-        let chunk = chunk_result.map_err(|e| format!("读取流失败: {}", e))?;
-        let text = String::from_utf8_lossy(&chunk);
-        buffer.push_str(&text);
-        
-        // 处理可能跨chunk的数据
-        while let Some(newline_pos) = buffer.find('\n') {
-            let line = buffer[..newline_pos].trim().to_string();
-            buffer = buffer[newline_pos + 1..].to_string();
-            
-            if line.is_empty() || line == "data: [DONE]" {
-                continue;
-            }
-            
-            if let Some(json_str) = line.strip_prefix("data: ") {
-                if let Ok(chunk_data) = serde_json::from_str::<StreamChunk>(json_str) {
-                    for choice in chunk_data.choices {
-                        if let Some(content) = choice.delta.content {
-                            if !content.is_empty() {
-                                full_content.push_str(&content);
-                                // 发送流式内容到前端
-                                let _ = app.emit("diary-chunk", &content);
-                                // 更新状态
-                                if let Ok(mut state) = CURRENT_DIARY.lock() {
-                                    state.content = full_content.clone();
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    let mut on_chunk = |content: &str| {
+        full_content.push_str(content);
+        // 发送流式内容到前端
+        let _ = app.emit("diary-chunk", content);
+        // 更新状态
+        if let Ok(mut state) = CURRENT_DIARY.lock() {
+            state.content = full_content.clone();
         }
-    }
-    
-    Ok(full_content)
+    };
+    provider.chat_stream(&request, &mut on_chunk).await
 }
 
 fn save_diary_to_file(date: &str, content: &str) -> Result<PathBuf, String> {
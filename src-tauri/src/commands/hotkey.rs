@@ -0,0 +1,222 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+use crate::models::AppConfig;
+use crate::services::WindowTracker;
+use super::region::start_region_capture;
+use super::system::{capture_screenshot, get_config, toggle_tracking_paused};
+
+/// 全局快捷键对应的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// 手动截图
+    Screenshot,
+    /// 框选区域截图
+    RegionCapture,
+    /// 暂停/恢复记录
+    PauseTracking,
+    /// 打开快速笔记
+    QuickNote,
+}
+
+impl HotkeyAction {
+    fn label(&self) -> &'static str {
+        match self {
+            HotkeyAction::Screenshot => "手动截图",
+            HotkeyAction::RegionCapture => "区域截图",
+            HotkeyAction::PauseTracking => "暂停记录",
+            HotkeyAction::QuickNote => "快速笔记",
+        }
+    }
+}
+
+/// 快捷键注册结果
+#[derive(Debug, Clone, Serialize)]
+pub struct HotkeyStatus {
+    pub action: HotkeyAction,
+    pub accelerator: String,
+    pub registered: bool,
+    /// 未能注册的原因（格式无法识别、与其他操作重复、已被其他程序占用）
+    pub error: Option<String>,
+}
+
+/// 当前已注册的快捷键
+static REGISTERED: Mutex<Vec<(Shortcut, HotkeyAction)>> = Mutex::new(Vec::new());
+/// 最近一次注册结果
+static STATUS: Mutex<Vec<HotkeyStatus>> = Mutex::new(Vec::new());
+
+/// 配置中各操作的快捷键
+pub(crate) fn hotkey_bindings(config: &AppConfig) -> [(HotkeyAction, String); 4] {
+    [
+        (HotkeyAction::Screenshot, config.screenshot_hotkey.clone()),
+        (HotkeyAction::RegionCapture, config.region_capture_hotkey.clone()),
+        (HotkeyAction::PauseTracking, config.pause_tracking_hotkey.clone()),
+        (HotkeyAction::QuickNote, config.quick_note_hotkey.clone()),
+    ]
+}
+
+/// 按配置重新注册全部全局快捷键
+/// 有快捷键注册失败时通过 hotkey-status 事件通知前端
+pub fn register_hotkeys(app: &AppHandle) -> Vec<HotkeyStatus> {
+    let bindings = hotkey_bindings(&get_config());
+    let manager = app.global_shortcut();
+    let mut registered = REGISTERED.lock().unwrap();
+    for (shortcut, _) in registered.drain(..) {
+        let _ = manager.unregister(shortcut);
+    }
+
+    let resolved = resolve_bindings(&bindings);
+    let statuses: Vec<HotkeyStatus> = bindings
+        .into_iter()
+        .zip(resolved)
+        .map(|((action, accelerator), resolved)| {
+            let result = resolved.and_then(|shortcut| match shortcut {
+                Some(shortcut) => {
+                    manager
+                        .register(shortcut)
+                        .map_err(|e| format!("注册失败，可能已被其他程序占用: {}", e))?;
+                    registered.push((shortcut, action));
+                    Ok(true)
+                }
+                None => Ok(false),
+            });
+            if let Err(e) = &result {
+                eprintln!("注册快捷键失败 {} {}: {}", action.label(), accelerator, e);
+            }
+            HotkeyStatus {
+                action,
+                registered: result == Ok(true),
+                error: result.err(),
+                accelerator,
+            }
+        })
+        .collect();
+    drop(registered);
+
+    if statuses.iter().any(|s| s.error.is_some()) {
+        let _ = app.emit("hotkey-status", statuses.clone());
+    }
+    *STATUS.lock().unwrap() = statuses.clone();
+    statuses
+}
+
+/// 逐个解析快捷键：未配置的为 Ok(None)，无法识别或与前面的操作重复的返回错误
+fn resolve_bindings(bindings: &[(HotkeyAction, String)]) -> Vec<Result<Option<Shortcut>, String>> {
+    let mut seen: Vec<(Shortcut, HotkeyAction)> = Vec::new();
+    bindings
+        .iter()
+        .map(|(action, accelerator)| {
+            let accelerator = accelerator.trim();
+            if accelerator.is_empty() {
+                return Ok(None);
+            }
+            let shortcut: Shortcut = accelerator
+                .parse()
+                .map_err(|e| format!("无法识别的快捷键: {}", e))?;
+            if let Some((_, other)) = seen.iter().find(|(s, _)| *s == shortcut) {
+                return Err(format!("与「{}」的快捷键相同", other.label()));
+            }
+            seen.push((shortcut, *action));
+            Ok(Some(shortcut))
+        })
+        .collect()
+}
+
+/// 获取快捷键注册结果
+#[tauri::command]
+pub fn get_hotkey_status() -> Vec<HotkeyStatus> {
+    STATUS.lock().unwrap().clone()
+}
+
+/// 全局快捷键回调
+pub fn handle_hotkey(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = REGISTERED
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|(s, _)| s == shortcut)
+        .map(|(_, action)| *action);
+    let Some(action) = action else {
+        return;
+    };
+
+    let app = app.clone();
+    match action {
+        HotkeyAction::Screenshot => {
+            std::thread::spawn(move || {
+                let app_name = WindowTracker::new()
+                    .get_active_window()
+                    .map(|w| w.app_name)
+                    .unwrap_or_default();
+                let response = capture_screenshot(&app_name, None);
+                let _ = app.emit("hotkey-screenshot", response);
+            });
+        }
+        HotkeyAction::RegionCapture => {
+            // 回调在主线程执行，在其中同步创建窗口会死锁
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = start_region_capture(app).await {
+                    eprintln!("打开区域截图失败: {}", e);
+                }
+            });
+        }
+        HotkeyAction::PauseTracking => toggle_tracking_paused(&app),
+        HotkeyAction::QuickNote => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            let _ = app.emit("open-quick-note", ());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(accelerators: [&str; 4]) -> Vec<Result<Option<Shortcut>, String>> {
+        let mut config = AppConfig::default();
+        config.screenshot_hotkey = accelerators[0].to_string();
+        config.region_capture_hotkey = accelerators[1].to_string();
+        config.pause_tracking_hotkey = accelerators[2].to_string();
+        config.quick_note_hotkey = accelerators[3].to_string();
+        resolve_bindings(&hotkey_bindings(&config))
+    }
+
+    #[test]
+    fn empty_bindings_are_not_registered() {
+        let resolved = resolve(["", "  ", "", ""]);
+        assert!(resolved.iter().all(|r| *r == Ok(None)));
+    }
+
+    #[test]
+    fn valid_bindings_resolve_in_order() {
+        let resolved = resolve(["Ctrl+Shift+S", "Ctrl+Shift+A", "", "Alt+N"]);
+        assert_eq!(resolved[0], Ok(Some("Ctrl+Shift+S".parse().unwrap())));
+        assert_eq!(resolved[1], Ok(Some("Ctrl+Shift+A".parse().unwrap())));
+        assert_eq!(resolved[2], Ok(None));
+        assert!(matches!(resolved[3], Ok(Some(_))));
+    }
+
+    #[test]
+    fn unparseable_binding_is_reported() {
+        let resolved = resolve(["Ctrl+Shift+NotAKey", "Ctrl+Shift+A", "", ""]);
+        assert!(resolved[0].as_ref().unwrap_err().starts_with("无法识别的快捷键"));
+        assert!(matches!(resolved[1], Ok(Some(_))));
+    }
+
+    #[test]
+    fn duplicate_binding_names_the_first_action() {
+        // 修饰键顺序不同也是同一组合
+        let resolved = resolve(["Ctrl+Shift+S", "", "Shift+Ctrl+S", "Ctrl+Shift+S"]);
+        assert!(matches!(resolved[0], Ok(Some(_))));
+        assert_eq!(resolved[2], Err("与「手动截图」的快捷键相同".to_string()));
+        assert_eq!(resolved[3], Err("与「手动截图」的快捷键相同".to_string()));
+    }
+}
@@ -3,5 +3,10 @@ pub mod ai;
 pub mod diary;
 pub mod update;
 pub mod deskpet;
+pub mod timelapse;
+pub mod hotkey;
+pub mod region;
+pub mod ocr_backfill;
+pub mod chat;
 
 pub use system::*;
@@ -0,0 +1,248 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use crate::models::{AppConfig, OcrStatus};
+use crate::services::{OcrPriority, OcrRepository, Redactor, ScreenshotCatalog, recognize_image_with_priority};
+use super::system::{get_config, store_ocr_result};
+
+/// 每批识别的截图数，每批结束后保存进度
+const BATCH_SIZE: usize = 20;
+/// 批次之间的间隔，给实时截图的OCR留出空档
+const BATCH_PAUSE: Duration = Duration::from_secs(2);
+
+/// OCR补录进度（保存在数据目录，中断后可继续）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OcrBackfillProgress {
+    pub start_date: String,
+    pub end_date: String,
+    /// 正在处理的日期
+    pub current_date: Option<String>,
+    /// 当前日期待识别的截图数
+    pub date_total: usize,
+    pub date_done: usize,
+    /// 累计处理的截图数
+    pub processed: u64,
+    pub succeeded: u64,
+    pub empty: u64,
+    pub failed: u64,
+    pub running: bool,
+    pub finished: bool,
+    pub error: Option<String>,
+}
+
+static IS_RUNNING: AtomicBool = AtomicBool::new(false);
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static PROGRESS: Mutex<Option<OcrBackfillProgress>> = Mutex::new(None);
+
+fn progress_path(config: &AppConfig) -> PathBuf {
+    config.data_dir.join("ocr_backfill.json")
+}
+
+fn load_progress(config: &AppConfig) -> Option<OcrBackfillProgress> {
+    let content = fs::read_to_string(progress_path(config)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_progress(config: &AppConfig, progress: &OcrBackfillProgress) {
+    let result = serde_json::to_string_pretty(progress)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(progress_path(config), json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        eprintln!("保存OCR补录进度失败: {}", e);
+    }
+}
+
+/// 更新进度：保存到文件并通过 ocr-backfill-progress 事件推送
+fn report(app: &AppHandle, config: &AppConfig, progress: &OcrBackfillProgress) {
+    save_progress(config, progress);
+    *PROGRESS.lock().unwrap() = Some(progress.clone());
+    let _ = app.emit("ocr-backfill-progress", progress);
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("日期格式错误 {}: {}", date, e))
+}
+
+/// 获取OCR补录进度（没有补录过时为空）
+#[tauri::command]
+pub fn get_ocr_backfill_progress() -> Option<OcrBackfillProgress> {
+    if let Some(progress) = PROGRESS.lock().unwrap().clone() {
+        return Some(progress);
+    }
+    load_progress(&get_config()).map(|progress| OcrBackfillProgress { running: false, ..progress })
+}
+
+/// 为日期范围内还没有OCR记录的截图补录文本
+/// 以低优先级分批提交到OCR队列，进度通过 ocr-backfill-progress 事件推送
+#[tauri::command]
+pub fn start_ocr_backfill(app: AppHandle, start_date: String, end_date: String) -> Result<OcrBackfillProgress, String> {
+    let start = parse_date(&start_date)?;
+    let end = parse_date(&end_date)?;
+    if start > end {
+        return Err("开始日期不能晚于结束日期".to_string());
+    }
+    spawn_backfill(
+        app,
+        OcrBackfillProgress {
+            start_date,
+            end_date,
+            ..Default::default()
+        },
+    )
+}
+
+/// 继续上次中断的补录（暂停或程序退出）
+#[tauri::command]
+pub fn resume_ocr_backfill(app: AppHandle) -> Result<OcrBackfillProgress, String> {
+    let progress = load_progress(&get_config())
+        .filter(|p| !p.finished)
+        .ok_or("没有可继续的OCR补录")?;
+    spawn_backfill(app, progress)
+}
+
+/// 暂停补录，之后可继续；返回是否有正在进行的补录
+#[tauri::command]
+pub fn stop_ocr_backfill() -> bool {
+    let running = IS_RUNNING.load(Ordering::SeqCst);
+    if running {
+        STOP_REQUESTED.store(true, Ordering::SeqCst);
+    }
+    running
+}
+
+/// 启动时继续因程序退出而中断的补录（手动暂停的不自动继续）
+pub fn resume_unfinished_ocr_backfill(app: &AppHandle) {
+    if load_progress(&get_config()).is_some_and(|p| !p.finished && p.running)
+        && let Err(e) = resume_ocr_backfill(app.clone())
+    {
+        eprintln!("继续OCR补录失败: {}", e);
+    }
+}
+
+fn spawn_backfill(app: AppHandle, mut progress: OcrBackfillProgress) -> Result<OcrBackfillProgress, String> {
+    if IS_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("OCR补录正在进行中".to_string());
+    }
+    STOP_REQUESTED.store(false, Ordering::SeqCst);
+
+    progress.running = true;
+    progress.error = None;
+    report(&app, &get_config(), &progress);
+
+    let started = progress.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = run_backfill(&app, &mut progress).await;
+        let config = get_config();
+        match result {
+            // 暂停时保留 finished = false，下次可继续
+            Ok(completed) => progress.finished = completed,
+            Err(e) => {
+                eprintln!("OCR补录失败: {}", e);
+                progress.error = Some(e);
+            }
+        }
+        progress.running = false;
+        report(&app, &config, &progress);
+        IS_RUNNING.store(false, Ordering::SeqCst);
+    });
+    Ok(started)
+}
+
+/// 逐日补录，返回是否全部完成（被暂停时返回 false）
+///
+/// 是否已处理以OCR记录为准，所以从 `current_date` 重新扫描即可接着上次的位置继续。
+fn has_ocr_record(ocr: &OcrRepository, image_path: &str) -> bool {
+    ocr.has_record(image_path).unwrap_or_else(|e| {
+        eprintln!("查询OCR记录失败: {}", e);
+        false
+    })
+}
+
+async fn run_backfill(app: &AppHandle, progress: &mut OcrBackfillProgress) -> Result<bool, String> {
+    let end = parse_date(&progress.end_date)?;
+    let mut date = match &progress.current_date {
+        Some(current) => parse_date(current)?,
+        None => parse_date(&progress.start_date)?,
+    };
+    // 此后截取的截图由正常OCR队列识别
+    let started = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    while date <= end {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let config = get_config();
+        if config.get_date_dir(&date_str).exists() {
+            let screenshots = {
+                let catalog = ScreenshotCatalog::open(&config, &date_str)
+                    .map_err(|e| format!("打开截图目录失败: {}", e))?;
+                let ocr = OcrRepository::open(&config, &date_str).map_err(|e| format!("打开OCR记录失败: {}", e))?;
+                catalog
+                    .import_untracked(&date_str, &config.get_date_dir(&date_str).join("screenshots"), &ocr)
+                    .map_err(|e| format!("补录截图失败: {}", e))?;
+                catalog.without_ocr(&ocr, &started).map_err(|e| format!("查询截图失败: {}", e))?
+            };
+
+            progress.current_date = Some(date_str.clone());
+            progress.date_total = screenshots.len();
+            progress.date_done = 0;
+            report(app, &config, progress);
+
+            for batch in screenshots.chunks(BATCH_SIZE) {
+                let config = get_config();
+                let redactor = config.pii_redaction_enabled.then(|| Redactor::from_config(&config));
+                let catalog = ScreenshotCatalog::open(&config, &date_str)
+                    .map_err(|e| format!("打开截图目录失败: {}", e))?;
+                let ocr = OcrRepository::open(&config, &date_str).map_err(|e| format!("打开OCR记录失败: {}", e))?;
+
+                for screenshot in batch {
+                    if STOP_REQUESTED.load(Ordering::SeqCst) {
+                        return Ok(false);
+                    }
+                    let path = &screenshot.image_path;
+                    if !Path::new(path).exists() {
+                        // 文件已被删除，移出目录，避免每次补录都重试
+                        if let Err(e) = catalog.remove(path) {
+                            eprintln!("移除截图记录失败: {}", e);
+                        }
+                    } else if !has_ocr_record(&ocr, path) {
+                        let result = recognize_image_with_priority(&config, path, OcrPriority::Low).await;
+                        // 识别期间正常队列可能已经保存了结果
+                        if !has_ocr_record(&ocr, path) {
+                            let status = store_ocr_result(
+                                &date_str,
+                                &screenshot.timestamp,
+                                path,
+                                &screenshot.app_name,
+                                result,
+                                redactor.as_ref(),
+                            );
+                            match status {
+                                OcrStatus::Done => progress.succeeded += 1,
+                                OcrStatus::Empty => progress.empty += 1,
+                                _ => progress.failed += 1,
+                            }
+                            if let Err(e) = catalog.set_ocr_status(path, status) {
+                                eprintln!("更新截图OCR状态失败: {}", e);
+                            }
+                        }
+                    }
+                    progress.processed += 1;
+                    progress.date_done += 1;
+                }
+
+                report(app, &config, progress);
+                tokio::time::sleep(BATCH_PAUSE).await;
+            }
+        }
+
+        date = date.succ_opt().ok_or("日期超出范围")?;
+        progress.current_date = Some(date.format("%Y-%m-%d").to_string());
+        progress.date_total = 0;
+        progress.date_done = 0;
+    }
+    Ok(true)
+}
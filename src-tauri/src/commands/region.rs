@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use crate::services::WindowTracker;
+use super::system::{ScreenshotResponse, take_screenshot_area};
+
+/// 打开选区窗口前的前台应用（选区窗口打开后前台会变成它自己）
+static REGION_TARGET_APP: Mutex<Option<String>> = Mutex::new(None);
+
+/// 打开框选截图窗口，覆盖鼠标所在的显示器
+#[tauri::command]
+pub async fn start_region_capture(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window("region") {
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let app_name = WindowTracker::new()
+        .get_active_window()
+        .map(|w| w.app_name)
+        .unwrap_or_default();
+    *REGION_TARGET_APP.lock().unwrap() = Some(app_name);
+
+    let monitor = app
+        .cursor_position()
+        .ok()
+        .and_then(|p| app.monitor_from_point(p.x, p.y).ok().flatten())
+        .or_else(|| app.primary_monitor().ok().flatten())
+        .ok_or("未找到显示器")?;
+    let position = *monitor.position();
+    let size = *monitor.size();
+
+    // 前端按显示器原点把选区换算为屏幕物理坐标
+    let url = format!("/region?x={}&y={}", position.x, position.y);
+    let window = WebviewWindowBuilder::new(&app, "region", WebviewUrl::App(url.into()))
+        .title("区域截图")
+        .transparent(true)
+        .shadow(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .decorations(false)
+        .resizable(false)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("创建选区窗口失败: {}", e))?;
+
+    // 显示器缩放比例可能各不相同，直接按物理像素铺满
+    window
+        .set_position(position)
+        .and_then(|_| window.set_size(size))
+        .map_err(|e| format!("设置选区窗口失败: {}", e))?;
+    let _ = window.show();
+    let _ = window.set_focus();
+    Ok(())
+}
+
+/// 完成框选：关闭选区窗口后截取选区（屏幕物理坐标）
+/// 结果同时通过 hotkey-screenshot 事件推送
+#[tauri::command]
+pub async fn finish_region_capture(app: AppHandle, x: i32, y: i32, width: u32, height: u32) -> ScreenshotResponse {
+    close_region_window(&app);
+    let app_name = REGION_TARGET_APP.lock().unwrap().take().unwrap_or_default();
+
+    // 等选区窗口从屏幕上消失再截图
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let response = take_screenshot_area(app_name, x, y, width, height);
+    let _ = app.emit("hotkey-screenshot", response.clone());
+    response
+}
+
+/// 取消框选
+#[tauri::command]
+pub fn cancel_region_capture(app: AppHandle) {
+    close_region_window(&app);
+    REGION_TARGET_APP.lock().unwrap().take();
+}
+
+fn close_region_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("region") {
+        let _ = window.close();
+    }
+}
@@ -1,16 +1,20 @@
-use crate::models::{AppConfig, RawEvent, EventType};
-use crate::services::{StorageService, WindowTracker, get_app_icon, input_tracker, ScreenshotService};
+use crate::models::{AppConfig, PiiPattern, RawEvent, EventType, DailySummaryPack, OcrStatus, ScreenshotPage, ScreenshotQuery, ScreenshotRecord};
+use crate::services::{StorageService, WindowTracker, FocusChangeKind, get_app_icon, InputTracker, ScreenshotService, ImageSettings, CaptureOutcome, WindowInfo, WindowRect, ScreenshotScheduler, ScreenshotTrigger, ScreenshotCatalog, build_daily_summary, segment_id_at, is_screenshot_file, staging_path, Redactor, ScreenshotUsage, EvictionReport, OcrQueueStatus, OcrLine, OcrResult, OcrRecord, OcrRepository};
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 /// 全局配置状态
 static CONFIG: Mutex<Option<AppConfig>> = Mutex::new(None);
 
-fn get_config() -> AppConfig {
-    let guard = CONFIG.lock().unwrap();
-    guard.clone().unwrap_or_else(|| AppConfig::load())
+pub(crate) fn get_config() -> AppConfig {
+    let mut guard = CONFIG.lock().unwrap();
+    // 缓存首次加载的配置，编译好的黑名单规则等随之复用
+    guard.get_or_insert_with(AppConfig::load).clone()
 }
 
 fn set_config(config: AppConfig) {
@@ -40,6 +44,8 @@ pub struct EventForDisplay {
     pub key_count: Option<u32>,
     pub mouse_distance: Option<f64>,
     pub click_count: Option<u32>,
+    pub cmdline: Option<String>,
+    pub cwd: Option<String>,
     pub time_display: String,  // 格式化的时间显示 如 "09:12"
 }
 
@@ -66,6 +72,127 @@ pub fn get_active_window() -> std::result::Result<ActiveWindowInfo, String> {
     }
 }
 
+/// 焦点监听是否已启动
+static FOCUS_WATCHING: AtomicBool = AtomicBool::new(false);
+/// 前台窗口当前是否全屏
+static FULLSCREEN_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// 最近一次推送的前台窗口（前端注册监听前启动的监听会错过首个事件）
+static CURRENT_FOCUS: Mutex<Option<FocusChangePayload>> = Mutex::new(None);
+/// 当前状态段ID（最近一次焦点事件开启的段）
+static CURRENT_SEGMENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// 前台窗口当前是否处于全屏
+pub fn is_fullscreen_active() -> bool {
+    FULLSCREEN_ACTIVE.load(Ordering::SeqCst)
+}
+
+/// 是否已暂停记录（暂停期间不记录焦点和输入事件，也不自动截图）
+static TRACKING_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// 是否已暂停记录
+#[tauri::command]
+pub fn is_tracking_paused() -> bool {
+    TRACKING_PAUSED.load(Ordering::SeqCst)
+}
+
+/// 暂停或恢复记录，通过 tracking-paused 事件通知前端
+#[tauri::command]
+pub fn set_tracking_paused(app: AppHandle, paused: bool) {
+    if TRACKING_PAUSED.swap(paused, Ordering::SeqCst) != paused {
+        let _ = app.emit("tracking-paused", paused);
+    }
+}
+
+/// 切换暂停状态（快捷键调用）
+pub fn toggle_tracking_paused(app: &AppHandle) {
+    set_tracking_paused(app.clone(), !is_tracking_paused());
+}
+
+/// 推送给前端的焦点变化
+#[derive(Serialize, Clone)]
+pub struct FocusChangePayload {
+    pub kind: FocusChangeKind,
+    pub app_name: String,
+    pub window_title: String,
+    pub exe_path: String,
+    pub fullscreen: bool,
+    pub timestamp: String,
+}
+
+/// 启动前台窗口监听（事件驱动，不可用时回退轮询）
+/// 焦点和标题变化按实际发生时刻写入原始事件，并通过 window-focus-changed 事件推送到前端；
+/// 全屏状态翻转时额外推送 fullscreen-changed，并按配置隐藏/恢复桌宠
+#[tauri::command]
+pub fn start_focus_watch(app: AppHandle) -> std::result::Result<(), String> {
+    if FOCUS_WATCHING.swap(true, Ordering::SeqCst) {
+        return Ok(()); // 已经在监听
+    }
+
+    let config = get_config();
+    let changes = WindowTracker::new().subscribe(Duration::from_millis(config.poll_interval_ms));
+
+    std::thread::spawn(move || {
+        for change in changes {
+            let window = change.window;
+            let event = RawEvent::app_focus_at(
+                window.app_name.clone(),
+                window.window_title.clone(),
+                window.exe_path.clone(),
+                change.timestamp,
+            )
+            .with_process(window.process.clone(), window.foreground_process.clone())
+            .with_location(window.workspace, window.monitor.clone())
+            .with_fullscreen(window.fullscreen);
+            // 全屏翻转不是焦点变化，不写焦点事件也不开启新段
+            if !is_tracking_paused() {
+                if change.kind != FocusChangeKind::Fullscreen {
+                    let storage = StorageService::new(get_config());
+                    if let Err(e) = storage.append_raw_event(&event) {
+                        eprintln!("记录焦点事件失败: {}", e);
+                    }
+                    *CURRENT_SEGMENT.lock().unwrap() = Some(segment_id_at(change.timestamp.with_timezone(&chrono::Utc)));
+                }
+
+                app.state::<ScreenshotScheduler>().on_focus_change(
+                    &window.app_name,
+                    &window.window_title,
+                    &get_config(),
+                );
+            }
+
+            let payload = FocusChangePayload {
+                kind: change.kind,
+                app_name: window.app_name,
+                window_title: window.window_title,
+                exe_path: window.exe_path,
+                fullscreen: window.fullscreen,
+                timestamp: change.timestamp.to_rfc3339(),
+            };
+            *CURRENT_FOCUS.lock().unwrap() = Some(payload.clone());
+            let _ = app.emit("window-focus-changed", payload);
+
+            if FULLSCREEN_ACTIVE.swap(window.fullscreen, Ordering::SeqCst) != window.fullscreen {
+                let _ = app.emit("fullscreen-changed", window.fullscreen);
+                if get_config().fullscreen_hide_pet {
+                    super::deskpet::set_pet_visible(&app, !window.fullscreen);
+                }
+            }
+        }
+        FOCUS_WATCHING.store(false, Ordering::SeqCst);
+    });
+
+    Ok(())
+}
+
+/// 焦点监听最近推送的前台窗口（kind 为 focus），监听尚未收到事件时为空
+#[tauri::command]
+pub fn get_current_focus() -> Option<FocusChangePayload> {
+    CURRENT_FOCUS.lock().unwrap().clone().map(|payload| FocusChangePayload {
+        kind: FocusChangeKind::Focus,
+        ..payload
+    })
+}
+
 /// 获取应用图标（base64编码的PNG）
 #[tauri::command]
 pub fn get_icon_for_app(exe_path: String) -> Option<String> {
@@ -128,6 +255,9 @@ pub fn set_data_dir(path: String) -> std::result::Result<(), String> {
 /// 记录键盘事件（关联当前应用）
 #[tauri::command]
 pub fn record_keyboard_event(key_count: u32, app: String, window_title: String, exe_path: String) -> std::result::Result<(), String> {
+    if is_tracking_paused() {
+        return Ok(());
+    }
     let config = get_config();
     let storage = StorageService::new(config);
     let event = RawEvent::keyboard(key_count, app, window_title, exe_path);
@@ -137,6 +267,9 @@ pub fn record_keyboard_event(key_count: u32, app: String, window_title: String,
 /// 记录鼠标事件（关联当前应用）
 #[tauri::command]
 pub fn record_mouse_event(distance: f64, click_count: u32, app: String, window_title: String, exe_path: String) -> std::result::Result<(), String> {
+    if is_tracking_paused() {
+        return Ok(());
+    }
     let config = get_config();
     let storage = StorageService::new(config);
     let event = RawEvent::mouse(distance, click_count, app, window_title, exe_path);
@@ -146,6 +279,9 @@ pub fn record_mouse_event(distance: f64, click_count: u32, app: String, window_t
 /// 记录空闲事件
 #[tauri::command]
 pub fn record_idle_event(duration_sec: u64) -> std::result::Result<(), String> {
+    if is_tracking_paused() {
+        return Ok(());
+    }
     let config = get_config();
     let storage = StorageService::new(config);
     let event = RawEvent::idle(duration_sec);
@@ -181,6 +317,7 @@ pub fn get_events_grouped_by_date(date: String) -> std::result::Result<GroupedEv
                 EventType::Keyboard => "keyboard".to_string(),
                 EventType::Mouse => "mouse".to_string(),
                 EventType::Idle => "idle".to_string(),
+                EventType::ScreenshotSkipped => "screenshot_skipped".to_string(),
             },
             app: event.app.clone(),
             window_title: event.window_title.clone(),
@@ -188,6 +325,8 @@ pub fn get_events_grouped_by_date(date: String) -> std::result::Result<GroupedEv
             key_count: event.metadata.key_count,
             mouse_distance: event.metadata.mouse_distance,
             click_count: event.metadata.click_count,
+            cmdline: event.working_process().and_then(|p| p.cmdline.clone()),
+            cwd: event.working_process().and_then(|p| p.cwd.clone()),
             time_display: event.timestamp.format("%H:%M:%S").to_string(),
         };
         
@@ -196,12 +335,37 @@ pub fn get_events_grouped_by_date(date: String) -> std::result::Result<GroupedEv
             EventType::Keyboard => grouped.keyboard.push(display),
             EventType::Mouse => grouped.mouse.push(display),
             EventType::Idle => grouped.idle.push(display),
+            EventType::ScreenshotSkipped => {}
         }
     }
     
     Ok(grouped)
 }
 
+/// 获取指定日期的汇总数据（状态段、按应用/工作区/显示器统计）
+#[tauri::command]
+pub fn get_daily_summary(date: String) -> std::result::Result<DailySummaryPack, String> {
+    let config = get_config();
+    let storage = StorageService::new(config.clone());
+    let events = storage.read_raw_events_by_date(&date).map_err(|e| e.to_string())?;
+    let mut pack = build_daily_summary(&date, &events);
+
+    // 每个状态段关联其第一张截图
+    if config.get_date_dir(&date).join("events.db").exists()
+        && let Ok(catalog) = ScreenshotCatalog::open(&config, &date)
+        && let Ok(page) = catalog.query(&ScreenshotQuery { limit: u32::MAX, ..Default::default() })
+    {
+        for segment in pack.segments.iter_mut() {
+            segment.screenshot_path = page
+                .items
+                .iter()
+                .find(|s| s.segment_id.as_deref() == Some(segment.id.as_str()))
+                .map(|s| s.image_path.clone());
+        }
+    }
+    Ok(pack)
+}
+
 /// 获取当日所有事件（平铺列表）
 #[tauri::command]
 pub fn get_today_events() -> std::result::Result<Vec<EventForDisplay>, String> {
@@ -220,6 +384,7 @@ pub fn get_today_events() -> std::result::Result<Vec<EventForDisplay>, String> {
                 EventType::Keyboard => "keyboard".to_string(),
                 EventType::Mouse => "mouse".to_string(),
                 EventType::Idle => "idle".to_string(),
+                EventType::ScreenshotSkipped => "screenshot_skipped".to_string(),
             },
             app: event.app.clone(),
             window_title: event.window_title.clone(),
@@ -227,6 +392,8 @@ pub fn get_today_events() -> std::result::Result<Vec<EventForDisplay>, String> {
             key_count: event.metadata.key_count,
             mouse_distance: event.metadata.mouse_distance,
             click_count: event.metadata.click_count,
+            cmdline: event.working_process().and_then(|p| p.cmdline.clone()),
+            cwd: event.working_process().and_then(|p| p.cwd.clone()),
             time_display: event.timestamp.format("%H:%M:%S").to_string(),
         })
         .collect();
@@ -243,17 +410,28 @@ pub struct InputStatsResponse {
     pub idle_seconds: u64,
 }
 
+/// 前端默认使用的输入统计订阅者
+const UI_INPUT_SUBSCRIBER: &str = "ui";
+
 /// 启动全局输入监听
 #[tauri::command]
-pub fn start_input_listening() -> std::result::Result<(), String> {
-    input_tracker::start_listening();
+pub fn start_input_listening(tracker: State<'_, InputTracker>) -> std::result::Result<(), String> {
+    tracker.subscribe(UI_INPUT_SUBSCRIBER);
+    tracker.start();
+    Ok(())
+}
+
+/// 停止全局输入监听
+#[tauri::command]
+pub fn stop_input_listening(tracker: State<'_, InputTracker>) -> std::result::Result<(), String> {
+    tracker.stop();
     Ok(())
 }
 
-/// 获取输入统计并重置计数器
+/// 获取输入统计并重置计数器（仅重置指定订阅者，默认为前端）
 #[tauri::command]
-pub fn get_input_stats() -> InputStatsResponse {
-    let stats = input_tracker::get_and_reset_stats();
+pub fn get_input_stats(tracker: State<'_, InputTracker>, subscriber: Option<String>) -> InputStatsResponse {
+    let stats = tracker.take_stats(subscriber.as_deref().unwrap_or(UI_INPUT_SUBSCRIBER));
     InputStatsResponse {
         key_count: stats.key_count,
         click_count: stats.click_count,
@@ -264,26 +442,58 @@ pub fn get_input_stats() -> InputStatsResponse {
 
 /// 获取当前空闲时间（秒）
 #[tauri::command]
-pub fn get_idle_seconds() -> u64 {
-    input_tracker::get_idle_seconds()
+pub fn get_idle_seconds(tracker: State<'_, InputTracker>) -> u64 {
+    tracker.idle_seconds()
 }
 
 /// 检查输入监听是否运行中
 #[tauri::command]
-pub fn is_input_listening() -> bool {
-    input_tracker::is_listening()
+pub fn is_input_listening(tracker: State<'_, InputTracker>) -> bool {
+    tracker.is_listening()
 }
 
 /// 应用配置响应结构
 #[derive(Serialize, Deserialize)]
 pub struct AppConfigResponse {
     pub poll_interval_ms: u64,
+    pub screenshot_blacklist: Vec<String>,
+    pub screenshot_blacklist_titles: Vec<String>,
+    pub screenshot_blacklist_exes: Vec<String>,
     pub idle_threshold_sec: u64,
     pub screenshot_enabled: bool,
     pub screenshot_trigger_sec: u32,
     pub screenshot_interval_sec: u32,
+    pub screenshot_on_app_switch: bool,
+    pub screenshot_title_patterns: Vec<String>,
+    pub screenshot_dedup_distance: u32,
+    pub screenshot_format: String,
+    pub screenshot_quality: u8,
+    pub screenshot_max_width: u32,
+    pub screenshot_max_height: u32,
+    pub thumbnail_size: u32,
+    pub screenshot_quota_mb: u64,
     pub screenshot_mode: String,
+    pub screenshot_monitors: String,
     pub screenshot_hotkey: String,
+    pub region_capture_hotkey: String,
+    pub pause_tracking_hotkey: String,
+    pub quick_note_hotkey: String,
+    pub pii_redaction_enabled: bool,
+    pub pii_redaction_style: String,
+    pub pii_patterns: Vec<PiiPattern>,
+    pub fullscreen_pause_screenshot: bool,
+    pub fullscreen_pause_ocr: bool,
+    pub fullscreen_hide_pet: bool,
+    pub ocr_engine: String,
+    pub ocr_python_path: String,
+    pub ocr_script_path: String,
+    pub ocr_tesseract_path: String,
+    pub ocr_timeout_sec: u64,
+    pub ocr_languages: String,
+    pub ocr_det_model_path: String,
+    pub ocr_rec_model_path: String,
+    pub ocr_rec_keys_path: String,
+    pub ocr_threads: u32,
 }
 
 /// 获取应用配置
@@ -292,130 +502,534 @@ pub fn get_app_config() -> AppConfigResponse {
     let config = get_config();
     AppConfigResponse {
         poll_interval_ms: config.poll_interval_ms,
+        screenshot_blacklist: config.screenshot_blacklist,
+        screenshot_blacklist_titles: config.screenshot_blacklist_titles,
+        screenshot_blacklist_exes: config.screenshot_blacklist_exes,
         idle_threshold_sec: config.idle_threshold_sec,
         screenshot_enabled: config.screenshot_enabled,
         screenshot_trigger_sec: config.screenshot_trigger_sec,
         screenshot_interval_sec: config.screenshot_interval_sec,
+        screenshot_on_app_switch: config.screenshot_on_app_switch,
+        screenshot_title_patterns: config.screenshot_title_patterns,
+        screenshot_dedup_distance: config.screenshot_dedup_distance,
+        screenshot_format: config.screenshot_format,
+        screenshot_quality: config.screenshot_quality,
+        screenshot_max_width: config.screenshot_max_width,
+        screenshot_max_height: config.screenshot_max_height,
+        thumbnail_size: config.thumbnail_size,
+        screenshot_quota_mb: config.screenshot_quota_mb,
         screenshot_mode: config.screenshot_mode,
+        screenshot_monitors: config.screenshot_monitors,
         screenshot_hotkey: config.screenshot_hotkey,
+        region_capture_hotkey: config.region_capture_hotkey,
+        pause_tracking_hotkey: config.pause_tracking_hotkey,
+        quick_note_hotkey: config.quick_note_hotkey,
+        pii_redaction_enabled: config.pii_redaction_enabled,
+        pii_redaction_style: config.pii_redaction_style,
+        pii_patterns: config.pii_patterns,
+        fullscreen_pause_screenshot: config.fullscreen_pause_screenshot,
+        fullscreen_pause_ocr: config.fullscreen_pause_ocr,
+        fullscreen_hide_pet: config.fullscreen_hide_pet,
+        ocr_engine: config.ocr_engine,
+        ocr_python_path: config.ocr_python_path,
+        ocr_script_path: config.ocr_script_path,
+        ocr_tesseract_path: config.ocr_tesseract_path,
+        ocr_timeout_sec: config.ocr_timeout_sec,
+        ocr_languages: config.ocr_languages,
+        ocr_det_model_path: config.ocr_det_model_path,
+        ocr_rec_model_path: config.ocr_rec_model_path,
+        ocr_rec_keys_path: config.ocr_rec_keys_path,
+        ocr_threads: config.ocr_threads,
     }
 }
 
-/// 保存应用配置
+/// 保存应用配置（快捷键随之重新注册）
 #[tauri::command]
-pub fn save_app_config(
-    poll_interval_ms: u64,
-    idle_threshold_sec: u64,
-    screenshot_enabled: bool,
-    screenshot_trigger_sec: u32,
-    screenshot_interval_sec: u32,
-    screenshot_mode: String,
-    screenshot_hotkey: String,
-) -> std::result::Result<(), String> {
-    let mut config = get_config();
-    config.poll_interval_ms = poll_interval_ms;
-    config.idle_threshold_sec = idle_threshold_sec;
-    config.screenshot_enabled = screenshot_enabled;
-    config.screenshot_trigger_sec = screenshot_trigger_sec;
-    config.screenshot_interval_sec = screenshot_interval_sec;
-    config.screenshot_mode = screenshot_mode;
-    config.screenshot_hotkey = screenshot_hotkey;
-    set_config(config);
+pub fn save_app_config(app: AppHandle, config: AppConfigResponse) -> std::result::Result<(), String> {
+    let mut current = get_config();
+    let previous_hotkeys = super::hotkey::hotkey_bindings(&current);
+    let hide_pet_changed = current.fullscreen_hide_pet != config.fullscreen_hide_pet;
+    current.poll_interval_ms = config.poll_interval_ms;
+    current.screenshot_blacklist = config.screenshot_blacklist;
+    current.screenshot_blacklist_titles = config.screenshot_blacklist_titles;
+    current.screenshot_blacklist_exes = config.screenshot_blacklist_exes;
+    current.idle_threshold_sec = config.idle_threshold_sec;
+    current.screenshot_enabled = config.screenshot_enabled;
+    current.screenshot_trigger_sec = config.screenshot_trigger_sec;
+    current.screenshot_interval_sec = config.screenshot_interval_sec;
+    current.screenshot_on_app_switch = config.screenshot_on_app_switch;
+    current.screenshot_title_patterns = config.screenshot_title_patterns;
+    current.screenshot_dedup_distance = config.screenshot_dedup_distance;
+    current.screenshot_format = config.screenshot_format;
+    current.screenshot_quality = config.screenshot_quality;
+    current.screenshot_max_width = config.screenshot_max_width;
+    current.screenshot_max_height = config.screenshot_max_height;
+    current.thumbnail_size = config.thumbnail_size;
+    current.screenshot_quota_mb = config.screenshot_quota_mb;
+    current.screenshot_mode = config.screenshot_mode;
+    current.screenshot_monitors = config.screenshot_monitors;
+    current.screenshot_hotkey = config.screenshot_hotkey;
+    current.region_capture_hotkey = config.region_capture_hotkey;
+    current.pause_tracking_hotkey = config.pause_tracking_hotkey;
+    current.quick_note_hotkey = config.quick_note_hotkey;
+    current.pii_redaction_enabled = config.pii_redaction_enabled;
+    current.pii_redaction_style = config.pii_redaction_style;
+    current.pii_patterns = config.pii_patterns;
+    current.fullscreen_pause_screenshot = config.fullscreen_pause_screenshot;
+    current.fullscreen_pause_ocr = config.fullscreen_pause_ocr;
+    current.fullscreen_hide_pet = config.fullscreen_hide_pet;
+    current.ocr_engine = config.ocr_engine;
+    current.ocr_python_path = config.ocr_python_path;
+    current.ocr_script_path = config.ocr_script_path;
+    current.ocr_tesseract_path = config.ocr_tesseract_path;
+    current.ocr_timeout_sec = config.ocr_timeout_sec;
+    current.ocr_languages = config.ocr_languages;
+    current.ocr_det_model_path = config.ocr_det_model_path;
+    current.ocr_rec_model_path = config.ocr_rec_model_path;
+    current.ocr_rec_keys_path = config.ocr_rec_keys_path;
+    current.ocr_threads = config.ocr_threads;
+    crate::services::apply_ocr_settings(&current);
+    let hotkeys_changed = super::hotkey::hotkey_bindings(&current) != previous_hotkeys;
+    set_config(current);
+    if hotkeys_changed {
+        super::hotkey::register_hotkeys(&app);
+    }
+    // 全屏期间切换“全屏时隐藏桌宠”立即生效
+    if hide_pet_changed && is_fullscreen_active() {
+        super::deskpet::set_pet_visible(&app, !config.fullscreen_hide_pet);
+    }
     Ok(())
 }
 
 /// 截图响应
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct ScreenshotResponse {
     pub success: bool,
     pub filepath: Option<String>,
+    /// 本次截图的全部文件（每个显示器各一张时有多个，第一个同 filepath）
+    pub filepaths: Vec<String>,
     pub error: Option<String>,
+    /// 跳过原因（"blacklisted" 未截图；"duplicate" 与上一张近似，filepath 为上一张）
+    pub skipped: Option<String>,
+    /// 截图在暂存目录等待脱敏，识别完成前 filepath 不可读取
+    pub staged: bool,
+}
+
+/// 黑名单跳过原因
+const SKIP_BLACKLISTED: &str = "blacklisted";
+/// 近似重复跳过原因
+const SKIP_DUPLICATE: &str = "duplicate";
+
+/// 截图前检查黑名单
+/// 同时检查调用方给出的应用名和当前前台窗口（前者可能已过期），命中时记录跳过标记而不截图
+fn check_screenshot_blacklist(config: &AppConfig, app_name: &str, active: Option<&WindowInfo>) -> Option<ScreenshotResponse> {
+    let (title, exe_path) = active
+        .map(|w| (w.window_title.clone(), w.exe_path.clone()))
+        .unwrap_or_default();
+
+    let rule = config
+        .screenshot_blacklist_match(app_name, &title, &exe_path)
+        .or_else(|| active.and_then(|w| config.screenshot_blacklist_match(&w.app_name, &title, &exe_path)))?;
+
+    let app = active.map(|w| w.app_name.clone()).unwrap_or_else(|| app_name.to_string());
+    Some(blacklist_skipped(config, app, title, exe_path, format!("前台窗口命中截图黑名单（{}），已跳过", rule)))
+}
+
+/// 区域截图前检查区域内所有可见窗口（不考虑遮挡，被挡住的黑名单窗口也会跳过）
+fn check_area_blacklist(config: &AppConfig, area: &WindowRect) -> Option<ScreenshotResponse> {
+    WindowTracker::new().windows_in_rect(area).into_iter().find_map(|window| {
+        let rule = config.screenshot_blacklist_match(&window.app_name, &window.window_title, &window.exe_path)?;
+        Some(blacklist_skipped(
+            config,
+            window.app_name,
+            window.window_title,
+            window.exe_path,
+            format!("截图区域内的窗口命中截图黑名单（{}），已跳过", rule),
+        ))
+    })
+}
+
+/// 记录黑名单跳过标记并生成跳过结果
+fn blacklist_skipped(config: &AppConfig, app: String, title: String, exe_path: String, message: String) -> ScreenshotResponse {
+    let storage = StorageService::new(config.clone());
+    if let Err(e) = storage.append_raw_event(&RawEvent::screenshot_skipped(app, title, exe_path, SKIP_BLACKLISTED)) {
+        eprintln!("记录截图跳过标记失败: {}", e);
+    }
+
+    ScreenshotResponse {
+        success: false,
+        filepath: None,
+        filepaths: vec![],
+        error: Some(message),
+        skipped: Some(SKIP_BLACKLISTED.to_string()),
+        staged: false,
+    }
 }
 
 /// 手动截图 - 根据配置选择全屏或应用窗口模式，截图后在后台异步执行OCR
 #[tauri::command]
 pub async fn take_screenshot(app_name: String) -> ScreenshotResponse {
+    capture_screenshot(&app_name, None)
+}
+
+/// 截图并在后台执行OCR（手动截图与自动截图共用）
+///
+/// 只有自动截图（`trigger` 非空）做近似去重；用户主动截图总是保存。
+pub(crate) fn capture_screenshot(app_name: &str, trigger: Option<ScreenshotTrigger>) -> ScreenshotResponse {
+    let app_name = app_name.to_string();
     let config = get_config();
+    if is_fullscreen_active() && config.fullscreen_pause_screenshot {
+        return ScreenshotResponse {
+            success: false,
+            filepath: None,
+            filepaths: vec![],
+            error: Some("全屏模式下已暂停截图".to_string()),
+            skipped: None,
+            staged: false,
+        };
+    }
+    let active = WindowTracker::new().get_active_window().ok();
+    if let Some(skipped) = check_screenshot_blacklist(&config, &app_name, active.as_ref()) {
+        return skipped;
+    }
     let screenshot_dir = config.get_screenshots_dir();
-    let service = ScreenshotService::new(screenshot_dir);
+    // 自动截图按应用和所在显示器比较上一张，近似时不再保存
+    let service = ScreenshotService::new(screenshot_dir)
+        .with_image_settings(ImageSettings::from_config(&config))
+        .with_staging(config.pii_redaction_enabled)
+        .with_dedup(if trigger.is_some() { config.screenshot_dedup_distance } else { 0 });
     
     // 根据截图模式选择截图方式
     let capture_result = if config.screenshot_mode == "app_window" {
-        // 应用窗口模式：获取当前窗口位置并截取
-        let tracker = crate::services::WindowTracker::new();
-        match tracker.get_active_window_rect() {
-            Ok(rect) => {
-                if rect.width > 0 && rect.height > 0 {
-                    service.capture_area(&app_name, rect.x, rect.y, rect.width, rect.height)
-                } else {
-                    service.capture_full_screen(&app_name)
-                }
-            }
-            Err(_) => service.capture_full_screen(&app_name)
-        }
+        // 应用窗口模式：只截取活动窗口，失败时退回全屏
+        service
+            .capture_active_window(&app_name)
+            .or_else(|_| service.capture_full_screen(&app_name))
+            .map(|outcome| vec![outcome])
     } else {
-        // 全屏模式
-        service.capture_full_screen(&app_name)
+        // 全屏模式：按配置截取活动窗口所在显示器或所有显示器
+        match config.screenshot_monitors.as_str() {
+            "all_stitched" => service.capture_all_monitors(&app_name).map(|outcome| vec![outcome]),
+            "all_separate" => service.capture_each_monitor(&app_name),
+            _ => service.capture_full_screen(&app_name).map(|outcome| vec![outcome]),
+        }
     };
     
-    match capture_result {
-        Ok(path) => {
-            let filepath = path.to_string_lossy().to_string();
-            
+    let outcomes = match capture_result {
+        Ok(outcomes) if !outcomes.is_empty() => outcomes,
+        Ok(_) => {
+            return ScreenshotResponse {
+                success: false,
+                filepath: None,
+                filepaths: vec![],
+                error: Some("未找到屏幕".to_string()),
+                skipped: None,
+                staged: false,
+            };
+        }
+        Err(e) => {
+            return ScreenshotResponse {
+                success: false,
+                filepath: None,
+                filepaths: vec![],
+                error: Some(e),
+                skipped: None,
+                staged: false,
+            };
+        }
+    };
+
+    // 全屏时按配置跳过OCR
+    let skip_ocr = is_fullscreen_active() && config.fullscreen_pause_ocr;
+    let ocr_status = if skip_ocr { OcrStatus::Skipped } else { OcrStatus::Pending };
+    for outcome in &outcomes {
+        catalog_screenshot(&config, outcome, &app_name, active.as_ref(), ocr_status);
+        // 需要脱敏时即使暂停OCR也要识别，用于定位敏感信息
+        if let CaptureOutcome::Saved { path, .. } = outcome
+            && (!skip_ocr || config.pii_redaction_enabled)
+        {
             // 在后台异步执行OCR，不阻塞主线程
-            let filepath_clone = filepath.clone();
-            let app_name_clone = app_name.clone();
-            tokio::spawn(async move {
-                let date = chrono::Local::now().format("%Y-%m-%d").to_string();
-                let timestamp = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
-                
-                match crate::services::extract_text_from_image(&filepath_clone).await {
-                    Ok(text) => {
-                        if !text.trim().is_empty() {
-                            let record = OcrRecord {
-                                timestamp,
-                                image_path: filepath_clone.clone(),
-                                text,
-                                app_name: Some(app_name_clone),
-                            };
-                            let _ = save_ocr_to_db(&date, &record);
-                        }
-                    }
-                    Err(_) => {}
+            spawn_screenshot_ocr(path.to_string_lossy().to_string(), app_name.clone(), !skip_ocr);
+        }
+    }
+
+    let filepaths: Vec<String> = outcomes.iter().map(|o| o.path().to_string_lossy().to_string()).collect();
+    let all_duplicate = outcomes.iter().all(|o| matches!(o, CaptureOutcome::Duplicate { .. }));
+    ScreenshotResponse {
+        success: true,
+        filepath: filepaths.first().cloned(),
+        filepaths,
+        error: None,
+        skipped: all_duplicate.then(|| SKIP_DUPLICATE.to_string()),
+        staged: outcomes.iter().any(|o| matches!(o, CaptureOutcome::Saved { staged: true, .. })),
+    }
+}
+
+/// 后台识别截图文本，保存OCR记录并更新截图目录中的OCR状态
+///
+/// 启用脱敏时截图先在暂存目录，识别后按OCR框打码再落到正式路径；`store_text` 为 false 时只做脱敏。
+fn spawn_screenshot_ocr(filepath: String, app_name: String, store_text: bool) {
+    tauri::async_runtime::spawn(async move {
+        let config = get_config();
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let timestamp = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let redactor = config.pii_redaction_enabled.then(|| Redactor::from_config(&config));
+        let set_status = |status: OcrStatus| {
+            if let Err(e) = ScreenshotCatalog::open(&config, &date)
+                .and_then(|catalog| catalog.set_ocr_status(&filepath, status))
+            {
+                eprintln!("更新截图OCR状态失败: {}", e);
+            }
+        };
+
+        let image_path = Path::new(&filepath);
+        let ocr_path = match redactor {
+            Some(_) => staging_path(image_path).to_string_lossy().to_string(),
+            None => filepath.clone(),
+        };
+        let result = crate::services::recognize_image(&config, &ocr_path).await;
+
+        if let Some(redactor) = &redactor {
+            let service = ScreenshotService::new(image_path.parent().map(Path::to_path_buf).unwrap_or_default())
+                .with_image_settings(ImageSettings::from_config(&config));
+            let redacted = match &result {
+                Ok(ocr) => service.finalize_staged(image_path, |img| {
+                    redactor.redact_image(img, &ocr.lines);
+                }),
+                Err(e) => {
+                    eprintln!("截图识别失败，无法定位敏感信息: {}", e);
+                    Err(e.clone())
                 }
-            });
-            
-            ScreenshotResponse {
-                success: true,
-                filepath: Some(filepath),
-                error: None,
+            };
+            if redacted.is_err() {
+                discard_staged_screenshot(&service, image_path);
+                set_status(OcrStatus::Failed);
+                return;
             }
-        },
-        Err(e) => ScreenshotResponse {
-            success: false,
-            filepath: None,
-            error: Some(e),
-        },
+        }
+        if !store_text {
+            if redactor.is_some() {
+                set_status(OcrStatus::Skipped);
+            }
+            return;
+        }
+
+        set_status(store_ocr_result(&date, &timestamp, &filepath, &app_name, result, redactor.as_ref()));
+    });
+}
+
+/// 脱敏失败时整张涂黑保存，不让未打码的截图落盘
+fn discard_staged_screenshot(service: &ScreenshotService, image_path: &Path) {
+    if let Err(e) = service.finalize_staged(image_path, |img| {
+        *img = image::DynamicImage::new_rgb8(img.width(), img.height());
+    }) {
+        eprintln!("涂黑暂存截图失败: {}", e);
+        let _ = fs::remove_file(staging_path(image_path));
+    }
+}
+
+/// 启动时处理上次退出前未完成脱敏的暂存截图：整张涂黑并标记识别失败
+pub fn recover_staged_screenshots() {
+    let config = get_config();
+    let Ok(entries) = fs::read_dir(&config.data_dir) else {
+        return;
+    };
+    for date_dir in entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()) {
+        let Some(date) = date_dir.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+            continue;
+        };
+        let service = ScreenshotService::new(date_dir.join("screenshots"))
+            .with_image_settings(ImageSettings::from_config(&config));
+        for image_path in service.leftover_staged() {
+            eprintln!("处理未完成脱敏的截图: {}", image_path.display());
+            discard_staged_screenshot(&service, &image_path);
+            if let Err(e) = ScreenshotCatalog::open(&config, &date)
+                .and_then(|catalog| catalog.set_ocr_status(&image_path.to_string_lossy(), OcrStatus::Failed))
+            {
+                eprintln!("更新截图OCR状态失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 保存截图的识别结果（启用脱敏时先脱敏文本），返回截图应标记的OCR状态
+pub(crate) fn store_ocr_result(
+    date: &str,
+    timestamp: &str,
+    image_path: &str,
+    app_name: &str,
+    result: Result<OcrResult, String>,
+    redactor: Option<&Redactor>,
+) -> OcrStatus {
+    match result {
+        Ok(ocr) => {
+            let (text, lines) = match redactor {
+                Some(redactor) => {
+                    let lines = ocr
+                        .lines
+                        .into_iter()
+                        .map(|line| OcrLine { text: redactor.redact_text(&line.text), ..line })
+                        .collect();
+                    (redactor.redact_text(&ocr.text), lines)
+                }
+                None => (ocr.text, ocr.lines),
+            };
+            if !text.trim().is_empty() {
+                let record = OcrRecord {
+                    id: 0,
+                    timestamp: timestamp.to_string(),
+                    image_path: image_path.to_string(),
+                    text,
+                    app_name: Some(app_name.to_string()),
+                    lines,
+                    base_id: None,
+                };
+                match save_ocr_to_db(date, &record) {
+                    Ok(()) => OcrStatus::Done,
+                    Err(_) => OcrStatus::Failed,
+                }
+            } else {
+                OcrStatus::Empty
+            }
+        }
+        Err(_) => OcrStatus::Failed,
+    }
+}
+
+/// 将截图登记到当日截图目录；近似截图只延长上一张的时间范围
+fn catalog_screenshot(
+    config: &AppConfig,
+    outcome: &CaptureOutcome,
+    app_name: &str,
+    active: Option<&WindowInfo>,
+    ocr_status: OcrStatus,
+) {
+    let now = chrono::Local::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    let timestamp = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    let result = ScreenshotCatalog::open(config, &date).and_then(|catalog| match outcome {
+        CaptureOutcome::Saved { path, hash, width, height, monitor, staged } => catalog
+            .insert(&ScreenshotRecord {
+                id: 0,
+                timestamp: timestamp.clone(),
+                end_timestamp: timestamp,
+                image_path: path.to_string_lossy().to_string(),
+                app_name: app_name.to_string(),
+                window_title: active.map(|w| w.window_title.clone()),
+                exe_path: active.map(|w| w.exe_path.clone()).filter(|p| !p.is_empty()),
+                monitor: monitor.clone().or_else(|| active.and_then(|w| w.monitor.clone())),
+                width: *width,
+                height: *height,
+                hash: Some(format!("{:016x}", hash)),
+                ocr_status: if *staged { OcrStatus::Redacting } else { ocr_status },
+                segment_id: CURRENT_SEGMENT.lock().unwrap().clone(),
+                thumbnail_path: None,
+                pinned: false,
+                note: None,
+            })
+            .map(|_| ()),
+        CaptureOutcome::Duplicate { previous, .. } => catalog.extend(&previous.to_string_lossy(), &timestamp),
+    });
+    if let Err(e) = result {
+        eprintln!("登记截图失败: {}", e);
+    }
+}
+
+/// 自动截图调度线程是否已启动
+static SCHEDULER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 自动截图结果推送
+#[derive(Serialize, Clone)]
+pub struct AutoScreenshotPayload {
+    pub trigger: ScreenshotTrigger,
+    pub app_name: String,
+    pub filepath: Option<String>,
+    pub error: Option<String>,
+    pub skipped: Option<String>,
+    pub staged: bool,
+}
+
+/// 启动自动截图调度线程
+/// 在后端按秒检查调度器，不依赖前端定时器，主窗口隐藏时照常截图；结果通过 auto-screenshot 事件推送
+pub fn start_screenshot_scheduler(app: AppHandle) {
+    if SCHEDULER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
     }
+
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            if is_tracking_paused() {
+                continue;
+            }
+
+            let config = get_config();
+            let tracker = app.state::<InputTracker>();
+            // 输入监听未运行时无法判断空闲，视为活跃
+            let idle_seconds = if tracker.is_listening() { tracker.idle_seconds() } else { 0 };
+            let Some((trigger, app_name)) = app.state::<ScreenshotScheduler>().poll(&config, idle_seconds) else {
+                continue;
+            };
+
+            let result = capture_screenshot(&app_name, Some(trigger));
+            let _ = app.emit(
+                "auto-screenshot",
+                AutoScreenshotPayload {
+                    trigger,
+                    app_name,
+                    filepath: result.filepath,
+                    error: result.error,
+                    skipped: result.skipped,
+                    staged: result.staged,
+                },
+            );
+        }
+    });
 }
 
 /// 截取指定区域
 #[tauri::command]
 pub fn take_screenshot_area(app_name: String, x: i32, y: i32, width: u32, height: u32) -> ScreenshotResponse {
     let config = get_config();
+    let active = WindowTracker::new().get_active_window().ok();
+    if let Some(skipped) = check_screenshot_blacklist(&config, &app_name, active.as_ref()) {
+        return skipped;
+    }
+    if let Some(skipped) = check_area_blacklist(&config, &WindowRect { x, y, width, height }) {
+        return skipped;
+    }
     let screenshot_dir = config.get_screenshots_dir();
-    let service = ScreenshotService::new(screenshot_dir);
+    let service = ScreenshotService::new(screenshot_dir)
+        .with_image_settings(ImageSettings::from_config(&config))
+        .with_staging(config.pii_redaction_enabled);
     
     match service.capture_area(&app_name, x, y, width, height) {
-        Ok(path) => ScreenshotResponse {
-            success: true,
-            filepath: Some(path.to_string_lossy().to_string()),
-            error: None,
-        },
+        Ok(outcome) => {
+            catalog_screenshot(&config, &outcome, &app_name, active.as_ref(), OcrStatus::Pending);
+            // 暂存的截图需要识别后才能脱敏落盘
+            if let CaptureOutcome::Saved { path, .. } = &outcome
+                && config.pii_redaction_enabled
+            {
+                spawn_screenshot_ocr(path.to_string_lossy().to_string(), app_name.clone(), true);
+            }
+            let filepath = outcome.path().to_string_lossy().to_string();
+            ScreenshotResponse {
+                success: true,
+                filepath: Some(filepath.clone()),
+                filepaths: vec![filepath],
+                error: None,
+                skipped: None,
+                staged: matches!(outcome, CaptureOutcome::Saved { staged: true, .. }),
+            }
+        }
         Err(e) => ScreenshotResponse {
             success: false,
             filepath: None,
+            filepaths: vec![],
             error: Some(e),
+            skipped: None,
+            staged: false,
         },
     }
 }
@@ -455,32 +1069,62 @@ pub fn get_icon_by_app_name(app_name: String) -> Option<String> {
     None
 }
 
-/// 清理缓存（删除所有日期的截图文件）
+/// 清理缓存（删除所有日期的截图文件，收藏的截图保留）
 #[tauri::command]
 pub fn clear_cache() -> std::result::Result<(), String> {
     let config = get_config();
-    let data_dir = &config.data_dir;
-    
-    if !data_dir.exists() {
-        return Ok(());
+    crate::services::clear_unpinned_screenshots(&config)
+        .map(|_| ())
+        .map_err(|e| format!("删除截图失败: {}", e))
+}
+
+/// 收藏或取消收藏截图（可附备注），收藏的截图不会被配额清理和清理缓存删除
+#[tauri::command]
+pub fn pin_screenshot(date: String, image_path: String, pinned: bool, note: Option<String>) -> Result<(), String> {
+    let config = get_config();
+    let catalog = ScreenshotCatalog::open(&config, &date).map_err(|e| format!("打开截图目录失败: {}", e))?;
+    let found = catalog
+        .set_pinned(&image_path, pinned, note.as_deref())
+        .map_err(|e| format!("收藏截图失败: {}", e))?;
+    if !found {
+        return Err("截图未登记".to_string());
     }
-    
-    // 遍历所有日期目录，删除其中的screenshots子目录
-    let entries = fs::read_dir(data_dir)
-        .map_err(|e| format!("读取数据目录失败: {}", e))?;
-    
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            let screenshots_dir = path.join("screenshots");
-            if screenshots_dir.exists() && screenshots_dir.is_dir() {
-                fs::remove_dir_all(&screenshots_dir)
-                    .map_err(|e| format!("删除截图目录失败: {}", e))?;
+    Ok(())
+}
+
+/// 获取截图占用空间和配额
+#[tauri::command]
+pub fn get_screenshot_usage() -> Result<ScreenshotUsage, String> {
+    crate::services::screenshot_usage(&get_config()).map_err(|e| format!("统计截图占用失败: {}", e))
+}
+
+/// 立即按配额清理截图
+#[tauri::command]
+pub fn apply_screenshot_quota() -> Result<EvictionReport, String> {
+    crate::services::enforce_screenshot_quota(&get_config()).map_err(|e| format!("清理截图失败: {}", e))
+}
+
+/// 截图配额检查线程是否已启动
+static QUOTA_WATCH_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 启动截图配额检查线程（启动时及每10分钟检查一次）
+pub fn start_screenshot_quota_watch() {
+    if QUOTA_WATCH_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    std::thread::spawn(|| {
+        loop {
+            match crate::services::enforce_screenshot_quota(&get_config()) {
+                Ok(report) if report.deleted > 0 => {
+                    eprintln!("截图超出配额，已删除 {} 张，释放 {} 字节", report.deleted, report.freed_bytes);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("截图配额检查失败: {}", e),
             }
+            std::thread::sleep(Duration::from_secs(600));
         }
-    }
-    
-    Ok(())
+    });
 }
 
 /// 打开文件夹
@@ -520,141 +1164,182 @@ pub fn get_screenshots_by_date(date: String) -> Vec<String> {
         return vec![];
     }
     
-    fs::read_dir(&data_dir)
+    let mut paths: Vec<String> = fs::read_dir(&data_dir)
         .map(|entries| {
             entries
                 .filter_map(|e| e.ok())
                 .map(|e| e.path())
-                .filter(|p| {
-                    p.extension()
-                        .map(|ext| ext == "jpg" || ext == "png")
-                        .unwrap_or(false)
-                })
+                .filter(|p| is_screenshot_file(p))
                 .map(|p| p.to_string_lossy().to_string())
                 .collect()
         })
-        .unwrap_or_default()
+        .unwrap_or_default();
+    // 文件名以 HH-MM-SS 开头，按名称排序即按时间排序
+    paths.sort();
+    paths
+}
+
+/// 本次运行中已补录过旧截图的截图目录（新截图在截取时登记，每个目录只需补录一次）
+static IMPORTED_SCREENSHOT_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 分页查询指定日期的截图目录（支持按应用、标题、OCR状态和时间段筛选）
+/// 本次运行首次查询某天时会补录目录中尚未登记的旧截图
+#[tauri::command]
+pub fn list_screenshots(date: String, query: Option<ScreenshotQuery>) -> Result<ScreenshotPage, String> {
+    let config = get_config();
+    let catalog = ScreenshotCatalog::open(&config, &date).map_err(|e| format!("打开截图目录失败: {}", e))?;
+    let screenshots_dir = config.get_date_dir(&date).join("screenshots");
+    if !IMPORTED_SCREENSHOT_DIRS.lock().unwrap().contains(&screenshots_dir) {
+        let ocr = OcrRepository::open(&config, &date).map_err(|e| format!("打开OCR记录失败: {}", e))?;
+        catalog
+            .import_untracked(&date, &screenshots_dir, &ocr)
+            .map_err(|e| format!("补录截图失败: {}", e))?;
+        IMPORTED_SCREENSHOT_DIRS.lock().unwrap().push(screenshots_dir);
+    }
+    catalog
+        .query(&query.unwrap_or_default())
+        .map_err(|e| format!("查询截图失败: {}", e))
+}
+
+/// 获取指定日期截图中出现过的应用（用于筛选）
+#[tauri::command]
+pub fn get_screenshot_apps(date: String) -> Result<Vec<String>, String> {
+    let config = get_config();
+    let catalog = ScreenshotCatalog::open(&config, &date).map_err(|e| format!("打开截图目录失败: {}", e))?;
+    catalog.apps().map_err(|e| format!("查询截图应用失败: {}", e))
+}
+
+/// 批量重新编码结果
+#[derive(Debug, Serialize)]
+pub struct RecompressReport {
+    /// 处理的截图数
+    pub processed: u32,
+    /// 实际替换（变小）的截图数
+    pub replaced: u32,
+    pub failed: u32,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// 按当前格式、质量和尺寸设置重新编码指定日期的截图，并补齐缩略图
+/// 新文件更小时才替换原图，截图目录和OCR记录中的路径同步更新
+#[tauri::command]
+pub async fn recompress_screenshots(date: String) -> Result<RecompressReport, String> {
+    tauri::async_runtime::spawn_blocking(move || recompress_date(&date))
+        .await
+        .map_err(|e| format!("重新编码任务失败: {}", e))?
+}
+
+fn recompress_date(date: &str) -> Result<RecompressReport, String> {
+    let config = get_config();
+    let screenshots_dir = config.get_date_dir(date).join("screenshots");
+    let catalog = ScreenshotCatalog::open(&config, date).map_err(|e| format!("打开截图目录失败: {}", e))?;
+    let ocr = OcrRepository::open(&config, date).map_err(|e| format!("打开OCR记录失败: {}", e))?;
+    // 先补录旧截图，保证替换后的路径能同步到目录
+    catalog
+        .import_untracked(date, &screenshots_dir, &ocr)
+        .map_err(|e| format!("补录截图失败: {}", e))?;
+    let service = ScreenshotService::new(screenshots_dir.clone()).with_image_settings(ImageSettings::from_config(&config));
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(&screenshots_dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| is_screenshot_file(p)).collect())
+        .unwrap_or_default();
+    paths.sort();
+
+    let mut report = RecompressReport { processed: 0, replaced: 0, failed: 0, bytes_before: 0, bytes_after: 0 };
+    for path in paths {
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        report.processed += 1;
+        report.bytes_before += size;
+        match service.recompress(&path) {
+            Ok(Some(result)) => {
+                report.replaced += 1;
+                report.bytes_after += result.new_bytes;
+                let old_path = result.old_path.to_string_lossy();
+                let new_path = result.new_path.to_string_lossy();
+                if let Err(e) = catalog.relocate(&old_path, &new_path, result.width, result.height) {
+                    eprintln!("更新截图目录失败: {}", e);
+                }
+                if let Err(e) = ocr.relocate(&old_path, &new_path) {
+                    eprintln!("更新OCR记录失败: {}", e);
+                }
+            }
+            Ok(None) => report.bytes_after += size,
+            Err(e) => {
+                eprintln!("重新编码 {} 失败: {}", path.display(), e);
+                report.failed += 1;
+                report.bytes_after += size;
+            }
+        }
+    }
+    Ok(report)
 }
 
 /// OCR识别图片文本
 #[tauri::command]
 pub async fn ocr_image(image_path: String) -> Result<String, String> {
-    crate::services::extract_text_from_image(&image_path).await
+    let config = get_config();
+    let text = crate::services::extract_text_from_image(&config, &image_path).await?;
+    if config.pii_redaction_enabled {
+        return Ok(Redactor::from_config(&config).redact_text(&text));
+    }
+    Ok(text)
+}
+
+/// 获取OCR队列状态
+#[tauri::command]
+pub fn get_ocr_queue_status() -> OcrQueueStatus {
+    crate::services::ocr_queue_status()
+}
+
+/// 取消所有排队中和正在执行的OCR任务，返回取消的数量
+#[tauri::command]
+pub fn cancel_ocr_queue() -> usize {
+    crate::services::cancel_ocr_jobs()
+}
+
+/// OCR队列状态变化时推送 ocr-queue-status 事件
+pub fn start_ocr_queue_events(app: AppHandle) {
+    crate::services::set_ocr_status_listener(move |status| {
+        let _ = app.emit("ocr-queue-status", status);
+    });
 }
 
 /// 获取指定日期的OCR数据
+///
+/// 默认还原每条记录的完整文本；`delta_only` 为 true 时增量记录只返回相对上一张新增的行，没有新增内容的记录不返回。
 #[tauri::command]
-pub fn get_ocr_data_by_date(date: String) -> Result<Vec<OcrRecord>, String> {
+pub fn get_ocr_data_by_date(date: String, min_confidence: Option<f32>, delta_only: Option<bool>) -> Result<Vec<OcrRecord>, String> {
     let config = get_config();
-    let db_path = config.data_dir.join(&date).join("events.db");
-    
-    if !db_path.exists() {
+    let Some(repository) = OcrRepository::open_existing(&config, &date)
+        .map_err(|e| format!("打开OCR记录失败: {}", e))?
+    else {
         return Ok(vec![]);
-    }
-    
-    let conn = rusqlite::Connection::open(&db_path)
-        .map_err(|e| format!("打开数据库失败: {}", e))?;
-    
-    // 确保ocr_records表存在
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS ocr_records (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp TEXT NOT NULL,
-            image_path TEXT NOT NULL,
-            text TEXT NOT NULL,
-            app_name TEXT
-        )",
-        [],
-    ).map_err(|e| format!("创建表失败: {}", e))?;
-    
-    let mut stmt = conn.prepare(
-        "SELECT timestamp, image_path, text, app_name FROM ocr_records ORDER BY timestamp"
-    ).map_err(|e| format!("准备查询失败: {}", e))?;
-    
-    let records = stmt.query_map([], |row| {
-        Ok(OcrRecord {
-            timestamp: row.get(0)?,
-            image_path: row.get(1)?,
-            text: row.get(2)?,
-            app_name: row.get(3)?,
+    };
+
+    let records = repository
+        .all(delta_only.unwrap_or(false))
+        .map_err(|e| format!("查询失败: {}", e))?
+        .into_iter()
+        .map(|record| match min_confidence {
+            Some(min) => record.drop_low_confidence(min),
+            None => record,
         })
-    }).map_err(|e| format!("查询失败: {}", e))?
-    .filter_map(|r| r.ok())
-    .collect();
-    
+        .filter(|record| !record.text.trim().is_empty())
+        .collect();
     Ok(records)
 }
 
 /// 保存OCR记录
 #[tauri::command]
 pub fn save_ocr_record(date: String, record: OcrRecord) -> Result<(), String> {
-    let config = get_config();
-    let data_dir = config.data_dir.join(&date);
-    
-    fs::create_dir_all(&data_dir)
-        .map_err(|e| format!("创建目录失败: {}", e))?;
-    
-    let db_path = data_dir.join("events.db");
-    
-    let conn = rusqlite::Connection::open(&db_path)
-        .map_err(|e| format!("打开数据库失败: {}", e))?;
-    
-    // 确保ocr_records表存在
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS ocr_records (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp TEXT NOT NULL,
-            image_path TEXT NOT NULL,
-            text TEXT NOT NULL,
-            app_name TEXT
-        )",
-        [],
-    ).map_err(|e| format!("创建表失败: {}", e))?;
-    
-    conn.execute(
-        "INSERT INTO ocr_records (timestamp, image_path, text, app_name) VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![record.timestamp, record.image_path, record.text, record.app_name],
-    ).map_err(|e| format!("插入记录失败: {}", e))?;
-    
-    Ok(())
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-pub struct OcrRecord {
-    pub timestamp: String,
-    pub image_path: String,
-    pub text: String,
-    pub app_name: Option<String>,
+    save_ocr_to_db(&date, &record)
 }
 
 /// 内部函数：保存OCR记录到数据库
 fn save_ocr_to_db(date: &str, record: &OcrRecord) -> Result<(), String> {
-    let config = get_config();
-    let data_dir = config.data_dir.join(date);
-    
-    fs::create_dir_all(&data_dir)
-        .map_err(|e| format!("创建目录失败: {}", e))?;
-    
-    let db_path = data_dir.join("events.db");
-    
-    let conn = rusqlite::Connection::open(&db_path)
-        .map_err(|e| format!("打开数据库失败: {}", e))?;
-    
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS ocr_records (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp TEXT NOT NULL,
-            image_path TEXT NOT NULL,
-            text TEXT NOT NULL,
-            app_name TEXT
-        )",
-        [],
-    ).map_err(|e| format!("创建表失败: {}", e))?;
-    
-    conn.execute(
-        "INSERT INTO ocr_records (timestamp, image_path, text, app_name) VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![record.timestamp, record.image_path, record.text, record.app_name],
-    ).map_err(|e| format!("插入记录失败: {}", e))?;
-    
-    Ok(())
+    OcrRepository::open(&get_config(), date)
+        .and_then(|repository| repository.insert(record))
+        .map(|_| ())
+        .map_err(|e| format!("保存OCR记录失败: {}", e))
 }
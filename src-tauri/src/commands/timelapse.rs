@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter};
+use crate::services::{TimelapseOptions, TimelapseProgress};
+use super::system::get_config;
+
+static IS_EXPORTING: AtomicBool = AtomicBool::new(false);
+
+/// 是否正在导出延时视频
+#[tauri::command]
+pub fn is_timelapse_exporting() -> bool {
+    IS_EXPORTING.load(Ordering::SeqCst)
+}
+
+/// 导出指定日期的截图延时动图/视频，返回输出文件路径
+/// 进度通过 timelapse-progress 事件推送
+#[tauri::command]
+pub async fn export_timelapse(app: AppHandle, date: String, options: Option<TimelapseOptions>) -> Result<String, String> {
+    if IS_EXPORTING.swap(true, Ordering::SeqCst) {
+        return Err("正在导出中，请稍候".to_string());
+    }
+
+    let options = options.unwrap_or_default();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::services::export_timelapse(&get_config(), &date, &options, |progress: TimelapseProgress| {
+            let _ = app.emit("timelapse-progress", &progress);
+        })
+    })
+    .await
+    .map_err(|e| format!("导出任务失败: {}", e));
+
+    IS_EXPORTING.store(false, Ordering::SeqCst);
+    result?.map(|path| path.to_string_lossy().to_string())
+}
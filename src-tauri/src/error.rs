@@ -17,9 +17,15 @@ pub enum AppError {
     #[error("Config error: {0}")]
     Config(String),
 
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+
     #[error("Screenshot error: {0}")]
     Screenshot(String),
 
+    #[error("Unsupported schema version for {table}: {found} (supported up to {supported})")]
+    SchemaVersion { table: &'static str, found: i64, supported: i64 },
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
@@ -8,11 +8,17 @@ use commands::ai::*;
 use commands::diary::*;
 use commands::update::*;
 use commands::deskpet::*;
+use commands::timelapse::*;
+use commands::hotkey::*;
+use commands::region::*;
+use commands::ocr_backfill::*;
+use commands::chat::*;
 use tauri::{
     Manager,
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
+use services::{InputTracker, ScreenshotScheduler, shutdown_ocr_queue};
 use tauri_plugin_autostart::MacosLauncher;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -21,6 +27,9 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec!["--autostart"])))
+        .plugin(tauri_plugin_global_shortcut::Builder::new().with_handler(handle_hotkey).build())
+        .manage(InputTracker::new())
+        .manage(ScreenshotScheduler::new())
         .setup(|app| {
             // 创建托盘菜单
             let show_item = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
@@ -56,6 +65,18 @@ pub fn run() {
                     }
                 })
                 .build(app)?;
+
+            // 自动截图在后端调度，不受主窗口隐藏影响
+            if let Err(e) = start_focus_watch(app.handle().clone()) {
+                eprintln!("启动焦点监听失败: {}", e);
+            }
+            // 上次退出时未完成脱敏的暂存截图不能原样落盘
+            recover_staged_screenshots();
+            start_screenshot_scheduler(app.handle().clone());
+            start_screenshot_quota_watch();
+            start_ocr_queue_events(app.handle().clone());
+            resume_unfinished_ocr_backfill(app.handle());
+            register_hotkeys(app.handle());
             
             Ok(())
         })
@@ -69,6 +90,8 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_active_window,
             record_app_focus,
+            start_focus_watch,
+            get_current_focus,
             get_data_dir,
             set_data_dir,
             init_today_storage,
@@ -79,9 +102,11 @@ pub fn run() {
             record_idle_event,
             get_today_events_grouped,
             get_events_grouped_by_date,
+            get_daily_summary,
             get_today_events,
             get_icon_for_app,
             start_input_listening,
+            stop_input_listening,
             get_input_stats,
             get_idle_seconds,
             is_input_listening,
@@ -91,6 +116,20 @@ pub fn run() {
             take_screenshot_area,
             get_today_screenshots,
             get_screenshots_by_date,
+            list_screenshots,
+            get_screenshot_apps,
+            recompress_screenshots,
+            export_timelapse,
+            is_timelapse_exporting,
+            pin_screenshot,
+            get_screenshot_usage,
+            apply_screenshot_quota,
+            is_tracking_paused,
+            set_tracking_paused,
+            get_hotkey_status,
+            start_region_capture,
+            finish_region_capture,
+            cancel_region_capture,
             get_icon_by_app_name,
             open_folder,
             clear_cache,
@@ -99,8 +138,13 @@ pub fn run() {
             generate_diary,
             ai_chat,
             ai_chat_stream,
-            save_chat_history,
-            load_chat_history,
+            create_chat_session,
+            list_chat_sessions,
+            get_chat_session,
+            rename_chat_session,
+            delete_chat_session,
+            export_chat_session,
+            send_chat_message,
             save_notes,
             load_notes,
             get_diary_state,
@@ -113,6 +157,12 @@ pub fn run() {
             ocr_image,
             get_ocr_data_by_date,
             save_ocr_record,
+            get_ocr_queue_status,
+            cancel_ocr_queue,
+            start_ocr_backfill,
+            resume_ocr_backfill,
+            stop_ocr_backfill,
+            get_ocr_backfill_progress,
             check_for_update,
             set_update_preference,
             get_current_version,
@@ -124,6 +174,12 @@ pub fn run() {
             close_pet_window,
             set_pet_ignore_cursor
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            // 退出时结束OCR常驻进程
+            if let tauri::RunEvent::Exit = event {
+                shutdown_ocr_queue();
+            }
+        });
 }
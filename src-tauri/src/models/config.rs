@@ -1,13 +1,73 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// 敏感信息识别规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiPattern {
+    /// 规则名称，也用作OCR文本中的替换标记
+    pub name: String,
+    /// 正则表达式
+    pub pattern: String,
+}
+
+impl PiiPattern {
+    fn new(name: &str, pattern: &str) -> Self {
+        Self { name: name.to_string(), pattern: pattern.to_string() }
+    }
+}
+
+/// 编译后的黑名单标题规则
+///
+/// 随配置一起克隆（共享同一份缓存），按规则原文比对，规则修改后自动重新编译。
+#[derive(Debug, Clone, Default)]
+pub struct TitleRuleCache(Arc<Mutex<Option<CompiledTitleRules>>>);
+
+/// 每条规则原文及其编译结果（无效的规则已剔除）
+type TitleRules = Arc<Vec<(String, Regex)>>;
+/// 编译时使用的全部规则原文，以及编译结果
+type CompiledTitleRules = (Vec<String>, TitleRules);
+
+impl TitleRuleCache {
+    fn get(&self, patterns: &[String]) -> TitleRules {
+        let mut cached = self.0.lock().unwrap();
+        if let Some((source, rules)) = cached.as_ref()
+            && source.as_slice() == patterns
+        {
+            return rules.clone();
+        }
+        let rules: TitleRules = Arc::new(
+            patterns
+                .iter()
+                .filter_map(|pattern| match Regex::new(pattern) {
+                    Ok(re) => Some((pattern.clone(), re)),
+                    Err(e) => {
+                        eprintln!("黑名单标题规则无效 {}: {}", pattern, e);
+                        None
+                    }
+                })
+                .collect(),
+        );
+        *cached = Some((patterns.to_vec(), rules.clone()));
+        rules
+    }
+}
 
 /// 应用配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
     /// 数据存储根目录
     pub data_dir: PathBuf,
     /// 截图黑名单应用
     pub screenshot_blacklist: Vec<String>,
+    /// 截图黑名单窗口标题（正则）
+    pub screenshot_blacklist_titles: Vec<String>,
+    #[serde(skip)]
+    title_rule_cache: TitleRuleCache,
+    /// 截图黑名单程序路径（包含匹配，不区分大小写）
+    pub screenshot_blacklist_exes: Vec<String>,
     /// 空闲判定阈值（秒）
     pub idle_threshold_sec: u64,
     /// 事件采集间隔（毫秒）
@@ -18,10 +78,68 @@ pub struct AppConfig {
     pub screenshot_trigger_sec: u32,
     /// 自动截图间隔（秒）
     pub screenshot_interval_sec: u32,
+    /// 切换应用时立即截图
+    pub screenshot_on_app_switch: bool,
+    /// 窗口标题匹配任一正则时立即截图
+    pub screenshot_title_patterns: Vec<String>,
+    /// 近似截图去重阈值：与上一张的感知哈希距离小于该值时不保存（0=关闭）
+    pub screenshot_dedup_distance: u32,
+    /// 截图保存格式：jpeg / webp（无损）/ png
+    pub screenshot_format: String,
+    /// JPEG质量（1-100），WebP（无损）和PNG忽略此项
+    pub screenshot_quality: u8,
+    /// 截图最大宽度（超出时等比缩小，0=不限制）
+    pub screenshot_max_width: u32,
+    /// 截图最大高度（超出时等比缩小，0=不限制）
+    pub screenshot_max_height: u32,
+    /// 缩略图最长边（像素，0=不生成）
+    pub thumbnail_size: u32,
+    /// 截图占用空间上限（MB，0=不限制），超出时从最早的截图开始删除，收藏的除外
+    pub screenshot_quota_mb: u64,
     /// 截图模式：app_window=应用窗口, full_screen=全屏
     pub screenshot_mode: String,
-    /// 手动截图快捷键
+    /// 全屏模式的截图范围：active=活动窗口所在显示器, all_stitched=所有显示器拼接为一张, all_separate=每个显示器各一张
+    pub screenshot_monitors: String,
+    /// 手动截图快捷键（全局，空字符串表示不启用，下同）
     pub screenshot_hotkey: String,
+    /// 框选区域截图快捷键
+    pub region_capture_hotkey: String,
+    /// 暂停/恢复记录快捷键
+    pub pause_tracking_hotkey: String,
+    /// 快速笔记快捷键
+    pub quick_note_hotkey: String,
+    /// 按OCR结果对截图中的敏感信息打码，并替换OCR文本中的对应内容
+    pub pii_redaction_enabled: bool,
+    /// 打码方式：blur=模糊, black=涂黑
+    pub pii_redaction_style: String,
+    /// 敏感信息识别规则
+    pub pii_patterns: Vec<PiiPattern>,
+    /// 全屏时暂停自动截图
+    pub fullscreen_pause_screenshot: bool,
+    /// 全屏时暂停OCR
+    pub fullscreen_pause_ocr: bool,
+    /// 全屏时隐藏桌宠
+    pub fullscreen_hide_pet: bool,
+    /// OCR引擎：rapidocr / tesseract / mock
+    pub ocr_engine: String,
+    /// RapidOCR 使用的 Python 解释器（不在 PATH 中时填写完整路径）
+    pub ocr_python_path: String,
+    /// RapidOCR 服务脚本路径（空为内置脚本）
+    pub ocr_script_path: String,
+    /// tesseract 可执行文件路径
+    pub ocr_tesseract_path: String,
+    /// 单张图片的识别超时（秒），超时后结束识别进程并重试
+    pub ocr_timeout_sec: u64,
+    /// 识别语言（tesseract 语言包，多个用 + 连接）；RapidOCR 的语言由识别模型决定
+    pub ocr_languages: String,
+    /// RapidOCR 文字检测模型路径（空为内置模型）
+    pub ocr_det_model_path: String,
+    /// RapidOCR 文字识别模型路径（空为内置模型）
+    pub ocr_rec_model_path: String,
+    /// 识别模型对应的字典文件（更换识别模型时需要）
+    pub ocr_rec_keys_path: String,
+    /// 识别使用的线程数
+    pub ocr_threads: u32,
 }
 
 impl Default for AppConfig {
@@ -37,13 +155,57 @@ impl Default for AppConfig {
                 "Bitwarden".to_string(),
                 "KeePass".to_string(),
             ],
+            screenshot_blacklist_titles: vec![],
+            title_rule_cache: TitleRuleCache::default(),
+            screenshot_blacklist_exes: vec![],
             idle_threshold_sec: 300,        // 5分钟
             poll_interval_ms: 1000,         // 1秒
             screenshot_enabled: false,      // 默认关闭
             screenshot_trigger_sec: 30,     // 停留30秒后截图
             screenshot_interval_sec: 60,    // 每60秒截图一次
+            screenshot_on_app_switch: false,
+            screenshot_title_patterns: vec![],
+            screenshot_dedup_distance: 6,
+            screenshot_format: "jpeg".to_string(),
+            screenshot_quality: 70,
+            screenshot_max_width: 0,
+            screenshot_max_height: 0,
+            thumbnail_size: 320,
+            screenshot_quota_mb: 2048,
             screenshot_mode: "full_screen".to_string(),
+            screenshot_monitors: "active".to_string(),
             screenshot_hotkey: "Alt+]".to_string(),
+            region_capture_hotkey: "Alt+[".to_string(),
+            pause_tracking_hotkey: "Alt+Shift+P".to_string(),
+            quick_note_hotkey: "Alt+Shift+N".to_string(),
+            pii_redaction_enabled: false,
+            pii_redaction_style: "blur".to_string(),
+            pii_patterns: vec![
+                PiiPattern::new("邮箱", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+                PiiPattern::new(
+                    "身份证号",
+                    r"[1-9]\d{5}(?:19|20)\d{2}(?:0[1-9]|1[0-2])(?:0[1-9]|[12]\d|3[01])\d{3}[\dXx]",
+                ),
+                PiiPattern::new("手机号", r"(?:\+?86[- ]?)?1[3-9]\d{9}"),
+                PiiPattern::new("银行卡号", r"\d{4}[ -]?\d{4}[ -]?\d{4}[ -]?\d{4}(?:\d{3})?"),
+                PiiPattern::new(
+                    "密钥",
+                    r"sk-[A-Za-z0-9_-]{16,}|gh[pousr]_[A-Za-z0-9]{20,}|AKIA[0-9A-Z]{16}|eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,}",
+                ),
+            ],
+            fullscreen_pause_screenshot: true,
+            fullscreen_pause_ocr: true,
+            fullscreen_hide_pet: true,
+            ocr_engine: "rapidocr".to_string(),
+            ocr_python_path: "python".to_string(),
+            ocr_script_path: String::new(),
+            ocr_tesseract_path: "tesseract".to_string(),
+            ocr_timeout_sec: 30,
+            ocr_languages: "chi_sim+eng".to_string(),
+            ocr_det_model_path: String::new(),
+            ocr_rec_model_path: String::new(),
+            ocr_rec_keys_path: String::new(),
+            ocr_threads: 1,
         }
     }
 }
@@ -118,4 +280,67 @@ impl AppConfig {
             .iter()
             .any(|b| app.to_lowercase().contains(&b.to_lowercase()))
     }
+
+    /// 按应用名、窗口标题和程序路径检查截图黑名单，返回命中的规则
+    pub fn screenshot_blacklist_match(&self, app: &str, window_title: &str, exe_path: &str) -> Option<String> {
+        if let Some(rule) = self
+            .screenshot_blacklist
+            .iter()
+            .find(|b| !app.is_empty() && app.to_lowercase().contains(&b.to_lowercase()))
+        {
+            return Some(format!("应用 {}", rule));
+        }
+
+        if !window_title.is_empty()
+            && let Some((pattern, _)) = self
+                .title_rule_cache
+                .get(&self.screenshot_blacklist_titles)
+                .iter()
+                .find(|(_, re)| re.is_match(window_title))
+        {
+            return Some(format!("标题 {}", pattern));
+        }
+
+        let exe_lower = exe_path.to_lowercase();
+        self.screenshot_blacklist_exes
+            .iter()
+            .find(|b| !exe_path.is_empty() && !b.is_empty() && exe_lower.contains(&b.to_lowercase()))
+            .map(|rule| format!("程序 {}", rule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blacklist_matches_app_title_and_exe() {
+        let mut config = AppConfig {
+            screenshot_blacklist: vec!["KeePass".to_string()],
+            screenshot_blacklist_titles: vec!["(?i)private browsing".to_string(), "(unclosed".to_string()],
+            screenshot_blacklist_exes: vec!["/opt/secret/".to_string()],
+            ..AppConfig::default()
+        };
+
+        assert_eq!(config.screenshot_blacklist_match("keepassxc", "", ""), Some("应用 KeePass".to_string()));
+        assert_eq!(
+            config.screenshot_blacklist_match("firefox", "Private Browsing - Mozilla", ""),
+            Some("标题 (?i)private browsing".to_string())
+        );
+        assert_eq!(config.screenshot_blacklist_match("tool", "", "/OPT/Secret/tool"), Some("程序 /opt/secret/".to_string()));
+        // 无效的正则被忽略，不影响其他规则
+        assert_eq!(config.screenshot_blacklist_match("firefox", "(unclosed", ""), None);
+
+        // 修改规则后缓存失效（克隆的配置共享缓存）
+        let cloned = config.clone();
+        config.screenshot_blacklist_titles = vec!["^Bank".to_string()];
+        assert!(config.screenshot_blacklist_match("firefox", "Private Browsing", "").is_none());
+        assert!(config.screenshot_blacklist_match("firefox", "Bank of X", "").is_some());
+        assert!(cloned.screenshot_blacklist_match("firefox", "Private Browsing", "").is_some());
+    }
+
+    #[test]
+    fn default_config_has_no_title_rules() {
+        assert!(AppConfig::default().screenshot_blacklist_titles.is_empty());
+    }
 }
@@ -9,6 +9,8 @@ pub enum EventType {
     Keyboard,
     Mouse,
     Idle,
+    /// 截图被跳过（如前台窗口命中黑名单），只记录标记不保存图片
+    ScreenshotSkipped,
 }
 
 /// 事件元数据
@@ -22,6 +24,20 @@ pub struct EventMetadata {
     pub click_count: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub idle_duration_sec: Option<u64>,
+    /// 截图跳过原因，如 "blacklisted"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+}
+
+/// 进程上下文（命令行、工作目录），用于按项目归因
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessContext {
+    pub pid: u32,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
 }
 
 /// 原始事件数据结构
@@ -36,6 +52,21 @@ pub struct RawEvent {
     pub window_title: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exe_path: Option<String>,
+    /// 窗口所属进程
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<ProcessContext>,
+    /// 前台子进程（如终端中正在运行的命令）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub foreground_process: Option<ProcessContext>,
+    /// 工作区/虚拟桌面序号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<u32>,
+    /// 显示器名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    /// 窗口是否全屏
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fullscreen: bool,
     #[serde(default)]
     pub metadata: EventMetadata,
 }
@@ -43,16 +74,51 @@ pub struct RawEvent {
 impl RawEvent {
     /// 创建应用焦点事件
     pub fn app_focus(app: String, window_title: String, exe_path: String) -> Self {
+        Self::app_focus_at(app, window_title, exe_path, Local::now())
+    }
+
+    /// 创建指定发生时刻的应用焦点事件（事件驱动的焦点监听使用）
+    pub fn app_focus_at(app: String, window_title: String, exe_path: String, timestamp: DateTime<Local>) -> Self {
         Self {
-            timestamp: Local::now(),
+            timestamp,
             event_type: EventType::AppFocus,
             app: Some(app),
             window_title: Some(window_title),
             exe_path: if exe_path.is_empty() { None } else { Some(exe_path) },
+            process: None,
+            foreground_process: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
             metadata: EventMetadata::default(),
         }
     }
 
+    /// 附加进程上下文
+    pub fn with_process(mut self, process: Option<ProcessContext>, foreground_process: Option<ProcessContext>) -> Self {
+        self.process = process;
+        self.foreground_process = foreground_process;
+        self
+    }
+
+    /// 附加窗口所在的工作区和显示器
+    pub fn with_location(mut self, workspace: Option<u32>, monitor: Option<String>) -> Self {
+        self.workspace = workspace;
+        self.monitor = monitor;
+        self
+    }
+
+    /// 标记窗口全屏状态
+    pub fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    /// 最能代表当前工作的进程上下文（优先前台子进程）
+    pub fn working_process(&self) -> Option<&ProcessContext> {
+        self.foreground_process.as_ref().or(self.process.as_ref())
+    }
+
     /// 创建键盘事件（关联当前应用）
     pub fn keyboard(key_count: u32, app: String, window_title: String, exe_path: String) -> Self {
         Self {
@@ -61,6 +127,11 @@ impl RawEvent {
             app: if app.is_empty() { None } else { Some(app) },
             window_title: if window_title.is_empty() { None } else { Some(window_title) },
             exe_path: if exe_path.is_empty() { None } else { Some(exe_path) },
+            process: None,
+            foreground_process: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
             metadata: EventMetadata {
                 key_count: Some(key_count),
                 ..Default::default()
@@ -76,6 +147,11 @@ impl RawEvent {
             app: if app.is_empty() { None } else { Some(app) },
             window_title: if window_title.is_empty() { None } else { Some(window_title) },
             exe_path: if exe_path.is_empty() { None } else { Some(exe_path) },
+            process: None,
+            foreground_process: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
             metadata: EventMetadata {
                 mouse_distance: Some(distance),
                 click_count: Some(click_count),
@@ -92,10 +168,35 @@ impl RawEvent {
             app: None,
             window_title: None,
             exe_path: None,
+            process: None,
+            foreground_process: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
             metadata: EventMetadata {
                 idle_duration_sec: Some(duration_sec),
                 ..Default::default()
             },
         }
     }
+
+    /// 创建截图跳过标记
+    pub fn screenshot_skipped(app: String, window_title: String, exe_path: String, reason: &str) -> Self {
+        Self {
+            timestamp: Local::now(),
+            event_type: EventType::ScreenshotSkipped,
+            app: if app.is_empty() { None } else { Some(app) },
+            window_title: if window_title.is_empty() { None } else { Some(window_title) },
+            exe_path: if exe_path.is_empty() { None } else { Some(exe_path) },
+            process: None,
+            foreground_process: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
+            metadata: EventMetadata {
+                skip_reason: Some(reason.to_string()),
+                ..Default::default()
+            },
+        }
+    }
 }
@@ -1,7 +1,9 @@
 pub mod event;
 pub mod segment;
 pub mod config;
+pub mod screenshot;
 
 pub use event::*;
 pub use segment::*;
 pub use config::*;
+pub use screenshot::*;
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+/// 截图的OCR状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrStatus {
+    /// 等待识别
+    #[default]
+    Pending,
+    /// 已识别并保存文本
+    Done,
+    /// 已识别但没有文本
+    Empty,
+    /// 识别失败
+    Failed,
+    /// 按配置跳过（如全屏时暂停OCR）
+    Skipped,
+    /// 等待识别后脱敏，截图仍在暂存目录，正式路径尚不可读
+    Redacting,
+}
+
+impl OcrStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OcrStatus::Pending => "pending",
+            OcrStatus::Done => "done",
+            OcrStatus::Empty => "empty",
+            OcrStatus::Failed => "failed",
+            OcrStatus::Skipped => "skipped",
+            OcrStatus::Redacting => "redacting",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "done" => OcrStatus::Done,
+            "empty" => OcrStatus::Empty,
+            "failed" => OcrStatus::Failed,
+            "skipped" => OcrStatus::Skipped,
+            "redacting" => OcrStatus::Redacting,
+            _ => OcrStatus::Pending,
+        }
+    }
+}
+
+/// 截图目录记录
+/// 每张截图一条，记录截取时的上下文，并通过 segment_id 关联状态段
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScreenshotRecord {
+    pub id: i64,
+    /// 截取时刻（本地时间 `%Y-%m-%dT%H:%M:%S`）
+    pub timestamp: String,
+    /// 画面保持不变的最后时刻（近似截图被跳过时延长）
+    pub end_timestamp: String,
+    pub image_path: String,
+    pub app_name: String,
+    pub window_title: Option<String>,
+    pub exe_path: Option<String>,
+    pub monitor: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    /// 感知哈希（16位十六进制）
+    pub hash: Option<String>,
+    pub ocr_status: OcrStatus,
+    pub segment_id: Option<String>,
+    /// 缩略图路径（查询时按文件是否存在填充）
+    #[serde(default)]
+    pub thumbnail_path: Option<String>,
+    /// 已收藏（不会被自动清理）
+    #[serde(default)]
+    pub pinned: bool,
+    /// 收藏备注
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// 截图列表查询条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScreenshotQuery {
+    /// 按应用名精确筛选
+    pub app_name: Option<String>,
+    /// 按窗口标题模糊筛选
+    pub title_contains: Option<String>,
+    pub ocr_status: Option<OcrStatus>,
+    /// 只看收藏 / 只看未收藏
+    pub pinned: Option<bool>,
+    /// 时间范围（`HH:MM:SS`，含边界）
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub order: SortOrder,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+impl Default for ScreenshotQuery {
+    fn default() -> Self {
+        Self {
+            app_name: None,
+            title_contains: None,
+            ocr_status: None,
+            pinned: None,
+            start_time: None,
+            end_time: None,
+            order: SortOrder::Asc,
+            offset: 0,
+            limit: 100,
+        }
+    }
+}
+
+/// 分页结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenshotPage {
+    /// 满足条件的总数（不受分页影响）
+    pub total: u32,
+    pub items: Vec<ScreenshotRecord>,
+}
@@ -32,6 +32,15 @@ pub struct StateSegment {
     pub input_density: InputDensity,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub screenshot_path: Option<String>,
+    /// 工作区/虚拟桌面序号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<u32>,
+    /// 显示器名称
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    /// 是否为全屏状态（演示、视频等）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fullscreen: bool,
 }
 
 impl StateSegment {
@@ -47,6 +56,9 @@ impl StateSegment {
             activity_level: ActivityLevel::Low,
             input_density: InputDensity::default(),
             screenshot_path: None,
+            workspace: None,
+            monitor: None,
+            fullscreen: false,
         }
     }
 
@@ -92,6 +104,10 @@ pub struct DailySummaryPack {
     pub total_active_minutes: u32,
     pub total_idle_minutes: u32,
     pub app_usage: Vec<AppUsage>,
+    #[serde(default)]
+    pub workspace_usage: Vec<WorkspaceUsage>,
+    #[serde(default)]
+    pub monitor_usage: Vec<MonitorUsage>,
 }
 
 /// 应用使用统计
@@ -101,3 +117,19 @@ pub struct AppUsage {
     pub total_minutes: u32,
     pub percentage: f32,
 }
+
+/// 工作区使用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceUsage {
+    pub workspace: u32,
+    pub total_minutes: u32,
+    pub percentage: f32,
+}
+
+/// 显示器使用统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorUsage {
+    pub monitor: String,
+    pub total_minutes: u32,
+    pub percentage: f32,
+}
@@ -1,17 +1,17 @@
-use once_cell::sync::Lazy;
 use rdev::{listen, Event, EventType};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
 
-/// 全局输入统计
-static KEY_COUNT: AtomicU32 = AtomicU32::new(0);
-static CLICK_COUNT: AtomicU32 = AtomicU32::new(0);
-static MOUSE_DISTANCE: Lazy<Mutex<f64>> = Lazy::new(|| Mutex::new(0.0));
-static LAST_MOUSE_POS: Lazy<Mutex<Option<(f64, f64)>>> = Lazy::new(|| Mutex::new(None));
-static LAST_INPUT_TIME: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
-static IS_LISTENING: AtomicBool = AtomicBool::new(false);
+/// 监听失败后的最大连续重启次数
+const MAX_RESTARTS: u32 = 5;
+/// 监听运行超过该时长后视为恢复正常，重启次数清零
+const STABLE_RUN: Duration = Duration::from_secs(60);
+/// 广播通道容量
+const BROADCAST_CAPACITY: usize = 256;
 
 /// 输入统计数据
 #[derive(Debug, Clone)]
@@ -22,89 +22,233 @@ pub struct InputStats {
     pub idle_seconds: u64,
 }
 
-/// 获取并重置输入统计
-pub fn get_and_reset_stats() -> InputStats {
-    let key_count = KEY_COUNT.swap(0, Ordering::SeqCst);
-    let click_count = CLICK_COUNT.swap(0, Ordering::SeqCst);
-    
-    let mouse_distance = {
-        let mut dist = MOUSE_DISTANCE.lock().unwrap();
-        let val = *dist;
-        *dist = 0.0;
-        val
-    };
-    
-    let idle_seconds = {
-        let last_input = LAST_INPUT_TIME.lock().unwrap();
-        last_input.elapsed().as_secs()
-    };
-    
-    InputStats {
-        key_count,
-        click_count,
-        mouse_distance,
-        idle_seconds,
-    }
+/// 广播给订阅者的输入活动
+#[derive(Debug, Clone)]
+pub enum InputActivity {
+    Key,
+    Click,
+    MouseMove { distance: f64 },
+    Wheel,
+}
+
+/// 单个订阅者的累计计数
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    key_count: u32,
+    click_count: u32,
+    mouse_distance: f64,
 }
 
-/// 获取当前空闲时间（秒）
-pub fn get_idle_seconds() -> u64 {
-    let last_input = LAST_INPUT_TIME.lock().unwrap();
-    last_input.elapsed().as_secs()
+struct Inner {
+    /// 是否处于启用状态（stop后回调丢弃事件）
+    active: AtomicBool,
+    /// 监听线程是否存活
+    thread_alive: AtomicBool,
+    last_input_time: Mutex<Instant>,
+    last_mouse_pos: Mutex<Option<(f64, f64)>>,
+    subscribers: Mutex<HashMap<String, Counters>>,
+    sender: broadcast::Sender<InputActivity>,
 }
 
-/// 更新最后输入时间
-fn update_last_input_time() {
-    let mut last_input = LAST_INPUT_TIME.lock().unwrap();
-    *last_input = Instant::now();
+/// 全局输入追踪器（作为Tauri State管理）
+///
+/// `rdev::listen` 无法从外部中断，因此监听线程只启动一次，
+/// `stop` 后回调直接丢弃事件，再次 `start` 时复用同一线程。
+#[derive(Clone)]
+pub struct InputTracker {
+    inner: Arc<Inner>,
 }
 
-/// 事件回调处理
-fn callback(event: Event) {
-    match event.event_type {
-        EventType::KeyPress(_) => {
-            KEY_COUNT.fetch_add(1, Ordering::SeqCst);
-            update_last_input_time();
+impl InputTracker {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            inner: Arc::new(Inner {
+                active: AtomicBool::new(false),
+                thread_alive: AtomicBool::new(false),
+                last_input_time: Mutex::new(Instant::now()),
+                last_mouse_pos: Mutex::new(None),
+                subscribers: Mutex::new(HashMap::new()),
+                sender,
+            }),
         }
-        EventType::ButtonPress(_) => {
-            CLICK_COUNT.fetch_add(1, Ordering::SeqCst);
-            update_last_input_time();
+    }
+
+    /// 启动全局输入监听（在单独线程中运行，失败时自动重启）
+    pub fn start(&self) {
+        self.inner.active.store(true, Ordering::SeqCst);
+
+        if self.inner.thread_alive.swap(true, Ordering::SeqCst) {
+            return; // 监听线程已存在
         }
-        EventType::MouseMove { x, y } => {
-            let mut last_pos = LAST_MOUSE_POS.lock().unwrap();
-            if let Some((last_x, last_y)) = *last_pos {
-                let dx = x - last_x;
-                let dy = y - last_y;
-                let distance = (dx * dx + dy * dy).sqrt();
-                
-                let mut total_dist = MOUSE_DISTANCE.lock().unwrap();
-                *total_dist += distance;
+
+        let inner = self.inner.clone();
+        thread::spawn(move || {
+            let mut restarts = 0;
+            loop {
+                let callback_inner = inner.clone();
+                let started = Instant::now();
+                let result = listen(move |event| callback_inner.handle(event));
+                if started.elapsed() >= STABLE_RUN {
+                    restarts = 0;
+                }
+
+                // listen 正常情况下不会返回，返回即意味着监听中断
+                if let Err(e) = result {
+                    eprintln!("输入监听错误: {:?}", e);
+                }
+
+                if !inner.active.load(Ordering::SeqCst) || restarts >= MAX_RESTARTS {
+                    break;
+                }
+                restarts += 1;
+                thread::sleep(Duration::from_secs(1 << restarts.min(5)));
+                eprintln!("重启输入监听（第{}次）", restarts);
             }
-            *last_pos = Some((x, y));
-            update_last_input_time();
+            inner.thread_alive.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// 停止统计输入（回调不再计数）
+    pub fn stop(&self) {
+        self.inner.active.store(false, Ordering::SeqCst);
+        *self.inner.last_mouse_pos.lock().unwrap() = None;
+    }
+
+    /// 检查是否正在监听
+    pub fn is_listening(&self) -> bool {
+        self.inner.active.load(Ordering::SeqCst) && self.inner.thread_alive.load(Ordering::SeqCst)
+    }
+
+    /// 注册一个具名订阅者，拥有独立的计数器
+    pub fn subscribe(&self, name: &str) {
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap()
+            .entry(name.to_string())
+            .or_default();
+    }
+
+    /// 注销订阅者
+    pub fn unsubscribe(&self, name: &str) {
+        self.inner.subscribers.lock().unwrap().remove(name);
+    }
+
+    /// 订阅原始输入活动的广播通道
+    pub fn subscribe_channel(&self) -> broadcast::Receiver<InputActivity> {
+        self.inner.sender.subscribe()
+    }
+
+    /// 获取并重置指定订阅者的输入统计（不影响其他订阅者）
+    pub fn take_stats(&self, name: &str) -> InputStats {
+        let counters = {
+            let mut subscribers = self.inner.subscribers.lock().unwrap();
+            std::mem::take(subscribers.entry(name.to_string()).or_default())
+        };
+
+        InputStats {
+            key_count: counters.key_count,
+            click_count: counters.click_count,
+            mouse_distance: counters.mouse_distance,
+            idle_seconds: self.idle_seconds(),
         }
-        EventType::Wheel { .. } => {
-            update_last_input_time();
+    }
+
+    /// 获取当前空闲时间（秒）
+    pub fn idle_seconds(&self) -> u64 {
+        self.inner.last_input_time.lock().unwrap().elapsed().as_secs()
+    }
+}
+
+impl Inner {
+    /// 事件回调处理
+    fn handle(&self, event: Event) {
+        if !self.active.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let activity = match event.event_type {
+            EventType::KeyPress(_) => InputActivity::Key,
+            EventType::ButtonPress(_) => InputActivity::Click,
+            EventType::MouseMove { x, y } => {
+                let mut last_pos = self.last_mouse_pos.lock().unwrap();
+                let distance = match *last_pos {
+                    Some((last_x, last_y)) => {
+                        let dx = x - last_x;
+                        let dy = y - last_y;
+                        (dx * dx + dy * dy).sqrt()
+                    }
+                    None => 0.0,
+                };
+                *last_pos = Some((x, y));
+                InputActivity::MouseMove { distance }
+            }
+            EventType::Wheel { .. } => InputActivity::Wheel,
+            _ => return,
+        };
+
+        *self.last_input_time.lock().unwrap() = Instant::now();
+
+        {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            for counters in subscribers.values_mut() {
+                match activity {
+                    InputActivity::Key => counters.key_count += 1,
+                    InputActivity::Click => counters.click_count += 1,
+                    InputActivity::MouseMove { distance } => counters.mouse_distance += distance,
+                    InputActivity::Wheel => {}
+                }
+            }
         }
-        _ => {}
+
+        // 没有接收者时发送会失败，忽略即可
+        let _ = self.sender.send(activity);
     }
 }
 
-/// 启动全局输入监听（在单独线程中运行）
-pub fn start_listening() {
-    if IS_LISTENING.swap(true, Ordering::SeqCst) {
-        return; // 已经在监听
+impl Default for InputTracker {
+    fn default() -> Self {
+        Self::new()
     }
-    
-    thread::spawn(|| {
-        if let Err(e) = listen(callback) {
-            eprintln!("输入监听错误: {:?}", e);
-            IS_LISTENING.store(false, Ordering::SeqCst);
-        }
-    });
 }
 
-/// 检查是否正在监听
-pub fn is_listening() -> bool {
-    IS_LISTENING.load(Ordering::SeqCst)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rdev::{Button, Key};
+    use std::time::SystemTime;
+
+    fn event(event_type: EventType) -> Event {
+        Event { time: SystemTime::now(), name: None, event_type }
+    }
+
+    #[test]
+    fn take_stats_is_per_subscriber() {
+        let tracker = InputTracker::new();
+        tracker.inner.active.store(true, Ordering::SeqCst);
+        tracker.subscribe("frontend");
+        tracker.subscribe("pet");
+
+        tracker.inner.handle(event(EventType::KeyPress(Key::Unknown(0))));
+        tracker.inner.handle(event(EventType::ButtonPress(Button::Left)));
+        tracker.inner.handle(event(EventType::MouseMove { x: 0.0, y: 0.0 }));
+        tracker.inner.handle(event(EventType::MouseMove { x: 3.0, y: 4.0 }));
+
+        let frontend = tracker.take_stats("frontend");
+        assert_eq!((frontend.key_count, frontend.click_count, frontend.mouse_distance), (1, 1, 5.0));
+        // 取走后只清零自己的计数
+        assert_eq!(tracker.take_stats("frontend").key_count, 0);
+
+        tracker.inner.handle(event(EventType::KeyPress(Key::Unknown(0))));
+        let pet = tracker.take_stats("pet");
+        assert_eq!((pet.key_count, pet.click_count, pet.mouse_distance), (2, 1, 5.0));
+        assert_eq!(tracker.take_stats("frontend").key_count, 1);
+
+        tracker.unsubscribe("pet");
+        tracker.stop();
+        tracker.inner.handle(event(EventType::KeyPress(Key::Unknown(0))));
+        assert_eq!(tracker.take_stats("frontend").key_count, 0);
+        assert_eq!(tracker.take_stats("pet").key_count, 0);
+    }
 }
@@ -0,0 +1,708 @@
+use futures_util::StreamExt;
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Anthropic 要求必须给出输出长度上限
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 对话消息，role 为 user / assistant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl LlmMessage {
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { role: "user".to_string(), content: content.into() }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self { role: "assistant".to_string(), content: content.into() }
+    }
+}
+
+/// 对话请求
+#[derive(Debug, Clone, Default)]
+pub struct LlmRequest {
+    /// 系统提示词（各接口的位置不同，由实现负责放置）
+    pub system: Option<String>,
+    pub messages: Vec<LlmMessage>,
+    pub max_tokens: Option<u32>,
+}
+
+impl LlmRequest {
+    /// 只有一条用户消息的请求
+    pub fn user(content: impl Into<String>) -> Self {
+        Self { messages: vec![LlmMessage::user(content)], ..Default::default() }
+    }
+
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+}
+
+/// 流式输出回调，每收到一段文本调用一次
+pub type LlmChunkHandler<'a> = &'a mut (dyn FnMut(&str) + Send);
+
+/// 大模型接口
+pub trait LlmProvider: Send + Sync {
+    /// 接口名称
+    fn name(&self) -> &'static str;
+
+    /// 一次性返回完整回复
+    fn chat<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, String>>;
+
+    /// 流式返回：每段文本交给 `on_chunk`，结束后返回完整回复
+    fn chat_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_chunk: LlmChunkHandler<'a>,
+    ) -> BoxFuture<'a, Result<String, String>>;
+}
+
+/// 粗略估算文本的 token 数：非 ASCII 字符（主要是中文）按每字 1 个，ASCII 按每 4 个字符 1 个
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text
+        .chars()
+        .fold((0usize, 0usize), |(ascii, other), c| if c.is_ascii() { (ascii + 1, other) } else { (ascii, other + 1) });
+    other + ascii.div_ceil(4)
+}
+
+/// 按接口格式（openai / anthropic / ollama）创建实现；地址为空时使用各自的默认地址
+pub fn create_llm_provider(
+    api_format: &str,
+    base_url: &str,
+    api_key: &str,
+    model: &str,
+) -> Result<Box<dyn LlmProvider>, String> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    match api_format {
+        "ollama" => Ok(Box::new(OllamaProvider::new(
+            if base_url.is_empty() { "http://localhost:11434" } else { base_url },
+            model,
+        ))),
+        "anthropic" => {
+            require_api_key(api_key)?;
+            Ok(Box::new(AnthropicProvider::new(
+                if base_url.is_empty() { "https://api.anthropic.com/v1" } else { base_url },
+                api_key,
+                model,
+            )))
+        }
+        _ => {
+            require_api_key(api_key)?;
+            if base_url.is_empty() {
+                return Err("请先配置API地址".to_string());
+            }
+            Ok(Box::new(OpenAiProvider::new(base_url, api_key, model)))
+        }
+    }
+}
+
+fn require_api_key(api_key: &str) -> Result<(), String> {
+    if api_key.trim().is_empty() {
+        return Err("请先配置API Key".to_string());
+    }
+    Ok(())
+}
+
+/// 发送请求，非 2xx 时把响应正文作为错误返回
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+    let response = request.send().await.map_err(|e| format!("请求失败: {}", e))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API请求失败（{}）: {}", status, error_text));
+    }
+    Ok(response)
+}
+
+async fn read_json(response: reqwest::Response) -> Result<Value, String> {
+    response.json().await.map_err(|e| format!("解析响应失败: {}", e))
+}
+
+/// 逐行读取流式响应（行可能跨越多个数据块），`on_line` 返回 false 时提前结束
+async fn for_each_line(
+    response: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<bool, String>,
+) -> Result<(), String> {
+    let mut stream = response.bytes_stream();
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("读取流失败: {}", e))?;
+        buffer.extend_from_slice(&chunk);
+        // 按字节切行，避免多字节字符被数据块截断后乱码
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if !line.is_empty() && !on_line(line)? {
+                return Ok(());
+            }
+        }
+    }
+    let rest = String::from_utf8_lossy(&buffer);
+    if !rest.trim().is_empty() {
+        on_line(rest.trim())?;
+    }
+    Ok(())
+}
+
+/// 取 SSE 的 data 字段
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(str::trim_start)
+}
+
+/// 响应中的错误信息（各家格式略有不同）
+fn error_message(value: &Value) -> Option<String> {
+    let error = value.get("error")?;
+    Some(
+        error
+            .get("message")
+            .and_then(Value::as_str)
+            .or_else(|| error.as_str())
+            .unwrap_or("未知错误")
+            .to_string(),
+    )
+}
+
+/// OpenAI 兼容接口（/chat/completions），国内大部分厂商都支持
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(base_url: &str, api_key: &str, model: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn body(&self, request: &LlmRequest, stream: bool) -> Value {
+        let mut messages = Vec::new();
+        if let Some(system) = &request.system {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        messages.extend(request.messages.iter().map(|m| json!({"role": m.role, "content": m.content})));
+        let mut body = json!({"model": self.model, "messages": messages, "stream": stream});
+        if let Some(max_tokens) = request.max_tokens {
+            body["max_tokens"] = json!(max_tokens);
+        }
+        body
+    }
+
+    fn post(&self, body: &Value) -> reqwest::RequestBuilder {
+        self.client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(body)
+    }
+}
+
+impl LlmProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn chat<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = read_json(send(self.post(&self.body(request, false))).await?).await?;
+            if let Some(error) = error_message(&response) {
+                return Err(error);
+            }
+            response["choices"][0]["message"]["content"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "AI未返回内容".to_string())
+        })
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_chunk: LlmChunkHandler<'a>,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = send(self.post(&self.body(request, true))).await?;
+            let mut content = String::new();
+            for_each_line(response, |line| {
+                let Some(data) = sse_data(line) else {
+                    return Ok(true);
+                };
+                if data == "[DONE]" {
+                    return Ok(false);
+                }
+                let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+                    return Ok(true);
+                };
+                if let Some(error) = error_message(&chunk) {
+                    return Err(error);
+                }
+                if let Some(text) = chunk["choices"][0]["delta"]["content"].as_str().filter(|t| !t.is_empty()) {
+                    content.push_str(text);
+                    on_chunk(text);
+                }
+                Ok(true)
+            })
+            .await?;
+            Ok(content)
+        })
+    }
+}
+
+/// Anthropic Messages 接口（/messages）
+pub struct AnthropicProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+}
+
+impl AnthropicProvider {
+    pub fn new(base_url: &str, api_key: &str, model: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn body(&self, request: &LlmRequest, stream: bool) -> Value {
+        let messages: Vec<Value> = request
+            .messages
+            .iter()
+            .map(|m| json!({"role": m.role, "content": m.content}))
+            .collect();
+        let mut body = json!({
+            "model": self.model,
+            "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            "messages": messages,
+            "stream": stream,
+        });
+        if let Some(system) = &request.system {
+            body["system"] = json!(system);
+        }
+        body
+    }
+
+    fn post(&self, body: &Value) -> reqwest::RequestBuilder {
+        self.client
+            .post(format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(body)
+    }
+}
+
+impl LlmProvider for AnthropicProvider {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn chat<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = read_json(send(self.post(&self.body(request, false))).await?).await?;
+            if let Some(error) = error_message(&response) {
+                return Err(error);
+            }
+            let text: String = response["content"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect();
+            if text.is_empty() {
+                return Err("AI未返回内容".to_string());
+            }
+            Ok(text)
+        })
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_chunk: LlmChunkHandler<'a>,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = send(self.post(&self.body(request, true))).await?;
+            let mut content = String::new();
+            // 事件类型同时写在 data 的 type 字段里，只看 data 行即可
+            for_each_line(response, |line| {
+                let Some(event) = sse_data(line).and_then(|data| serde_json::from_str::<Value>(data).ok()) else {
+                    return Ok(true);
+                };
+                match event["type"].as_str() {
+                    Some("content_block_delta") => {
+                        if let Some(text) = event["delta"]["text"].as_str().filter(|t| !t.is_empty()) {
+                            content.push_str(text);
+                            on_chunk(text);
+                        }
+                        Ok(true)
+                    }
+                    Some("message_stop") => Ok(false),
+                    Some("error") => Err(error_message(&event).unwrap_or_else(|| "未知错误".to_string())),
+                    _ => Ok(true),
+                }
+            })
+            .await?;
+            Ok(content)
+        })
+    }
+}
+
+/// Ollama 原生接口（/api/chat），本地模型无需 API Key
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str, model: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn body(&self, request: &LlmRequest, stream: bool) -> Value {
+        let mut messages = Vec::new();
+        if let Some(system) = &request.system {
+            messages.push(json!({"role": "system", "content": system}));
+        }
+        messages.extend(request.messages.iter().map(|m| json!({"role": m.role, "content": m.content})));
+        let mut body = json!({"model": self.model, "messages": messages, "stream": stream});
+        if let Some(max_tokens) = request.max_tokens {
+            body["options"] = json!({"num_predict": max_tokens});
+        }
+        body
+    }
+
+    fn post(&self, body: &Value) -> reqwest::RequestBuilder {
+        self.client.post(format!("{}/api/chat", self.base_url)).json(body)
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn chat<'a>(&'a self, request: &'a LlmRequest) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = read_json(send(self.post(&self.body(request, false))).await?).await?;
+            if let Some(error) = error_message(&response) {
+                return Err(error);
+            }
+            response["message"]["content"]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| "AI未返回内容".to_string())
+        })
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: &'a LlmRequest,
+        on_chunk: LlmChunkHandler<'a>,
+    ) -> BoxFuture<'a, Result<String, String>> {
+        Box::pin(async move {
+            let response = send(self.post(&self.body(request, true))).await?;
+            let mut content = String::new();
+            // 每行一个 JSON 对象，最后一行 done 为 true
+            for_each_line(response, |line| {
+                let Ok(chunk) = serde_json::from_str::<Value>(line) else {
+                    return Ok(true);
+                };
+                if let Some(error) = error_message(&chunk) {
+                    return Err(error);
+                }
+                if let Some(text) = chunk["message"]["content"].as_str().filter(|t| !t.is_empty()) {
+                    content.push_str(text);
+                    on_chunk(text);
+                }
+                Ok(chunk["done"].as_bool() != Some(true))
+            })
+            .await?;
+            Ok(content)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// 本地服务收到的请求
+    struct Captured {
+        path: String,
+        headers: HashMap<String, String>,
+        body: Value,
+    }
+
+    /// 启动只处理一次请求的本地服务，按 `chunks` 分块（chunked 编码）返回响应，返回服务地址
+    async fn serve(status: u16, chunks: Vec<Vec<u8>>) -> (String, JoinHandle<Captured>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let mut read = [0u8; 4096];
+            let header_end = loop {
+                let n = socket.read(&mut read).await.unwrap();
+                buffer.extend_from_slice(&read[..n]);
+                if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+            let mut lines = head.lines();
+            let path = lines.next().unwrap().split(' ').nth(1).unwrap().to_string();
+            let headers: HashMap<String, String> = lines
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+                .collect();
+            let length: usize = headers.get("content-length").map_or(0, |v| v.parse().unwrap());
+            while buffer.len() < header_end + length {
+                let n = socket.read(&mut read).await.unwrap();
+                buffer.extend_from_slice(&read[..n]);
+            }
+            let body = serde_json::from_slice(&buffer[header_end..header_end + length]).unwrap();
+
+            let head = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for chunk in chunks {
+                socket.write_all(format!("{:x}\r\n", chunk.len()).as_bytes()).await.unwrap();
+                socket.write_all(&chunk).await.unwrap();
+                socket.write_all(b"\r\n").await.unwrap();
+                socket.flush().await.unwrap();
+                // 让每块单独到达客户端
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            socket.write_all(b"0\r\n\r\n").await.unwrap();
+            Captured { path, headers, body }
+        });
+        (format!("http://{}", addr), handle)
+    }
+
+    /// 把响应正文在给定字节位置（不必有序）切成多块，可以切在行中间或多字节字符中间
+    fn split(text: &str, at: &[usize]) -> Vec<Vec<u8>> {
+        let bytes = text.as_bytes();
+        let mut chunks = Vec::new();
+        let mut at = at.to_vec();
+        at.sort_unstable();
+        let mut start = 0;
+        for end in at {
+            chunks.push(bytes[start..end].to_vec());
+            start = end;
+        }
+        chunks.push(bytes[start..].to_vec());
+        chunks
+    }
+
+    fn whole(text: &str) -> Vec<Vec<u8>> {
+        vec![text.as_bytes().to_vec()]
+    }
+
+    fn request() -> LlmRequest {
+        LlmRequest {
+            system: Some("你是助手".to_string()),
+            messages: vec![LlmMessage::user("你好"), LlmMessage::assistant("hi"), LlmMessage::user("今天做了什么")],
+            max_tokens: Some(256),
+        }
+    }
+
+    async fn stream(provider: &dyn LlmProvider) -> (Result<String, String>, Vec<String>) {
+        let mut chunks = Vec::new();
+        let mut on_chunk = |text: &str| chunks.push(text.to_string());
+        let result = provider.chat_stream(&request(), &mut on_chunk).await;
+        (result, chunks)
+    }
+
+    #[test]
+    fn estimate_tokens_counts_cjk_per_char_and_ascii_per_four() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("今天"), 2);
+        assert_eq!(estimate_tokens("今天 coding"), 4);
+    }
+
+    #[tokio::test]
+    async fn openai_sends_bearer_and_parses_reply() {
+        let (url, server) = serve(200, whole(r#"{"choices":[{"message":{"role":"assistant","content":"写了代码"}}]}"#)).await;
+        let provider = create_llm_provider("openai", &format!("{}/v1/", url), "sk-test", "gpt-test").unwrap();
+        assert_eq!(provider.chat(&request()).await.unwrap(), "写了代码");
+
+        let captured = server.await.unwrap();
+        assert_eq!(captured.path, "/v1/chat/completions");
+        assert_eq!(captured.headers["authorization"], "Bearer sk-test");
+        assert!(!captured.headers.contains_key("x-api-key"));
+        let body = captured.body;
+        assert_eq!(body["model"], "gpt-test");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["messages"][0], json!({"role": "system", "content": "你是助手"}));
+        assert_eq!(body["messages"][3], json!({"role": "user", "content": "今天做了什么"}));
+    }
+
+    #[tokio::test]
+    async fn openai_stream_joins_split_lines_and_stops_at_done() {
+        let sse = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"写了\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"代码\"}}]}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"多余\"}}]}\n\n",
+        );
+        // 第二个切点落在“写”字的字节中间
+        let split_at = sse.find("写").unwrap() + 1;
+        let (url, server) = serve(200, split(sse, &[20, split_at, 120, 150])).await;
+        let provider = OpenAiProvider::new(&url, "sk-test", "gpt-test");
+        let (result, chunks) = stream(&provider).await;
+        assert_eq!(result.unwrap(), "写了代码");
+        assert_eq!(chunks, ["写了", "代码"]);
+        assert_eq!(server.await.unwrap().body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn openai_reports_error_bodies() {
+        let (url, _server) = serve(401, whole(r#"{"error":{"message":"invalid api key"}}"#)).await;
+        let error = OpenAiProvider::new(&url, "sk-bad", "gpt-test").chat(&request()).await.unwrap_err();
+        assert!(error.contains("401"), "{}", error);
+        assert!(error.contains("invalid api key"), "{}", error);
+
+        let sse = "data: {\"choices\":[{\"delta\":{\"content\":\"写\"}}]}\n\ndata: {\"error\":{\"message\":\"rate limited\"}}\n\n";
+        let (url, _server) = serve(200, whole(sse)).await;
+        let (result, chunks) = stream(&OpenAiProvider::new(&url, "sk-test", "gpt-test")).await;
+        assert_eq!(result.unwrap_err(), "rate limited");
+        assert_eq!(chunks, ["写"]);
+    }
+
+    #[tokio::test]
+    async fn anthropic_sends_key_version_and_top_level_system() {
+        let reply = r#"{"content":[{"type":"text","text":"写了"},{"type":"tool_use","id":"t"},{"type":"text","text":"代码"}]}"#;
+        let (url, server) = serve(200, whole(reply)).await;
+        let provider = create_llm_provider("anthropic", &url, "ak-test", "claude-test").unwrap();
+        let mut req = request();
+        req.max_tokens = None;
+        assert_eq!(provider.chat(&req).await.unwrap(), "写了代码");
+
+        let captured = server.await.unwrap();
+        assert_eq!(captured.path, "/messages");
+        assert_eq!(captured.headers["x-api-key"], "ak-test");
+        assert_eq!(captured.headers["anthropic-version"], ANTHROPIC_VERSION);
+        assert!(!captured.headers.contains_key("authorization"));
+        let body = captured.body;
+        assert_eq!(body["system"], "你是助手");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(body["messages"][0], json!({"role": "user", "content": "你好"}));
+    }
+
+    #[tokio::test]
+    async fn anthropic_stream_reads_deltas_until_message_stop() {
+        let sse = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"写了\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"代码\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"多余\"}}\n\n",
+        );
+        let split_at = sse.find("代码").unwrap() + 2;
+        let (url, server) = serve(200, split(sse, &[30, 100, split_at, 300])).await;
+        let (result, chunks) = stream(&AnthropicProvider::new(&url, "ak-test", "claude-test")).await;
+        assert_eq!(result.unwrap(), "写了代码");
+        assert_eq!(chunks, ["写了", "代码"]);
+        let body = server.await.unwrap().body;
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["max_tokens"], 256);
+    }
+
+    #[tokio::test]
+    async fn anthropic_reports_error_bodies() {
+        let error_body = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        let (url, _server) = serve(529, whole(error_body)).await;
+        let error = AnthropicProvider::new(&url, "ak-test", "claude-test").chat(&request()).await.unwrap_err();
+        assert!(error.contains("529"), "{}", error);
+        assert!(error.contains("Overloaded"), "{}", error);
+
+        let (url, _server) = serve(200, whole(&format!("event: error\ndata: {}\n\n", error_body))).await;
+        let (result, _) = stream(&AnthropicProvider::new(&url, "ak-test", "claude-test")).await;
+        assert_eq!(result.unwrap_err(), "Overloaded");
+    }
+
+    #[tokio::test]
+    async fn ollama_puts_system_in_messages_and_limit_in_options() {
+        let (url, server) = serve(200, whole(r#"{"message":{"role":"assistant","content":"写了代码"},"done":true}"#)).await;
+        let provider = create_llm_provider("ollama", &url, "", "qwen-test").unwrap();
+        assert_eq!(provider.chat(&request()).await.unwrap(), "写了代码");
+
+        let captured = server.await.unwrap();
+        assert_eq!(captured.path, "/api/chat");
+        assert!(!captured.headers.contains_key("authorization"));
+        let body = captured.body;
+        assert_eq!(body["messages"][0], json!({"role": "system", "content": "你是助手"}));
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("system").is_none());
+    }
+
+    #[tokio::test]
+    async fn ollama_stream_reads_ndjson_until_done() {
+        let ndjson = concat!(
+            "{\"message\":{\"content\":\"写了\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"代码\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"\"},\"done\":true}\n",
+            "{\"message\":{\"content\":\"多余\"},\"done\":false}\n",
+        );
+        let split_at = ndjson.find("代码").unwrap() + 4;
+        let (url, _server) = serve(200, split(ndjson, &[10, split_at, 70])).await;
+        let (result, chunks) = stream(&OllamaProvider::new(&url, "qwen-test")).await;
+        assert_eq!(result.unwrap(), "写了代码");
+        assert_eq!(chunks, ["写了", "代码"]);
+    }
+
+    #[tokio::test]
+    async fn ollama_reports_error_bodies() {
+        let (url, _server) = serve(404, whole(r#"{"error":"model \"qwen-test\" not found"}"#)).await;
+        let error = OllamaProvider::new(&url, "qwen-test").chat(&request()).await.unwrap_err();
+        assert!(error.contains("404"), "{}", error);
+        assert!(error.contains("not found"), "{}", error);
+
+        let (url, _server) = serve(200, whole("{\"error\":\"out of memory\"}\n")).await;
+        let (result, _) = stream(&OllamaProvider::new(&url, "qwen-test")).await;
+        assert_eq!(result.unwrap_err(), "out of memory");
+    }
+
+    #[test]
+    fn create_provider_checks_key_and_url() {
+        assert!(create_llm_provider("anthropic", "", " ", "m").is_err());
+        assert!(create_llm_provider("openai", "", "sk", "m").is_err());
+        assert_eq!(create_llm_provider("anthropic", "", "ak", "m").unwrap().name(), "anthropic");
+        assert_eq!(create_llm_provider("ollama", "", "", "m").unwrap().name(), "ollama");
+    }
+}
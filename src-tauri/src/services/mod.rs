@@ -4,6 +4,17 @@ pub mod icon_extractor;
 pub mod input_tracker;
 pub mod screenshot;
 pub mod ocr;
+pub mod ocr_queue;
+pub mod ocr_diff;
+pub mod ocr_repository;
+pub mod process_info;
+pub mod summary;
+pub mod screenshot_scheduler;
+pub mod screenshot_catalog;
+pub mod redaction;
+pub mod timelapse;
+pub mod screenshot_quota;
+pub mod llm;
 
 pub use storage::*;
 pub use window_tracker::*;
@@ -11,3 +22,14 @@ pub use icon_extractor::*;
 pub use input_tracker::*;
 pub use screenshot::*;
 pub use ocr::*;
+pub use ocr_queue::*;
+pub use ocr_diff::*;
+pub use ocr_repository::*;
+pub use process_info::*;
+pub use summary::*;
+pub use screenshot_scheduler::*;
+pub use screenshot_catalog::*;
+pub use redaction::*;
+pub use timelapse::*;
+pub use screenshot_quota::*;
+pub use llm::*;
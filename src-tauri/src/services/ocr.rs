@@ -1,130 +1,630 @@
-use std::path::Path;
-use std::process::{Command, Stdio, Child, ChildStdin, ChildStdout};
-use std::sync::Mutex;
-use std::io::{BufRead, BufReader, Write};
-use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio, Child, ChildStdin};
+use std::sync::{Arc, Mutex, Weak, mpsc};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::models::AppConfig;
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
 
+/// OCR识别出的一行文本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrLine {
+    pub text: String,
+    /// 文本行的四边形顶点（图片像素坐标，左上起顺时针）
+    #[serde(rename = "box")]
+    pub points: Vec<[f32; 2]>,
+    /// 置信度（0-1）
+    #[serde(rename = "score", default)]
+    pub confidence: f32,
+}
+
+impl OcrLine {
+    /// 外接矩形 (left, top, right, bottom)
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let first = self.points.first()?;
+        Some(self.points.iter().fold(
+            (first[0], first[1], first[0], first[1]),
+            |(l, t, r, b), p| (l.min(p[0]), t.min(p[1]), r.max(p[0]), b.max(p[1])),
+        ))
+    }
+}
+
+/// OCR识别结果
+#[derive(Debug, Clone, Default)]
+pub struct OcrResult {
+    /// 按行拼接的全文
+    pub text: String,
+    /// 逐行结果（旧版脚本不返回时为空）
+    pub lines: Vec<OcrLine>,
+}
+
+impl OcrResult {
+    /// 由逐行结果拼出全文
+    pub fn from_lines(lines: Vec<OcrLine>) -> Self {
+        let text = lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+        Self { text, lines }
+    }
+}
+
+/// OCR引擎
+pub trait OcrEngine: Send {
+    /// 引擎名称
+    fn name(&self) -> &'static str;
+
+    /// 识别图片，返回全文和逐行位置；超过 `timeout` 未完成时结束子进程并返回错误
+    fn recognize(&mut self, image_path: &Path, timeout: Duration) -> Result<OcrResult, String>;
+}
+
+/// OCR引擎设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrSettings {
+    /// 引擎：rapidocr / tesseract / mock
+    pub engine: String,
+    /// RapidOCR 使用的 Python 解释器
+    pub python_path: String,
+    /// RapidOCR 服务脚本（空为内置脚本）
+    pub script_path: String,
+    /// tesseract 可执行文件
+    pub tesseract_path: String,
+    /// 语言、模型和线程数
+    pub model: OcrModelOptions,
+}
+
+/// 识别语言、模型和线程数；RapidOCR 启动时作为握手参数发给服务脚本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OcrModelOptions {
+    /// tesseract 语言包，多个用 + 连接
+    pub languages: String,
+    /// 检测模型路径（空为内置模型）
+    pub det_model_path: String,
+    /// 识别模型路径（空为内置模型）
+    pub rec_model_path: String,
+    /// 识别模型对应的字典文件
+    pub rec_keys_path: String,
+    pub threads: u32,
+}
+
+impl OcrSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            engine: config.ocr_engine.clone(),
+            python_path: config.ocr_python_path.clone(),
+            script_path: config.ocr_script_path.clone(),
+            tesseract_path: config.ocr_tesseract_path.clone(),
+            model: OcrModelOptions {
+                languages: config.ocr_languages.trim().to_string(),
+                det_model_path: config.ocr_det_model_path.trim().to_string(),
+                rec_model_path: config.ocr_rec_model_path.trim().to_string(),
+                rec_keys_path: config.ocr_rec_keys_path.trim().to_string(),
+                threads: config.ocr_threads.max(1),
+            },
+        }
+    }
+
+    /// 按设置创建引擎
+    pub fn create_engine(&self) -> Box<dyn OcrEngine> {
+        match self.engine.as_str() {
+            "tesseract" => Box::new(TesseractEngine::new(&self.tesseract_path, &self.model)),
+            "mock" => Box::new(MockOcrEngine::new()),
+            _ => Box::new(RapidOcrEngine::new(&self.python_path, &self.script_path, &self.model)),
+        }
+    }
+}
+
+/// 子进程不弹出控制台窗口
+fn hide_console(_cmd: &mut Command) {
+    #[cfg(windows)]
+    _cmd.creation_flags(CREATE_NO_WINDOW);
+}
+
+/// 所有存活的OCR子进程，程序退出时统一结束
+static CHILDREN: Mutex<Vec<Weak<Mutex<Child>>>> = Mutex::new(Vec::new());
+
+/// 登记子进程
+fn track_child(child: Child) -> Arc<Mutex<Child>> {
+    let child = Arc::new(Mutex::new(child));
+    let mut children = CHILDREN.lock().unwrap();
+    children.retain(|c| c.strong_count() > 0);
+    children.push(Arc::downgrade(&child));
+    child
+}
+
+fn kill_child(child: &Mutex<Child>) {
+    if let Ok(mut child) = child.lock() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// 结束所有OCR子进程（程序退出时调用）
+pub fn kill_ocr_processes() {
+    let children: Vec<_> = CHILDREN.lock().unwrap().drain(..).collect();
+    for child in children.iter().filter_map(Weak::upgrade) {
+        kill_child(&child);
+    }
+}
+
+/// 在后台线程读完管道，避免子进程因输出缓冲区写满而阻塞
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+/// 等待子进程结束，超时则结束进程；返回退出状态、stdout 和 stderr
+fn wait_with_timeout(mut child: Child, timeout: Duration, name: &str) -> Result<(ExitStatus, String, String), String> {
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let child = track_child(child);
+    let deadline = Instant::now() + timeout;
+
+    let status = loop {
+        let polled = child.lock().map_err(|_| format!("{}进程锁获取失败", name))?.try_wait();
+        match polled {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(50)),
+            Ok(None) => {
+                kill_child(&child);
+                return Err(format!("{}识别超时（{}秒）", name, timeout.as_secs()));
+            }
+            Err(e) => return Err(format!("等待{}进程失败: {}", name, e)),
+        }
+    };
+    Ok((status, stdout.join().unwrap_or_default(), stderr.join().unwrap_or_default()))
+}
+
+/// 常驻进程加载模型的最长等待时间
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
 // OCR常驻进程
 struct OcrProcess {
-    child: Child,
+    child: Arc<Mutex<Child>>,
     stdin: ChildStdin,
-    stdout: BufReader<ChildStdout>,
-}
-
-static OCR_PROCESS: Lazy<Mutex<Option<OcrProcess>>> = Lazy::new(|| Mutex::new(None));
-
-/// 获取或启动OCR进程
-fn get_or_start_ocr_process() -> Result<(), String> {
-    let mut guard = OCR_PROCESS.lock().map_err(|_| "OCR锁获取失败")?;
-    
-    // 检查进程是否存活
-    if let Some(ref mut proc) = *guard {
-        match proc.child.try_wait() {
-            Ok(None) => return Ok(()), // 进程仍在运行
-            _ => {} // 进程已退出，需要重启
-        }
-    }
-    
-    // 启动新进程
-    let script_path = get_ocr_script_path()?;
-    
-    let mut cmd = Command::new("python");
-    cmd.arg(&script_path)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .env("PYTHONIOENCODING", "utf-8");
-    
-    #[cfg(windows)]
-    cmd.creation_flags(CREATE_NO_WINDOW);
-    
-    let mut child = cmd.spawn()
-        .map_err(|e| format!("启动OCR进程失败: {}", e))?;
-    
-    let stdin = child.stdin.take()
-        .ok_or("无法获取OCR进程stdin")?;
-    let stdout = child.stdout.take()
-        .ok_or("无法获取OCR进程stdout")?;
-    
-    let mut reader = BufReader::new(stdout);
-    
-    // 等待就绪信号
-    let mut ready_line = String::new();
-    reader.read_line(&mut ready_line)
-        .map_err(|e| format!("读取OCR就绪信号失败: {}", e))?;
-    
-    let ready_json: serde_json::Value = serde_json::from_str(&ready_line)
-        .map_err(|e| format!("解析OCR就绪信号失败: {}", e))?;
-    
-    if let Some(error) = ready_json.get("error").and_then(|v| v.as_str()) {
+    /// stdout 由读取线程逐行转发，便于按超时等待
+    lines: mpsc::Receiver<String>,
+}
+
+impl OcrProcess {
+    fn is_alive(&self) -> bool {
+        matches!(self.child.lock().map(|mut c| c.try_wait()), Ok(Ok(None)))
+    }
+
+    /// 等待下一行输出
+    fn read_line(&self, timeout: Duration) -> Result<String, String> {
+        self.lines.recv_timeout(timeout).map_err(|e| match e {
+            mpsc::RecvTimeoutError::Timeout => format!("OCR识别超时（{}秒）", timeout.as_secs()),
+            mpsc::RecvTimeoutError::Disconnected => "OCR进程已退出".to_string(),
+        })
+    }
+}
+
+/// RapidOCR：常驻 Python 进程，通过 stdin/stdout 逐行收发 JSON
+pub struct RapidOcrEngine {
+    python_path: String,
+    script_path: String,
+    options: OcrModelOptions,
+    process: Option<OcrProcess>,
+}
+
+impl RapidOcrEngine {
+    pub fn new(python_path: &str, script_path: &str, options: &OcrModelOptions) -> Self {
+        Self {
+            python_path: python_path.to_string(),
+            script_path: script_path.to_string(),
+            options: options.clone(),
+            process: None,
+        }
+    }
+
+    /// 获取或启动OCR进程
+    fn ensure_started(&mut self) -> Result<&mut OcrProcess, String> {
+        // 检查进程是否存活，已退出则重启
+        if self.process.as_ref().is_some_and(|p| !p.is_alive()) {
+            self.process = None;
+        }
+        if self.process.is_none() {
+            self.process = Some(self.start()?);
+        }
+        self.process.as_mut().ok_or_else(|| "OCR进程未启动".to_string())
+    }
+
+    fn start(&self) -> Result<OcrProcess, String> {
+        let script_path = if self.script_path.is_empty() {
+            get_ocr_script_path()?
+        } else {
+            PathBuf::from(&self.script_path)
+        };
+        if !script_path.exists() {
+            return Err(format!("OCR脚本不存在: {}", script_path.display()));
+        }
+        let python = if self.python_path.is_empty() { "python" } else { self.python_path.as_str() };
+
+        let mut cmd = Command::new(python);
+        cmd.arg(&script_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .env("PYTHONIOENCODING", "utf-8");
+        hide_console(&mut cmd);
+
+        let mut child = cmd.spawn()
+            .map_err(|e| format!("启动OCR进程失败（{}）: {}", python, e))?;
+
+        let mut stdin = child.stdin.take()
+            .ok_or("无法获取OCR进程stdin")?;
+        // 握手：首行发送模型设置，脚本据此加载模型后回复就绪
+        writeln!(stdin, "{}", serde_json::json!({ "init": &self.options }))
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("发送OCR设置失败: {}", e))?;
+        let stdout = child.stdout.take()
+            .ok_or("无法获取OCR进程stdout")?;
+
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        // 出错返回时进程随 OcrProcess 一起结束
+        let process = OcrProcess { child: track_child(child), stdin, lines };
+
+        // 等待就绪信号（首次加载模型较慢）
+        let ready_line = process.read_line(STARTUP_TIMEOUT)
+            .map_err(|e| format!("等待OCR就绪失败: {}", e))?;
+        let ready: serde_json::Value = serde_json::from_str(&ready_line)
+            .map_err(|e| format!("解析OCR就绪信号失败: {}", e))?;
+        if let Some(error) = ready.get("error").and_then(|v| v.as_str()) {
+            return Err(error.to_string());
+        }
+        if let Some(warning) = ready.get("warning").and_then(|v| v.as_str()) {
+            eprintln!("OCR设置提示: {}", warning);
+        }
+        // 旧版脚本不读握手，会把设置行当作请求回一行错误，读掉以免后续响应错位
+        if ready.get("init").is_none() {
+            let _ = process.read_line(Duration::from_secs(5));
+        }
+
+        Ok(process)
+    }
+
+    /// 发送请求并等待响应行
+    fn request(proc: &mut OcrProcess, image_path: &Path, timeout: Duration) -> Result<String, String> {
+        let request = serde_json::json!({"image_path": image_path.to_string_lossy()});
+        writeln!(proc.stdin, "{}", request)
+            .map_err(|e| format!("发送OCR请求失败: {}", e))?;
+        proc.stdin.flush()
+            .map_err(|e| format!("刷新OCR请求失败: {}", e))?;
+        proc.read_line(timeout)
+    }
+}
+
+impl OcrEngine for RapidOcrEngine {
+    fn name(&self) -> &'static str {
+        "rapidocr"
+    }
+
+    fn recognize(&mut self, image_path: &Path, timeout: Duration) -> Result<OcrResult, String> {
+        let proc = self.ensure_started()?;
+        match Self::request(proc, image_path, timeout) {
+            Ok(line) => parse_rapidocr_response(&line),
+            Err(e) => {
+                // 超时、卡死或崩溃：结束进程，下次请求时重新启动
+                self.process = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for OcrProcess {
+    fn drop(&mut self) {
+        kill_child(&self.child);
+    }
+}
+
+fn parse_rapidocr_response(line: &str) -> Result<OcrResult, String> {
+    let response: serde_json::Value = serde_json::from_str(line)
+        .map_err(|e| format!("解析OCR响应失败: {}", e))?;
+
+    if let Some(error) = response.get("error").and_then(|v| v.as_str()) {
         return Err(error.to_string());
     }
-    
-    *guard = Some(OcrProcess { child, stdin, stdout: reader });
-    Ok(())
+
+    let text = response.get("text")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or("OCR响应中没有text字段".to_string())?;
+    let lines = response.get("lines")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    Ok(OcrResult { text, lines })
 }
 
-fn get_ocr_script_path() -> Result<std::path::PathBuf, String> {
+fn get_ocr_script_path() -> Result<PathBuf, String> {
     let exe_dir = std::env::current_exe()
         .map_err(|e| format!("获取程序路径失败: {}", e))?
         .parent()
         .map(|p| p.to_path_buf())
         .unwrap_or_else(|| Path::new(".").to_path_buf());
-    
+
     let script_path = exe_dir.join("scripts").join("ocr_service.py");
-    
+
     if script_path.exists() {
         return Ok(script_path);
     }
-    
+
     let dev_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts").join("ocr_service.py");
     if dev_path.exists() {
         return Ok(dev_path);
     }
-    
+
     Err("OCR脚本不存在".to_string())
 }
 
-/// 使用RapidOCR进行文字识别（常驻进程模式）
-pub async fn extract_text_from_image(image_path: &str) -> Result<String, String> {
-    let path = Path::new(image_path);
-    if !path.exists() {
-        return Err(format!("图片文件不存在: {}", image_path));
-    }
-    
-    // 确保OCR进程运行
-    get_or_start_ocr_process()?;
-    
-    // 发送请求并获取结果
-    let mut guard = OCR_PROCESS.lock().map_err(|_| "OCR锁获取失败")?;
-    let proc = guard.as_mut().ok_or("OCR进程未启动")?;
-    
-    // 发送请求
-    let request = serde_json::json!({"image_path": image_path});
-    writeln!(proc.stdin, "{}", request)
-        .map_err(|e| format!("发送OCR请求失败: {}", e))?;
-    proc.stdin.flush()
-        .map_err(|e| format!("刷新OCR请求失败: {}", e))?;
-    
-    // 读取响应
-    let mut response_line = String::new();
-    proc.stdout.read_line(&mut response_line)
-        .map_err(|e| format!("读取OCR响应失败: {}", e))?;
-    
-    let response: serde_json::Value = serde_json::from_str(&response_line)
-        .map_err(|e| format!("解析OCR响应失败: {}", e))?;
-    
-    if let Some(error) = response.get("error").and_then(|v| v.as_str()) {
-        return Err(error.to_string());
+/// tesseract 命令行：每张图启动一次，解析 TSV 输出得到逐行位置
+pub struct TesseractEngine {
+    binary: String,
+    languages: String,
+    threads: u32,
+}
+
+impl TesseractEngine {
+    pub fn new(binary: &str, options: &OcrModelOptions) -> Self {
+        Self {
+            binary: if binary.is_empty() { "tesseract".to_string() } else { binary.to_string() },
+            languages: if options.languages.is_empty() { "chi_sim+eng".to_string() } else { options.languages.clone() },
+            threads: options.threads.max(1),
+        }
+    }
+}
+
+impl OcrEngine for TesseractEngine {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    fn recognize(&mut self, image_path: &Path, timeout: Duration) -> Result<OcrResult, String> {
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg(image_path)
+            .arg("stdout")
+            .args(["-l", &self.languages])
+            .arg("tsv")
+            .env("OMP_THREAD_LIMIT", self.threads.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        hide_console(&mut cmd);
+
+        let child = cmd.spawn()
+            .map_err(|e| format!("启动tesseract失败（{}）: {}", self.binary, e))?;
+        let (status, stdout, stderr) = wait_with_timeout(child, timeout, "tesseract")?;
+        if !status.success() {
+            return Err(format!("tesseract识别失败: {}", stderr.trim()));
+        }
+        Ok(parse_tesseract_tsv(&stdout))
+    }
+}
+
+/// 解析 tesseract TSV：按 (页, 块, 段, 行) 合并单词，外接矩形作为行框，置信度取单词平均
+fn parse_tesseract_tsv(tsv: &str) -> OcrResult {
+    struct Line {
+        key: (u32, u32, u32, u32),
+        text: String,
+        rect: (f32, f32, f32, f32),
+        conf_sum: f32,
+        words: u32,
+    }
+
+    let mut lines: Vec<Line> = Vec::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let word = cols[11].trim();
+        let conf: f32 = cols[10].parse().unwrap_or(-1.0);
+        if word.is_empty() || conf < 0.0 {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let key = (num(1), num(2), num(3), num(4));
+        let (left, top) = (num(6) as f32, num(7) as f32);
+        let (right, bottom) = (left + num(8) as f32, top + num(9) as f32);
+
+        match lines.last_mut() {
+            Some(line) if line.key == key => {
+                // 中文按字切分，相邻的非ASCII字符之间不加空格
+                let joined = line.text.ends_with(|c: char| c.is_ascii()) || word.starts_with(|c: char| c.is_ascii());
+                if joined {
+                    line.text.push(' ');
+                }
+                line.text.push_str(word);
+                line.rect = (line.rect.0.min(left), line.rect.1.min(top), line.rect.2.max(right), line.rect.3.max(bottom));
+                line.conf_sum += conf;
+                line.words += 1;
+            }
+            _ => lines.push(Line {
+                key,
+                text: word.to_string(),
+                rect: (left, top, right, bottom),
+                conf_sum: conf,
+                words: 1,
+            }),
+        }
+    }
+
+    OcrResult::from_lines(
+        lines
+            .into_iter()
+            .map(|line| {
+                let (l, t, r, b) = line.rect;
+                OcrLine {
+                    text: line.text,
+                    points: vec![[l, t], [r, t], [r, b], [l, b]],
+                    confidence: line.conf_sum / line.words as f32 / 100.0,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// 模拟引擎：读取图片旁同名 `.txt` 文件的内容作为识别结果（每行一条），用于测试和调试
+#[derive(Debug, Default)]
+pub struct MockOcrEngine {
+    /// 固定返回的文本，设置后忽略 `.txt` 文件
+    text: Option<String>,
+}
+
+impl MockOcrEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 固定返回指定文本
+    pub fn with_text(text: &str) -> Self {
+        Self { text: Some(text.to_string()) }
+    }
+}
+
+impl OcrEngine for MockOcrEngine {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn recognize(&mut self, image_path: &Path, _timeout: Duration) -> Result<OcrResult, String> {
+        let text = match &self.text {
+            Some(text) => text.clone(),
+            None => std::fs::read_to_string(image_path.with_extension("txt")).unwrap_or_default(),
+        };
+        Ok(OcrResult::from_lines(
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| OcrLine {
+                    text: line.to_string(),
+                    points: Vec::new(),
+                    confidence: 1.0,
+                })
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV_HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    /// 单词行，`rect` 为 (left, top, width, height)
+    fn word(line: u32, word: u32, rect: (u32, u32, u32, u32), conf: f32, text: &str) -> String {
+        let (left, top, width, height) = rect;
+        format!("5\t1\t1\t1\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}", line, word, left, top, width, height, conf, text)
+    }
+
+    #[test]
+    fn tesseract_tsv_merges_words_into_lines() {
+        let tsv = [
+            TSV_HEADER.to_string(),
+            // 非单词层级的行被忽略
+            "4\t1\t1\t1\t1\t0\t10\t10\t200\t20\t-1\t".to_string(),
+            word(1, 1, (10, 12, 50, 16), 90.0, "Hello"),
+            word(1, 2, (70, 10, 60, 20), 80.0, "world"),
+            word(2, 1, (10, 40, 20, 20), 96.0, "中"),
+            word(2, 2, (30, 40, 20, 20), 94.0, "文"),
+            word(2, 3, (55, 40, 40, 20), 95.0, "OCR"),
+            // 置信度为 -1 或空文本的单词跳过
+            word(2, 4, (100, 40, 20, 20), -1.0, "x"),
+            word(2, 5, (120, 40, 20, 20), 90.0, " "),
+        ]
+        .join("\n");
+
+        let result = parse_tesseract_tsv(&tsv);
+        assert_eq!(result.text, "Hello world\n中文 OCR");
+        assert_eq!(result.lines.len(), 2);
+
+        let first = &result.lines[0];
+        assert_eq!(first.bounds(), Some((10.0, 10.0, 130.0, 30.0)));
+        assert!((first.confidence - 0.85).abs() < 1e-6);
+
+        assert_eq!(result.lines[1].bounds(), Some((10.0, 40.0, 95.0, 60.0)));
+    }
+
+    #[test]
+    fn tesseract_tsv_without_words_is_empty() {
+        let result = parse_tesseract_tsv(TSV_HEADER);
+        assert!(result.text.is_empty());
+        assert!(result.lines.is_empty());
+    }
+
+    #[test]
+    fn rapidocr_response_parses_text_and_lines() {
+        let result = parse_rapidocr_response(
+            r#"{"text":"第一行\nsecond","lines":[{"text":"第一行","box":[[0,0],[30,0],[30,10],[0,10]],"score":0.9},{"text":"second","box":[[0,20],[40,20],[40,30],[0,30]]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(result.text, "第一行\nsecond");
+        assert_eq!(result.lines.len(), 2);
+        assert_eq!(result.lines[0].bounds(), Some((0.0, 0.0, 30.0, 10.0)));
+        assert!((result.lines[0].confidence - 0.9).abs() < 1e-6);
+        // 缺少 score 时默认为 0
+        assert_eq!(result.lines[1].confidence, 0.0);
+
+        // 旧版脚本只返回 text
+        let legacy = parse_rapidocr_response(r#"{"text":"only text"}"#).unwrap();
+        assert_eq!(legacy.text, "only text");
+        assert!(legacy.lines.is_empty());
+    }
+
+    #[test]
+    fn rapidocr_response_reports_errors() {
+        assert_eq!(parse_rapidocr_response(r#"{"error":"模型加载失败"}"#).unwrap_err(), "模型加载失败");
+        assert!(parse_rapidocr_response(r#"{"lines":[]}"#).is_err());
+        assert!(parse_rapidocr_response("not json").is_err());
+    }
+
+    #[test]
+    fn create_engine_follows_settings() {
+        let mut settings = OcrSettings::from_config(&AppConfig::default());
+        for (engine, expected) in [("tesseract", "tesseract"), ("mock", "mock"), ("rapidocr", "rapidocr"), ("unknown", "rapidocr")] {
+            settings.engine = engine.to_string();
+            assert_eq!(settings.create_engine().name(), expected);
+        }
+    }
+
+    #[test]
+    fn mock_engine_returns_fixed_text_as_lines() {
+        let mut engine = MockOcrEngine::with_text("第一行\n\n  \nsecond line");
+        let result = engine.recognize(Path::new("/nonexistent.png"), Duration::from_secs(1)).unwrap();
+        assert_eq!(result.text, "第一行\nsecond line");
+        assert_eq!(result.lines.len(), 2);
+        assert!(result.lines.iter().all(|l| l.confidence == 1.0 && l.bounds().is_none()));
+    }
+
+    #[test]
+    fn mock_engine_reads_sidecar_text() {
+        let dir = std::env::temp_dir().join(format!("mock_ocr_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("shot.png");
+        std::fs::write(dir.join("shot.txt"), "识别结果\n").unwrap();
+
+        let mut engine = MockOcrEngine::new();
+        assert_eq!(engine.recognize(&image, Duration::from_secs(1)).unwrap().text, "识别结果");
+        // 没有同名文本文件时结果为空
+        let missing = engine.recognize(&dir.join("other.png"), Duration::from_secs(1)).unwrap();
+        assert!(missing.text.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
     }
-    
-    response.get("text")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .ok_or("OCR响应中没有text字段".to_string())
 }
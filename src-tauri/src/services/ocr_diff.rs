@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use crate::services::ocr::OcrLine;
+
+/// 布局中表示“取下一条新增行”的标记，其余值为基准记录的行号
+pub const NEW_LINE: i64 = -1;
+
+/// 相对基准记录的增量：只保存新增行的文本，其余行的文本按行号引用基准
+#[derive(Debug, Clone, Default)]
+pub struct OcrDelta {
+    /// 完整结果的每一行；引用基准的行只保留本次的位置和置信度，文本为空
+    pub lines: Vec<OcrLine>,
+    /// 完整结果的每一行来自哪里：基准行号或 [`NEW_LINE`]
+    pub layout: Vec<i64>,
+}
+
+impl OcrDelta {
+    /// 基准中没有的行（按出现顺序）
+    pub fn new_lines(&self) -> Vec<OcrLine> {
+        delta_new_lines(&self.lines, &self.layout)
+    }
+
+    /// 新增行占比不超过一半时才值得按增量保存
+    pub fn is_worthwhile(&self) -> bool {
+        let new = self.layout.iter().filter(|&&index| index == NEW_LINE).count();
+        !self.layout.is_empty() && new * 2 <= self.layout.len()
+    }
+}
+
+/// 增量记录中保存的行（与布局一一对应）里属于新增的部分
+pub fn delta_new_lines(lines: &[OcrLine], layout: &[i64]) -> Vec<OcrLine> {
+    layout
+        .iter()
+        .zip(lines)
+        .filter(|(index, _)| **index == NEW_LINE)
+        .map(|(_, line)| line.clone())
+        .collect()
+}
+
+/// 没有逐行结果的旧记录按换行拆成行
+pub fn text_lines(text: &str, lines: &[OcrLine]) -> Vec<OcrLine> {
+    if !lines.is_empty() {
+        return lines.to_vec();
+    }
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| OcrLine {
+            text: line.to_string(),
+            points: Vec::new(),
+            confidence: 1.0,
+        })
+        .collect()
+}
+
+/// 没有逐行结果的引擎只保存新增行的文本，按布局展开成与布局一一对应的行，引用基准的行为空占位
+pub fn text_delta_lines(text: &str, layout: &[i64]) -> Vec<OcrLine> {
+    let placeholder = || OcrLine { text: String::new(), points: Vec::new(), confidence: 1.0 };
+    let mut new_lines = text_lines(text, &[]).into_iter();
+    layout
+        .iter()
+        .map(|&index| match index {
+            NEW_LINE => new_lines.next().unwrap_or_else(placeholder),
+            _ => placeholder(),
+        })
+        .collect()
+}
+
+/// 对比两次识别结果，文本相同（忽略首尾空白）的行视为未变化
+pub fn diff_ocr_lines(base: &[OcrLine], current: &[OcrLine]) -> OcrDelta {
+    let mut unused: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, line) in base.iter().enumerate().rev() {
+        unused.entry(line.text.trim()).or_default().push(index);
+    }
+
+    let mut delta = OcrDelta::default();
+    for line in current {
+        match unused.get_mut(line.text.trim()).and_then(|indexes| indexes.pop()) {
+            Some(index) => {
+                delta.layout.push(index as i64);
+                delta.lines.push(OcrLine { text: String::new(), ..line.clone() });
+            }
+            None => {
+                delta.layout.push(NEW_LINE);
+                delta.lines.push(line.clone());
+            }
+        }
+    }
+    delta
+}
+
+/// 由基准和增量还原完整结果；基准行号越界（基准已损坏）时跳过该行
+///
+/// `lines` 与布局一一对应，引用基准的行只取基准的文本，位置和置信度用本次的。
+pub fn apply_ocr_delta(base: &[OcrLine], lines: &[OcrLine], layout: &[i64]) -> Vec<OcrLine> {
+    let base_line = |index: i64| usize::try_from(index).ok().and_then(|i| base.get(i));
+    layout
+        .iter()
+        .zip(lines)
+        .filter_map(|(&index, line)| match index {
+            NEW_LINE => Some(line.clone()),
+            index => base_line(index).map(|b| OcrLine { text: b.text.clone(), ..line.clone() }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 宽 100、高 10、位于 y 处的一行
+    pub(crate) fn line(text: &str, y: f32) -> OcrLine {
+        OcrLine {
+            text: text.to_string(),
+            points: vec![[0.0, y], [100.0, y], [100.0, y + 10.0], [0.0, y + 10.0]],
+            confidence: 0.9,
+        }
+    }
+
+    fn texts(lines: &[OcrLine]) -> Vec<&str> {
+        lines.iter().map(|l| l.text.as_str()).collect()
+    }
+
+    #[test]
+    fn diff_matches_lines_by_trimmed_text() {
+        let base = [line("标题", 0.0), line("正文", 10.0), line("页脚", 20.0)];
+        let current = [line("正文 ", 0.0), line("标题", 10.0), line("新内容", 20.0), line("标题", 30.0)];
+        let delta = diff_ocr_lines(&base, &current);
+
+        // 重复的行只能引用基准中同样次数，多出的视为新增
+        assert_eq!(delta.layout, [1, 0, NEW_LINE, NEW_LINE]);
+        assert_eq!(texts(&delta.new_lines()), ["新内容", "标题"]);
+        // 引用基准的行只保留本次位置，不重复保存文本
+        assert_eq!(texts(&delta.lines), ["", "", "新内容", "标题"]);
+        assert_eq!(delta.lines[0].bounds(), current[0].bounds());
+    }
+
+    #[test]
+    fn delta_worthwhile_when_at_most_half_new() {
+        let base = [line("a", 0.0), line("b", 10.0), line("c", 20.0)];
+        let delta = diff_ocr_lines(&base, &[line("a", 0.0), line("b", 10.0), line("x", 20.0), line("y", 30.0)]);
+        assert!(delta.is_worthwhile());
+        assert!(!diff_ocr_lines(&base, &[]).is_worthwhile());
+    }
+
+    #[test]
+    fn apply_restores_text_with_current_boxes() {
+        let base = [line("a", 0.0), line("b", 10.0), line("c", 20.0)];
+        // 窗口滚动后同样的行出现在不同位置
+        let current = [line("b", 50.0), line("c", 60.0), line("d", 70.0)];
+        let delta = diff_ocr_lines(&base, &current);
+
+        let restored = apply_ocr_delta(&base, &delta.lines, &delta.layout);
+        assert_eq!(texts(&restored), ["b", "c", "d"]);
+        let boxes: Vec<_> = restored.iter().map(|l| l.bounds()).collect();
+        let expected: Vec<_> = current.iter().map(|l| l.bounds()).collect();
+        assert_eq!(boxes, expected);
+    }
+
+    #[test]
+    fn text_only_delta_expands_to_layout() {
+        let base = text_lines("a\nb", &[]);
+        let layout = [1, NEW_LINE, 0];
+        let lines = text_delta_lines("x", &layout);
+        assert_eq!(texts(&lines), ["", "x", ""]);
+        assert_eq!(texts(&delta_new_lines(&lines, &layout)), ["x"]);
+        assert_eq!(texts(&apply_ocr_delta(&base, &lines, &layout)), ["b", "x", "a"]);
+        assert!(delta_new_lines(&[], &layout).is_empty());
+    }
+
+    #[test]
+    fn apply_skips_out_of_range_base_lines() {
+        let base = [line("a", 0.0)];
+        let lines = [line("", 0.0), line("", 10.0), line("x", 20.0)];
+        let restored = apply_ocr_delta(&base, &lines, &[0, 5, NEW_LINE]);
+        assert_eq!(texts(&restored), ["a", "x"]);
+    }
+}
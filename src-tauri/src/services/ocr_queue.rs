@@ -0,0 +1,281 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::oneshot;
+use crate::models::AppConfig;
+use crate::services::ocr::{OcrEngine, OcrResult, OcrSettings, kill_ocr_processes};
+
+/// 队列容量，满时拒绝新任务
+const QUEUE_CAPACITY: usize = 64;
+/// 失败后最多重试次数（不含首次）
+const MAX_RETRIES: u32 = 2;
+/// 首次重试前的等待，之后每次翻倍
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// 任务优先级：补录等后台任务用低优先级，只在没有普通任务时执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OcrPriority {
+    Normal,
+    Low,
+}
+
+/// OCR任务
+struct OcrJob {
+    image_path: PathBuf,
+    priority: OcrPriority,
+    timeout: Duration,
+    /// 已重试次数
+    retries: u32,
+    /// 重试前的等待截止时刻
+    not_before: Instant,
+    reply: oneshot::Sender<Result<OcrResult, String>>,
+}
+
+/// OCR队列状态（通过状态监听推送给前端）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OcrQueueStatus {
+    /// 排队中的任务数（含等待重试的）
+    pub pending: usize,
+    /// 正在识别的图片
+    pub running: Option<String>,
+    pub completed: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    pub retried: u64,
+}
+
+#[derive(Default)]
+struct QueueState {
+    jobs: VecDeque<OcrJob>,
+    status: OcrQueueStatus,
+    /// 正在执行的任务已被取消，结果丢弃
+    cancel_running: bool,
+    /// 当前引擎设置，工作线程发现变化时重建引擎
+    settings: Option<OcrSettings>,
+    worker_started: bool,
+    shutdown: bool,
+}
+
+static QUEUE: Lazy<(Mutex<QueueState>, Condvar)> = Lazy::new(|| (Mutex::new(QueueState::default()), Condvar::new()));
+
+type StatusListener = Box<dyn Fn(OcrQueueStatus) + Send + Sync>;
+
+static STATUS_LISTENER: Mutex<Option<StatusListener>> = Mutex::new(None);
+
+/// 设置队列状态监听（状态变化时调用）
+pub fn set_ocr_status_listener(listener: impl Fn(OcrQueueStatus) + Send + Sync + 'static) {
+    *STATUS_LISTENER.lock().unwrap() = Some(Box::new(listener));
+}
+
+/// 当前队列状态
+pub fn ocr_queue_status() -> OcrQueueStatus {
+    QUEUE.0.lock().unwrap().status.clone()
+}
+
+fn notify_status() {
+    let status = ocr_queue_status();
+    if let Some(listener) = STATUS_LISTENER.lock().unwrap().as_ref() {
+        listener(status);
+    }
+}
+
+/// 识别图片文本
+pub async fn extract_text_from_image(config: &AppConfig, image_path: &str) -> Result<String, String> {
+    recognize_image(config, image_path).await.map(|result| result.text)
+}
+
+/// 提交到OCR队列并等待结果，返回全文和逐行位置
+/// 丢弃返回的 future 即取消尚未开始的任务
+pub async fn recognize_image(config: &AppConfig, image_path: &str) -> Result<OcrResult, String> {
+    recognize_image_with_priority(config, image_path, OcrPriority::Normal).await
+}
+
+/// 按指定优先级提交到OCR队列并等待结果
+pub async fn recognize_image_with_priority(
+    config: &AppConfig,
+    image_path: &str,
+    priority: OcrPriority,
+) -> Result<OcrResult, String> {
+    let path = Path::new(image_path);
+    if !path.exists() {
+        return Err(format!("图片文件不存在: {}", image_path));
+    }
+
+    let (reply, result) = oneshot::channel();
+    {
+        let (lock, cvar) = &*QUEUE;
+        let mut state = lock.lock().unwrap();
+        if state.shutdown {
+            return Err("OCR队列已关闭".to_string());
+        }
+        if state.jobs.len() >= QUEUE_CAPACITY {
+            return Err("OCR队列已满，请稍后再试".to_string());
+        }
+        if state.settings.is_none() {
+            state.settings = Some(OcrSettings::from_config(config));
+        }
+        if !state.worker_started {
+            std::thread::spawn(worker_loop);
+            state.worker_started = true;
+        }
+        state.jobs.push_back(OcrJob {
+            image_path: path.to_path_buf(),
+            priority,
+            timeout: Duration::from_secs(config.ocr_timeout_sec.max(1)),
+            retries: 0,
+            not_before: Instant::now(),
+            reply,
+        });
+        state.status.pending = state.jobs.len();
+        cvar.notify_one();
+    }
+    notify_status();
+
+    result.await.map_err(|_| "OCR任务已取消".to_string())?
+}
+
+/// 取消所有排队中和正在执行的任务，返回取消的数量
+///
+/// 正在执行的任务通过结束识别进程中止（RapidOCR 常驻进程在下个任务时重新启动）。
+pub fn cancel_ocr_jobs() -> usize {
+    let (cancelled, stop_running) = {
+        let mut state = QUEUE.0.lock().unwrap();
+        let jobs: Vec<OcrJob> = state.jobs.drain(..).collect();
+        let mut cancelled = jobs.len();
+        for job in jobs {
+            let _ = job.reply.send(Err("OCR任务已取消".to_string()));
+        }
+        let stop_running = state.status.running.is_some() && !state.cancel_running;
+        if stop_running {
+            state.cancel_running = true;
+            cancelled += 1;
+        }
+        state.status.cancelled += cancelled as u64;
+        state.status.pending = 0;
+        (cancelled, stop_running)
+    };
+    if stop_running {
+        kill_ocr_processes();
+    }
+    notify_status();
+    cancelled
+}
+
+/// 应用新的OCR设置（保存配置时调用）
+/// 设置有变化时结束当前识别进程，正在执行的任务按新设置重试，后续任务使用新引擎
+pub fn apply_ocr_settings(config: &AppConfig) {
+    let settings = OcrSettings::from_config(config);
+    let changed = {
+        let mut state = QUEUE.0.lock().unwrap();
+        let changed = state.settings.as_ref().is_some_and(|current| *current != settings);
+        state.settings = Some(settings);
+        changed
+    };
+    if changed {
+        kill_ocr_processes();
+    }
+}
+
+/// 关闭队列并结束OCR子进程（程序退出时调用）
+pub fn shutdown_ocr_queue() {
+    QUEUE.0.lock().unwrap().shutdown = true;
+    cancel_ocr_jobs();
+    QUEUE.1.notify_all();
+    kill_ocr_processes();
+}
+
+/// 取出下一个可执行的任务及当前引擎设置；队列关闭时返回 None
+fn next_job() -> Option<(OcrJob, OcrSettings)> {
+    let (lock, cvar) = &*QUEUE;
+    let mut state = lock.lock().unwrap();
+    loop {
+        if state.shutdown {
+            return None;
+        }
+
+        // 等待方已放弃的任务直接丢弃
+        let before = state.jobs.len();
+        state.jobs.retain(|job| !job.reply.is_closed());
+        let abandoned = before - state.jobs.len();
+        state.status.cancelled += abandoned as u64;
+
+        // 普通任务优先，其次按入队顺序
+        let now = Instant::now();
+        let ready = |job: &OcrJob, priority| job.priority == priority && job.not_before <= now;
+        let next = state
+            .jobs
+            .iter()
+            .position(|job| ready(job, OcrPriority::Normal))
+            .or_else(|| state.jobs.iter().position(|job| ready(job, OcrPriority::Low)));
+        if let Some(index) = next {
+            let job = state.jobs.remove(index)?;
+            let settings = state.settings.clone()?;
+            state.status.pending = state.jobs.len();
+            state.status.running = Some(job.image_path.to_string_lossy().to_string());
+            state.cancel_running = false;
+            drop(state);
+            notify_status();
+            return Some((job, settings));
+        }
+        state.status.pending = state.jobs.len();
+
+        // 只剩等待重试的任务时睡到最早的重试时刻
+        state = match state.jobs.iter().map(|job| job.not_before).min() {
+            Some(wake) => cvar.wait_timeout(state, wake - now).unwrap().0,
+            None => cvar.wait(state).unwrap(),
+        };
+    }
+}
+
+/// 记录任务结果，失败且可重试时放回队列
+fn finish_job(mut job: OcrJob, result: Result<OcrResult, String>) {
+    {
+        let (lock, cvar) = &*QUEUE;
+        let mut state = lock.lock().unwrap();
+        state.status.running = None;
+        let cancelled = std::mem::take(&mut state.cancel_running);
+
+        match result {
+            _ if cancelled => {
+                let _ = job.reply.send(Err("OCR任务已取消".to_string()));
+            }
+            Ok(result) => {
+                state.status.completed += 1;
+                let _ = job.reply.send(Ok(result));
+            }
+            Err(e) if job.retries < MAX_RETRIES && !state.shutdown && job.image_path.exists() => {
+                eprintln!("OCR识别失败，稍后重试 {}: {}", job.image_path.display(), e);
+                job.not_before = Instant::now() + RETRY_BACKOFF * 2u32.pow(job.retries);
+                job.retries += 1;
+                state.status.retried += 1;
+                state.jobs.push_back(job);
+                state.status.pending = state.jobs.len();
+                cvar.notify_one();
+            }
+            Err(e) => {
+                state.status.failed += 1;
+                let _ = job.reply.send(Err(e));
+            }
+        }
+    }
+    notify_status();
+}
+
+/// OCR工作线程：串行执行任务，引擎设置变化时重建引擎
+fn worker_loop() {
+    let mut engine: Option<(OcrSettings, Box<dyn OcrEngine>)> = None;
+    while let Some((job, settings)) = next_job() {
+        if engine.as_ref().is_none_or(|(current, _)| *current != settings) {
+            engine = Some((settings.clone(), settings.create_engine()));
+        }
+        let result = match engine.as_mut() {
+            Some((_, engine)) => engine.recognize(&job.image_path, job.timeout),
+            None => Err("OCR引擎未创建".to_string()),
+        };
+        finish_job(job, result);
+    }
+    // 引擎随线程退出释放，常驻进程一并结束
+}
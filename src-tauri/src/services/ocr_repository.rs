@@ -0,0 +1,661 @@
+use crate::error::{AppError, Result};
+use crate::models::AppConfig;
+use crate::services::ocr::OcrLine;
+use crate::services::ocr_diff::{apply_ocr_delta, delta_new_lines, diff_ocr_lines, text_delta_lines, text_lines};
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::fs;
+
+/// ocr_records 表结构版本
+/// 1: 只有全文；2: 增加逐行结果、增量字段和索引
+const SCHEMA_VERSION: i64 = 2;
+
+/// 增量链的最大长度，超过后保存完整记录，避免还原时回溯太远
+const MAX_DELTA_CHAIN: usize = 30;
+
+const COLUMNS: &str = "id, timestamp, image_path, text, app_name, lines_json, base_id, layout_json";
+
+/// OCR记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcrRecord {
+    #[serde(default)]
+    pub id: i64,
+    pub timestamp: String,
+    pub image_path: String,
+    pub text: String,
+    pub app_name: Option<String>,
+    /// 逐行文本、位置和置信度（旧记录为空）
+    #[serde(default)]
+    pub lines: Vec<OcrLine>,
+    /// 增量记录所基于的上一条记录（完整记录为空）
+    #[serde(default)]
+    pub base_id: Option<i64>,
+}
+
+impl OcrRecord {
+    /// 去掉置信度低于 `min` 的行并重建全文；没有逐行结果的旧记录原样返回
+    pub fn drop_low_confidence(mut self, min: f32) -> Self {
+        if self.lines.is_empty() {
+            return self;
+        }
+        self.lines.retain(|line| line.confidence >= min);
+        self.text = join_lines(&self.lines);
+        self
+    }
+}
+
+/// OCR记录查询条件，时间为 `YYYY-MM-DDTHH:MM:SS`（含两端）
+#[derive(Debug, Clone, Default)]
+pub struct OcrQuery {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub app_name: Option<String>,
+    pub image_path: Option<String>,
+    /// 增量记录只返回相对上一张新增的行，否则还原完整文本
+    pub delta_only: bool,
+}
+
+/// 库中的一行；增量记录的文本和 `record.lines` 只含新增的行
+struct StoredOcr {
+    record: OcrRecord,
+    /// 增量记录每一行的来源（见 [`crate::services::OcrDelta`]）
+    layout: Vec<i64>,
+    /// 库中保存的逐行结果，增量记录与布局一一对应（含引用基准的行的位置）；没有逐行结果的引擎为空
+    lines: Vec<OcrLine>,
+}
+
+/// OCR记录仓库（按日期存放在当日 events.db 的 ocr_records 表）
+pub struct OcrRepository {
+    conn: Connection,
+}
+
+impl OcrRepository {
+    /// 打开指定日期的OCR记录，不存在时创建
+    pub fn open(config: &AppConfig, date: &str) -> Result<Self> {
+        let data_dir = config.get_date_dir(date);
+        fs::create_dir_all(&data_dir)?;
+        Self::with_connection(Connection::open(data_dir.join("events.db"))?)
+    }
+
+    /// 打开指定日期的OCR记录，当天没有数据库时返回 None
+    pub fn open_existing(config: &AppConfig, date: &str) -> Result<Option<Self>> {
+        let db_path = config.get_date_dir(date).join("events.db");
+        if !db_path.exists() {
+            return Ok(None);
+        }
+        Self::with_connection(Connection::open(db_path)?).map(Some)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        migrate(&conn)?;
+        Ok(Self { conn })
+    }
+
+    /// 写入记录，返回行ID；与同一应用上一条记录大部分相同时只保存新增的行
+    pub fn insert(&self, record: &OcrRecord) -> Result<i64> {
+        let current = text_lines(&record.text, &record.lines);
+        let delta = match record.app_name.as_deref().filter(|app| !app.is_empty()) {
+            Some(app) => self
+                .previous_lines(app, &record.timestamp)?
+                .map(|(base_id, base)| (base_id, diff_ocr_lines(&base, &current)))
+                .filter(|(_, delta)| delta.is_worthwhile()),
+            None => None,
+        };
+
+        let (text, lines, base_id, layout) = match delta {
+            Some((base_id, delta)) => {
+                // 文本只存新增行；逐行位置每行都存，没有逐行结果的引擎只保存文本
+                let lines = if record.lines.is_empty() { Vec::new() } else { delta.lines.clone() };
+                let layout = serde_json::to_string(&delta.layout)?;
+                (join_lines(&delta.new_lines()), lines, Some(base_id), Some(layout))
+            }
+            None => (record.text.clone(), record.lines.clone(), None, None),
+        };
+        let lines = (!lines.is_empty()).then(|| serde_json::to_string(&lines)).transpose()?;
+        self.conn.execute(
+            "INSERT INTO ocr_records (timestamp, image_path, text, app_name, lines_json, base_id, layout_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![record.timestamp, record.image_path, text, record.app_name, lines, base_id, layout],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 按条件查询（按时间升序）
+    pub fn query(&self, query: &OcrQuery) -> Result<Vec<OcrRecord>> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut args: Vec<String> = Vec::new();
+        if let Some(start) = &query.start {
+            conditions.push("timestamp >= ?");
+            args.push(start.clone());
+        }
+        if let Some(end) = &query.end {
+            conditions.push("timestamp <= ?");
+            args.push(end.clone());
+        }
+        if let Some(app) = &query.app_name {
+            conditions.push("app_name = ?");
+            args.push(app.clone());
+        }
+        if let Some(path) = &query.image_path {
+            conditions.push("image_path = ?");
+            args.push(path.clone());
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {} FROM ocr_records {} ORDER BY timestamp, id", COLUMNS, where_clause))?;
+        let stored = stmt
+            .query_map(params_from_iter(args.iter()), read_stored)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let order: Vec<i64> = stored.iter().map(|s| s.record.id).collect();
+        let mut rows: HashMap<i64, StoredOcr> = stored.into_iter().map(|s| (s.record.id, s)).collect();
+        let mut cache = HashMap::new();
+        let mut records = Vec::with_capacity(order.len());
+        for id in order {
+            let Some(mut record) = rows.get(&id).map(|s| s.record.clone()) else {
+                continue;
+            };
+            if record.base_id.is_some() && !query.delta_only {
+                record.lines = self.resolve_lines(id, &mut rows, &mut cache)?;
+                record.text = join_lines(&record.lines);
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// 当天全部记录
+    pub fn all(&self, delta_only: bool) -> Result<Vec<OcrRecord>> {
+        self.query(&OcrQuery { delta_only, ..Default::default() })
+    }
+
+    /// 时间范围内的记录（完整文本）
+    pub fn by_range(&self, start: &str, end: &str) -> Result<Vec<OcrRecord>> {
+        self.query(&OcrQuery {
+            start: Some(start.to_string()),
+            end: Some(end.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// 指定应用的记录（完整文本）
+    pub fn by_app(&self, app_name: &str) -> Result<Vec<OcrRecord>> {
+        self.query(&OcrQuery {
+            app_name: Some(app_name.to_string()),
+            ..Default::default()
+        })
+    }
+
+    /// 指定截图的记录（完整文本）
+    pub fn by_image_path(&self, image_path: &str) -> Result<Option<OcrRecord>> {
+        Ok(self
+            .query(&OcrQuery {
+                image_path: Some(image_path.to_string()),
+                ..Default::default()
+            })?
+            .pop())
+    }
+
+    /// 截图是否已有OCR记录
+    pub fn has_record(&self, image_path: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM ocr_records WHERE image_path = ?1)",
+            [image_path],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// 已有OCR记录的全部截图路径
+    pub fn paths_with_records(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT DISTINCT image_path FROM ocr_records")?;
+        let paths = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<String>, _>>()?;
+        Ok(paths)
+    }
+
+    /// 截图文件被重新编码后更新记录中的路径
+    pub fn relocate(&self, old_path: &str, new_path: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE ocr_records SET image_path = ?1 WHERE image_path = ?2",
+            params![new_path, old_path],
+        )?;
+        Ok(())
+    }
+
+    /// 删除截图对应的记录，返回删除的行数
+    ///
+    /// 以被删记录为基准的增量记录先还原为完整记录，保证其余记录仍可还原。
+    pub fn delete_by_image_path(&self, image_path: &str) -> Result<usize> {
+        let ids: Vec<i64> = {
+            let mut stmt = self.conn.prepare("SELECT id FROM ocr_records WHERE image_path = ?1")?;
+            stmt.query_map([image_path], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?
+        };
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut rows = HashMap::new();
+        let mut cache = HashMap::new();
+        for id in &ids {
+            let dependents: Vec<i64> = {
+                let mut stmt = self.conn.prepare("SELECT id FROM ocr_records WHERE base_id = ?1")?;
+                stmt.query_map([id], |row| row.get(0))?
+                    .collect::<std::result::Result<_, _>>()?
+            };
+            for dependent in dependents.into_iter().filter(|d| !ids.contains(d)) {
+                let lines = self.resolve_lines(dependent, &mut rows, &mut cache)?;
+                self.conn.execute(
+                    "UPDATE ocr_records SET text = ?1, lines_json = ?2, base_id = NULL, layout_json = NULL WHERE id = ?3",
+                    params![join_lines(&lines), serde_json::to_string(&lines)?, dependent],
+                )?;
+            }
+        }
+        let deleted = self.conn.execute("DELETE FROM ocr_records WHERE image_path = ?1", [image_path])?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// 同一应用在 `timestamp` 之前最近一条记录的ID和完整逐行结果；增量链过长时返回 None
+    fn previous_lines(&self, app_name: &str, timestamp: &str) -> Result<Option<(i64, Vec<OcrLine>)>> {
+        let id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM ocr_records WHERE app_name = ?1 AND timestamp <= ?2
+                 ORDER BY timestamp DESC, id DESC LIMIT 1",
+                params![app_name, timestamp],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let mut rows = HashMap::new();
+        let mut next = Some(id);
+        while let Some(current) = next {
+            if rows.len() >= MAX_DELTA_CHAIN {
+                return Ok(None);
+            }
+            let Some(stored) = self.load(current)? else {
+                break;
+            };
+            next = stored.record.base_id;
+            rows.insert(current, stored);
+        }
+        let lines = self.resolve_lines(id, &mut rows, &mut HashMap::new())?;
+        Ok(Some((id, lines)))
+    }
+
+    fn load(&self, id: i64) -> Result<Option<StoredOcr>> {
+        Ok(self
+            .conn
+            .query_row(&format!("SELECT {} FROM ocr_records WHERE id = ?1", COLUMNS), [id], read_stored)
+            .optional()?)
+    }
+
+    /// 沿基准链还原记录的完整逐行结果
+    ///
+    /// `rows` 中没有的基准按需从库中读取，已还原的记录缓存在 `cache` 中；基准已不存在时按空基准处理。
+    fn resolve_lines(
+        &self,
+        id: i64,
+        rows: &mut HashMap<i64, StoredOcr>,
+        cache: &mut HashMap<i64, Vec<OcrLine>>,
+    ) -> Result<Vec<OcrLine>> {
+        let mut chain = Vec::new();
+        let mut base = Vec::new();
+        let mut next = Some(id);
+        while let Some(current) = next {
+            if let Some(lines) = cache.get(&current) {
+                base = lines.clone();
+                break;
+            }
+            if chain.contains(&current) {
+                break;
+            }
+            if let Entry::Vacant(entry) = rows.entry(current) {
+                match self.load(current)? {
+                    Some(stored) => {
+                        entry.insert(stored);
+                    }
+                    None => break,
+                }
+            }
+            chain.push(current);
+            next = rows[&current].record.base_id;
+        }
+
+        for current in chain.into_iter().rev() {
+            let stored = &rows[&current];
+            base = match stored.record.base_id {
+                // 没有逐行结果的引擎只保存了新增行的文本
+                Some(_) if stored.lines.is_empty() => {
+                    apply_ocr_delta(&base, &text_delta_lines(&stored.record.text, &stored.layout), &stored.layout)
+                }
+                Some(_) => apply_ocr_delta(&base, &stored.lines, &stored.layout),
+                None => text_lines(&stored.record.text, &stored.lines),
+            };
+            cache.insert(current, base.clone());
+        }
+        Ok(base)
+    }
+}
+
+fn join_lines(lines: &[OcrLine]) -> String {
+    lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n")
+}
+
+fn read_stored(row: &Row) -> rusqlite::Result<StoredOcr> {
+    let lines: Vec<OcrLine> = row
+        .get::<_, Option<String>>(5)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let layout: Vec<i64> = row
+        .get::<_, Option<String>>(7)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let base_id: Option<i64> = row.get(6)?;
+    Ok(StoredOcr {
+        record: OcrRecord {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            image_path: row.get(2)?,
+            text: row.get(3)?,
+            app_name: row.get(4)?,
+            lines: match base_id {
+                Some(_) => delta_new_lines(&lines, &layout),
+                None => lines.clone(),
+            },
+            base_id,
+        },
+        layout,
+        lines,
+    })
+}
+
+/// 建表并升级到当前版本；版本记录在 schema_versions 表（同库的其他表共用）
+fn migrate(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_versions (
+            name TEXT PRIMARY KEY,
+            version INTEGER NOT NULL
+        )",
+    )?;
+    let version: i64 = conn
+        .query_row("SELECT version FROM schema_versions WHERE name = 'ocr_records'", [], |row| row.get(0))
+        .optional()?
+        .unwrap_or(0);
+    if version > SCHEMA_VERSION {
+        return Err(AppError::SchemaVersion {
+            table: "ocr_records",
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ocr_records (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp TEXT NOT NULL,
+            image_path TEXT NOT NULL,
+            text TEXT NOT NULL,
+            app_name TEXT,
+            lines_json TEXT,
+            base_id INTEGER,
+            layout_json TEXT
+        )",
+    )?;
+    // 版本 1 的表（或没有版本记录的旧表）缺少的字段
+    for (column, kind) in [("lines_json", "TEXT"), ("base_id", "INTEGER"), ("layout_json", "TEXT")] {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('ocr_records') WHERE name = ?1)",
+            [column],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            tx.execute(&format!("ALTER TABLE ocr_records ADD COLUMN {} {}", column, kind), [])?;
+        }
+    }
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_ocr_records_timestamp ON ocr_records(timestamp);
+         CREATE INDEX IF NOT EXISTS idx_ocr_records_app ON ocr_records(app_name, timestamp);
+         CREATE INDEX IF NOT EXISTS idx_ocr_records_image_path ON ocr_records(image_path);
+         CREATE INDEX IF NOT EXISTS idx_ocr_records_base ON ocr_records(base_id);",
+    )?;
+    tx.execute(
+        "INSERT OR REPLACE INTO schema_versions (name, version) VALUES ('ocr_records', ?1)",
+        [SCHEMA_VERSION],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ocr_diff::tests::line;
+
+    fn record(time: &str, texts: &[&str]) -> OcrRecord {
+        let lines: Vec<OcrLine> = texts.iter().enumerate().map(|(i, t)| line(t, i as f32 * 20.0)).collect();
+        OcrRecord {
+            id: 0,
+            timestamp: format!("2024-01-01T{}", time),
+            image_path: format!("screenshots/{}.webp", time.replace(':', "")),
+            text: join_lines(&lines),
+            app_name: Some("Code".to_string()),
+            lines,
+            base_id: None,
+        }
+    }
+
+    /// 同一应用逐步修改的三张截图
+    fn edits() -> [OcrRecord; 3] {
+        [
+            record("09:00:00", &["fn main() {", "let a = 1;", "let b = 2;", "println!(a);", "}"]),
+            record("09:01:00", &["fn main() {", "let a = 1;", "let b = 2;", "println!(b);", "}"]),
+            record("09:02:00", &["fn main() {", "let a = 1;", "let c = 3;", "println!(b);", "}"]),
+        ]
+    }
+
+    /// 一条完整记录加两级增量：09:00 <- 09:01 <- 09:02
+    fn chained() -> (OcrRepository, [OcrRecord; 3]) {
+        let repo = OcrRepository::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let records = edits();
+        for r in &records {
+            repo.insert(r).unwrap();
+        }
+        (repo, records)
+    }
+
+    fn full_text(repo: &OcrRepository, record: &OcrRecord) -> String {
+        repo.by_image_path(&record.image_path).unwrap().unwrap().text
+    }
+
+    #[test]
+    fn similar_records_are_stored_as_deltas() {
+        let (repo, records) = chained();
+        let stored = repo.all(true).unwrap();
+        assert_eq!(stored[0].base_id, None);
+        assert_eq!(stored[1].base_id, Some(stored[0].id));
+        assert_eq!(stored[2].base_id, Some(stored[1].id));
+        assert_eq!(stored[1].text, "println!(b);");
+        for r in &records {
+            assert_eq!(full_text(&repo, r), r.text);
+        }
+    }
+
+    #[test]
+    fn deleting_base_rebases_dependents() {
+        let (repo, records) = chained();
+        assert_eq!(repo.delete_by_image_path(&records[0].image_path).unwrap(), 1);
+
+        let stored = repo.all(true).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].base_id, None);
+        assert_eq!(stored[0].text, records[1].text);
+        assert_eq!(stored[0].lines.len(), 5);
+        assert_eq!(stored[0].lines[3].points, records[1].lines[3].points);
+        assert_eq!(stored[1].base_id, Some(stored[0].id));
+        assert_eq!(full_text(&repo, &records[1]), records[1].text);
+        assert_eq!(full_text(&repo, &records[2]), records[2].text);
+    }
+
+    #[test]
+    fn deleting_middle_of_chain_keeps_tail_resolvable() {
+        let (repo, records) = chained();
+        assert_eq!(repo.delete_by_image_path(&records[1].image_path).unwrap(), 1);
+
+        let stored = repo.all(true).unwrap();
+        assert_eq!(stored[1].base_id, None);
+        assert_eq!(full_text(&repo, &records[0]), records[0].text);
+        assert_eq!(full_text(&repo, &records[2]), records[2].text);
+        assert_eq!(repo.delete_by_image_path(&records[1].image_path).unwrap(), 0);
+    }
+
+    #[test]
+    fn text_only_records_round_trip_as_deltas() {
+        let repo = OcrRepository::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let records: Vec<OcrRecord> = [["a", "b", "c"], ["b", "x", "c"]]
+            .iter()
+            .enumerate()
+            .map(|(i, texts)| OcrRecord { lines: Vec::new(), ..record(&format!("09:0{}:00", i), texts) })
+            .collect();
+        for r in &records {
+            repo.insert(r).unwrap();
+        }
+
+        let stored = repo.all(true).unwrap();
+        assert_eq!(stored[1].base_id, Some(stored[0].id));
+        assert_eq!(stored[1].text, "x");
+        assert!(stored[1].lines.is_empty());
+        assert_eq!(full_text(&repo, &records[1]), "b\nx\nc");
+    }
+
+    #[test]
+    fn drop_low_confidence_rebuilds_text_from_kept_lines() {
+        let mut r = record("09:00:00", &["a", "b", "c"]);
+        r.lines[1].confidence = 0.3;
+        let filtered = r.drop_low_confidence(0.5);
+        assert_eq!(filtered.text, "a\nc");
+        assert_eq!(filtered.lines.len(), 2);
+
+        // 没有逐行结果的旧记录无法按行过滤
+        let legacy = OcrRecord { lines: Vec::new(), text: "a\nb".to_string(), ..record("09:01:00", &[]) };
+        let kept = legacy.drop_low_confidence(0.95);
+        assert_eq!(kept.text, "a\nb");
+        assert!(kept.lines.is_empty());
+    }
+
+    #[test]
+    fn unversioned_table_gains_lines_json() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ocr_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                image_path TEXT NOT NULL,
+                text TEXT NOT NULL,
+                app_name TEXT
+            );
+            INSERT INTO ocr_records (timestamp, image_path, text, app_name)
+            VALUES ('2024-01-01T08:00:00', 'screenshots/old.webp', 'old text', 'Code');",
+        )
+        .unwrap();
+        let repo = OcrRepository::with_connection(conn).unwrap();
+
+        let old = repo.by_image_path("screenshots/old.webp").unwrap().unwrap();
+        assert_eq!(old.text, "old text");
+        assert!(old.lines.is_empty());
+        let r = record("09:00:00", &["a", "b"]);
+        repo.insert(&r).unwrap();
+        assert_eq!(repo.by_image_path(&r.image_path).unwrap().unwrap().lines.len(), 2);
+    }
+
+    #[test]
+    fn version_1_table_is_upgraded() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_versions (name TEXT PRIMARY KEY, version INTEGER NOT NULL);
+            INSERT INTO schema_versions (name, version) VALUES ('ocr_records', 1);
+            CREATE TABLE ocr_records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                image_path TEXT NOT NULL,
+                text TEXT NOT NULL,
+                app_name TEXT,
+                lines_json TEXT
+            );",
+        )
+        .unwrap();
+        let repo = OcrRepository::with_connection(conn).unwrap();
+
+        let version: i64 = repo
+            .conn
+            .query_row("SELECT version FROM schema_versions WHERE name = 'ocr_records'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        for r in &edits() {
+            repo.insert(r).unwrap();
+        }
+        assert_eq!(repo.all(true).unwrap()[1].base_id, Some(1));
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE schema_versions (name TEXT PRIMARY KEY, version INTEGER NOT NULL);
+            INSERT INTO schema_versions (name, version) VALUES ('ocr_records', 3);",
+        )
+        .unwrap();
+        match OcrRepository::with_connection(conn) {
+            Err(AppError::SchemaVersion { table, found, supported }) => {
+                assert_eq!((table, found, supported), ("ocr_records", 3, SCHEMA_VERSION));
+            }
+            other => panic!("expected SchemaVersion error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn range_and_app_queries_resolve_full_text() {
+        let (repo, records) = chained();
+        let mut other = record("09:01:30", &["inbox"]);
+        other.app_name = Some("Mail".to_string());
+        repo.insert(&other).unwrap();
+
+        let range = repo.by_range("2024-01-01T09:01:00", "2024-01-01T09:02:00").unwrap();
+        let texts: Vec<&str> = range.iter().map(|r| r.text.as_str()).collect();
+        assert_eq!(texts, [records[1].text.as_str(), "inbox", records[2].text.as_str()]);
+
+        let code = repo.by_app("Code").unwrap();
+        assert_eq!(code.len(), 3);
+        assert_eq!(code[2].text, records[2].text);
+        assert_eq!(repo.by_app("Mail").unwrap()[0].image_path, other.image_path);
+        assert!(repo.by_app("Firefox").unwrap().is_empty());
+    }
+
+    #[test]
+    fn record_lookups_by_path() {
+        let (repo, records) = chained();
+        assert!(repo.has_record(&records[0].image_path).unwrap());
+        assert!(!repo.has_record("screenshots/missing.webp").unwrap());
+        let paths = repo.paths_with_records().unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(records.iter().all(|r| paths.contains(&r.image_path)));
+    }
+}
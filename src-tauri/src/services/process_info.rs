@@ -0,0 +1,184 @@
+use crate::models::ProcessContext;
+
+/// 读取进程上下文（命令行、工作目录）
+#[cfg(target_os = "linux")]
+pub fn get_process_context(pid: u32) -> Option<ProcessContext> {
+    if pid == 0 {
+        return None;
+    }
+    let proc_dir = std::path::PathBuf::from(format!("/proc/{}", pid));
+
+    let name = std::fs::read_to_string(proc_dir.join("comm"))
+        .map(|s| s.trim().to_string())
+        .ok()?;
+
+    // cmdline 以 \0 分隔参数
+    let cmdline = std::fs::read(proc_dir.join("cmdline"))
+        .ok()
+        .map(|raw| {
+            raw.split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|s| !s.is_empty());
+
+    let cwd = std::fs::read_link(proc_dir.join("cwd"))
+        .ok()
+        .map(|p| p.to_string_lossy().to_string());
+
+    Some(ProcessContext {
+        pid,
+        name,
+        cmdline,
+        cwd,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_process_context(_pid: u32) -> Option<ProcessContext> {
+    None
+}
+
+/// /proc/<pid>/stat 中需要的字段
+#[cfg(target_os = "linux")]
+struct ProcStat {
+    ppid: u32,
+    tty_nr: i64,
+    tpgid: i64,
+    start_time: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn read_stat(pid: u32) -> Option<ProcStat> {
+    let content = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // 进程名可能包含空格和括号，从最后一个 ')' 之后开始解析
+    let rest = &content[content.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+
+    Some(ProcStat {
+        ppid: fields.get(1)?.parse().ok()?,
+        tty_nr: fields.get(4)?.parse().ok()?,
+        tpgid: fields.get(5)?.parse().ok()?,
+        start_time: fields.get(19)?.parse().ok()?,
+    })
+}
+
+/// 同一进程的前台子进程在这段时间内复用上次结果（标题变化可能很频繁）
+#[cfg(target_os = "linux")]
+const FOREGROUND_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(1);
+
+#[cfg(target_os = "linux")]
+type ForegroundCache = std::collections::HashMap<u32, (std::time::Instant, Option<ProcessContext>)>;
+
+#[cfg(target_os = "linux")]
+static FOREGROUND_CACHE: std::sync::Mutex<Option<ForegroundCache>> = std::sync::Mutex::new(None);
+
+/// 解析 /proc/<pid>/task/<tid>/children（空格分隔的子进程号）
+#[cfg(target_os = "linux")]
+fn parse_pid_list(content: &str) -> impl Iterator<Item = u32> + '_ {
+    content.split_whitespace().filter_map(|s| s.parse().ok())
+}
+
+/// 进程的直接子进程；内核未提供 children 文件时返回空
+#[cfg(target_os = "linux")]
+fn child_pids(pid: u32) -> Vec<u32> {
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return Vec::new();
+    };
+    let mut children: Vec<u32> = tasks
+        .flatten()
+        .filter_map(|task| std::fs::read_to_string(task.path().join("children")).ok())
+        .flat_map(|content| parse_pid_list(&content).collect::<Vec<_>>())
+        .collect();
+    children.sort_unstable();
+    children.dedup();
+    children
+}
+
+/// 从子进程中选出前台进程：只考虑挂在终端上的，优先正在运行前台作业的，其次最近启动的
+#[cfg(target_os = "linux")]
+fn pick_foreground(children: impl IntoIterator<Item = (u32, ProcStat)>) -> Option<u32> {
+    let mut best: Option<(bool, u64, u32)> = None;
+    for (child_pid, stat) in children {
+        if stat.tty_nr == 0 {
+            continue;
+        }
+        // tpgid 是终端前台进程组，与自身不同说明shell正在运行命令
+        let running_job = stat.tpgid > 0 && stat.tpgid as u32 != child_pid;
+        let target = if running_job { stat.tpgid as u32 } else { child_pid };
+        let candidate = (running_job, stat.start_time, target);
+        if best.is_none_or(|b| (candidate.0, candidate.1) > (b.0, b.1)) {
+            best = Some(candidate);
+        }
+    }
+    best.map(|(_, _, target)| target)
+}
+
+/// 查找窗口进程的前台子进程（终端中正在运行的命令或交互shell）
+///
+/// 只遍历窗口进程自己的子进程，结果按进程缓存 [`FOREGROUND_CACHE_TTL`]。
+#[cfg(target_os = "linux")]
+pub fn get_foreground_child(pid: u32) -> Option<ProcessContext> {
+    if pid == 0 {
+        return None;
+    }
+
+    let now = std::time::Instant::now();
+    let mut cache = FOREGROUND_CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(Default::default);
+    if let Some((at, context)) = cache.get(&pid)
+        && now.duration_since(*at) < FOREGROUND_CACHE_TTL
+    {
+        return context.clone();
+    }
+
+    let children = child_pids(pid)
+        .into_iter()
+        .filter_map(|child| read_stat(child).filter(|stat| stat.ppid == pid).map(|stat| (child, stat)));
+    let context = pick_foreground(children).and_then(get_process_context);
+
+    cache.retain(|_, (at, _)| now.duration_since(*at) < FOREGROUND_CACHE_TTL);
+    cache.insert(pid, (now, context.clone()));
+    context
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_foreground_child(_pid: u32) -> Option<ProcessContext> {
+    None
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    fn stat(tty_nr: i64, tpgid: i64, start_time: u64) -> ProcStat {
+        ProcStat { ppid: 1, tty_nr, tpgid, start_time }
+    }
+
+    #[test]
+    fn parses_children_list() {
+        assert_eq!(parse_pid_list("12 345 6789 \n").collect::<Vec<_>>(), vec![12, 345, 6789]);
+        assert_eq!(parse_pid_list("").count(), 0);
+    }
+
+    #[test]
+    fn prefers_running_job_then_newest_shell() {
+        // 两个空闲shell：选最近启动的
+        assert_eq!(pick_foreground([(10, stat(1, 10, 100)), (11, stat(1, 11, 200))]), Some(11));
+        // 较早的shell正在运行前台作业：选该作业的进程组
+        assert_eq!(pick_foreground([(10, stat(1, 42, 100)), (11, stat(1, 11, 200))]), Some(42));
+        // 没有终端的子进程不算
+        assert_eq!(pick_foreground([(10, stat(0, -1, 100))]), None);
+    }
+
+    #[test]
+    fn finds_child_of_current_process() {
+        let mut child = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+        let children = child_pids(std::process::id());
+        child.kill().ok();
+        child.wait().ok();
+        assert!(children.contains(&child.id()));
+    }
+}
@@ -0,0 +1,200 @@
+use crate::models::AppConfig;
+use crate::services::OcrLine;
+use image::{DynamicImage, GenericImage, Rgba, imageops};
+use regex::Regex;
+
+/// 敏感信息脱敏：按正则规则对截图打码，并替换OCR文本中的对应内容
+pub struct Redactor {
+    detectors: Vec<(String, Regex)>,
+    /// true 为涂黑，false 为模糊
+    black_out: bool,
+}
+
+impl Redactor {
+    /// 按配置编译规则，无效的规则记录日志后忽略
+    pub fn from_config(config: &AppConfig) -> Self {
+        let detectors = config
+            .pii_patterns
+            .iter()
+            .filter(|p| !p.pattern.is_empty())
+            .filter_map(|p| match Regex::new(&p.pattern) {
+                Ok(re) => Some((p.name.clone(), re)),
+                Err(e) => {
+                    eprintln!("脱敏规则无效 {}: {}", p.name, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            detectors,
+            black_out: config.pii_redaction_style == "black",
+        }
+    }
+
+    /// 文本中所有命中区间（字节偏移），按起点排序并合并重叠部分
+    fn find_spans(&self, text: &str) -> Vec<(usize, usize, &str)> {
+        let mut spans: Vec<(usize, usize, &str)> = self
+            .detectors
+            .iter()
+            .flat_map(|(name, re)| {
+                re.find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(move |m| (m.start(), m.end(), name.as_str()))
+            })
+            .collect();
+        spans.sort_by_key(|&(start, end, _)| (start, std::cmp::Reverse(end)));
+
+        let mut merged: Vec<(usize, usize, &str)> = Vec::new();
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.0 < last.1 => last.1 = last.1.max(span.1),
+                _ => merged.push(span),
+            }
+        }
+        merged
+    }
+
+    /// 将命中的内容替换为 `[规则名]`
+    ///
+    /// 与截图打码一样逐行匹配，跨行的内容（如含 `\s` 的规则跨过换行）两边都不处理，保证文本和截图一致。
+    pub fn redact_text(&self, text: &str) -> String {
+        text.split('\n').map(|line| self.redact_line(line)).collect::<Vec<_>>().join("\n")
+    }
+
+    fn redact_line(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end, name) in self.find_spans(text) {
+            result.push_str(&text[last..start]);
+            result.push('[');
+            result.push_str(name);
+            result.push(']');
+            last = end;
+        }
+        result.push_str(&text[last..]);
+        result
+    }
+
+    /// 对OCR行中命中的片段打码，返回打码区域数
+    ///
+    /// OCR只给出整行的四边形，按字符显示宽度（全角算两个半角）估算片段在行内的横向位置，
+    /// 左右各放宽半个字符以覆盖估算误差。
+    pub fn redact_image(&self, img: &mut DynamicImage, lines: &[OcrLine]) -> usize {
+        let mut count = 0;
+        for line in lines {
+            let spans = self.find_spans(&line.text);
+            let Some((left, top, right, bottom)) = line.bounds() else {
+                continue;
+            };
+            let total = display_width(&line.text).max(1) as f32;
+            let unit = (right - left) / total;
+            for (start, end, _) in spans {
+                let before = display_width(&line.text[..start]) as f32;
+                let len = display_width(&line.text[start..end]) as f32;
+                let x0 = left + unit * before - unit / 2.0;
+                let x1 = left + unit * (before + len) + unit / 2.0;
+                self.mask(img, x0, top, x1, bottom);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn mask(&self, img: &mut DynamicImage, left: f32, top: f32, right: f32, bottom: f32) {
+        let x = left.max(0.0).floor() as u32;
+        let y = top.max(0.0).floor() as u32;
+        let right = (right.max(0.0).ceil() as u32).min(img.width());
+        let bottom = (bottom.max(0.0).ceil() as u32).min(img.height());
+        if right <= x || bottom <= y {
+            return;
+        }
+
+        if self.black_out {
+            for py in y..bottom {
+                for px in x..right {
+                    img.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+                }
+            }
+        } else {
+            // 模糊半径与行高相当，文字不可辨认
+            let region = img.crop_imm(x, y, right - x, bottom - y);
+            let sigma = ((bottom - y) as f32 / 2.0).max(4.0);
+            imageops::replace(img, &region.blur(sigma), x as i64, y as i64);
+        }
+    }
+}
+
+/// 字符显示宽度：ASCII 算1，其余（中日韩等全角字符）算2
+fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PiiPattern;
+    use image::GenericImageView;
+
+    fn redactor(patterns: &[(&str, &str)], style: &str) -> Redactor {
+        let mut config = AppConfig::default();
+        config.pii_patterns = patterns
+            .iter()
+            .map(|(name, pattern)| PiiPattern { name: name.to_string(), pattern: pattern.to_string() })
+            .collect();
+        config.pii_redaction_style = style.to_string();
+        Redactor::from_config(&config)
+    }
+
+    #[test]
+    fn redact_text_matches_per_line_like_image() {
+        let r = redactor(&[("卡号", r"\d{4}\s\d{4}")], "black");
+        assert_eq!(r.redact_text("卡号 1234 5678\n尾号 1234\n5678"), "卡号 [卡号]\n尾号 1234\n5678");
+
+        let mut img = DynamicImage::new_rgba8(200, 40);
+        let line = |text: &str, y: f32| OcrLine {
+            text: text.to_string(),
+            points: vec![[0.0, y], [200.0, y], [200.0, y + 20.0], [0.0, y + 20.0]],
+            confidence: 0.9,
+        };
+        assert_eq!(r.redact_image(&mut img, &[line("尾号 1234", 0.0), line("5678", 20.0)]), 0);
+    }
+
+    #[test]
+    fn redact_text_replaces_matches_with_rule_name() {
+        let r = redactor(&[("手机号", r"1[3-9]\d{9}"), ("邮箱", r"[\w.]+@[\w.]+")], "blur");
+        assert_eq!(
+            r.redact_text("电话 13812345678，邮件 a.b@example.com。"),
+            "电话 [手机号]，邮件 [邮箱]。"
+        );
+        assert_eq!(r.redact_text("没有敏感信息"), "没有敏感信息");
+    }
+
+    #[test]
+    fn redact_text_merges_overlapping_matches() {
+        let r = redactor(&[("卡号", r"\d{16}"), ("数字", r"\d{4}")], "blur");
+        // 重叠的命中合并为一处，使用起点最早、范围最长的规则名
+        assert_eq!(r.redact_text("卡 6222021234567890 尾号"), "卡 [卡号] 尾号");
+    }
+
+    #[test]
+    fn redact_text_skips_invalid_and_empty_patterns() {
+        let r = redactor(&[("无效", "("), ("空", ""), ("可选", "x*"), ("密钥", r"sk-(?:[a-z]+)")], "blur");
+        assert_eq!(r.redact_text("key sk-abc end"), "key [密钥] end");
+    }
+
+    #[test]
+    fn redact_image_blacks_out_matched_span() {
+        let r = redactor(&[("密钥", "secret")], "black");
+        let mut img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(200, 40, Rgba([255, 255, 255, 255])));
+        let line = OcrLine {
+            text: "my secret".to_string(),
+            points: vec![[0.0, 10.0], [180.0, 10.0], [180.0, 30.0], [0.0, 30.0]],
+            confidence: 1.0,
+        };
+        assert_eq!(r.redact_image(&mut img, &[line]), 1);
+        // 每个字符宽 20 像素，"secret" 位于 60..180
+        assert_eq!(img.get_pixel(120, 20), Rgba([0, 0, 0, 255]));
+        assert_eq!(img.get_pixel(20, 20), Rgba([255, 255, 255, 255]));
+        assert_eq!(img.get_pixel(120, 5), Rgba([255, 255, 255, 255]));
+    }
+}
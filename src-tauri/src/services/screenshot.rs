@@ -1,19 +1,316 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::sync::Mutex;
+use std::time::SystemTime;
 use std::io::Cursor;
 use chrono::Local;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use once_cell::sync::Lazy;
+use crate::models::AppConfig;
 
 #[cfg(windows)]
 use screenshots::Screen;
 
+#[cfg(any(windows, target_os = "linux"))]
+use super::WindowTracker;
+
+/// 截图结果
+#[derive(Debug, Clone)]
+pub enum CaptureOutcome {
+    /// 已保存新截图（`staged` 为 true 时在暂存目录等待脱敏，`path` 尚不存在）
+    Saved { path: PathBuf, hash: u64, width: u32, height: u32, monitor: Option<String>, staged: bool },
+    /// 与上一张截图近似，未保存，只延长了上一张的时间范围
+    Duplicate { previous: PathBuf, distance: u32 },
+}
+
+impl CaptureOutcome {
+    /// 截图文件路径（重复时为上一张截图）
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            CaptureOutcome::Saved { path, .. } => path,
+            CaptureOutcome::Duplicate { previous, .. } => previous,
+        }
+    }
+}
+
+/// 每个去重键（应用+显示器）最近一次保存的截图
+struct LastCapture {
+    hash: u64,
+    path: PathBuf,
+}
+
+static LAST_CAPTURES: Lazy<Mutex<HashMap<String, LastCapture>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 计算图像的差值哈希（dHash）
+///
+/// 缩放为 9x8 灰度图，逐行比较相邻像素亮度得到 64 位指纹，对压缩噪声和微小变化不敏感。
+pub fn perceptual_hash(img: &DynamicImage) -> u64 {
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left < right) as u64;
+        }
+    }
+    hash
+}
+
+/// 两个哈希的汉明距离
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// 截图编码设置
+#[derive(Debug, Clone)]
+pub struct ImageSettings {
+    /// 保存格式：jpeg / webp / png
+    pub format: String,
+    /// JPEG质量（1-100）；WebP只支持无损编码，与PNG一样忽略此项
+    pub quality: u8,
+    /// 最大宽高（0为不限制），超出时等比缩小
+    pub max_width: u32,
+    pub max_height: u32,
+    /// 缩略图最长边（0为不生成）
+    pub thumbnail_size: u32,
+}
+
+impl ImageSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            format: config.screenshot_format.clone(),
+            quality: config.screenshot_quality,
+            max_width: config.screenshot_max_width,
+            max_height: config.screenshot_max_height,
+            thumbnail_size: config.thumbnail_size,
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self.format.as_str() {
+            "webp" => "webp",
+            "png" => "png",
+            _ => "jpg",
+        }
+    }
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            format: "jpeg".to_string(),
+            quality: 70,
+            max_width: 0,
+            max_height: 0,
+            thumbnail_size: 320,
+        }
+    }
+}
+
+/// 缩略图质量
+const THUMBNAIL_QUALITY: u8 = 60;
+
+/// 缩略图路径：截图所在目录下 thumbs/完整文件名.jpg
+///
+/// 保留原扩展名，同一时刻不同格式的截图（如切换格式后重新编码）不会共用缩略图。
+pub fn thumbnail_path(image_path: &Path) -> PathBuf {
+    let mut name = image_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".jpg");
+    image_path.parent().unwrap_or(Path::new(".")).join("thumbs").join(name)
+}
+
+/// 暂存目录名
+pub const STAGING_DIR: &str = "pending";
+
+/// 待脱敏截图的暂存路径：截图所在目录下 pending/完整文件名.png
+///
+/// 暂存文件无损保存，脱敏后只按设置编码一次。
+pub fn staging_path(image_path: &Path) -> PathBuf {
+    let mut name = image_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".png");
+    image_path.parent().unwrap_or(Path::new(".")).join(STAGING_DIR).join(name)
+}
+
+/// 判断文件是否为截图图片
+pub fn is_screenshot_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext == "jpg" || ext == "png" || ext == "webp")
+        .unwrap_or(false)
+}
+
+/// 按最大宽高等比缩小（不放大）
+fn fit_within(img: DynamicImage, max_width: u32, max_height: u32) -> DynamicImage {
+    let width_ok = max_width == 0 || img.width() <= max_width;
+    let height_ok = max_height == 0 || img.height() <= max_height;
+    if width_ok && height_ok {
+        return img;
+    }
+    let bound = |max: u32| if max == 0 { u32::MAX } else { max };
+    img.resize(bound(max_width), bound(max_height), FilterType::Triangle)
+}
+
+/// 按设置编码图片
+fn encode_image(img: &DynamicImage, format: &str, quality: u8) -> Result<Vec<u8>, String> {
+    use image::codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder};
+
+    // 截图不需要透明度
+    let rgb_img = img.to_rgb8();
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        // image 库只提供无损WebP编码，quality 不生效
+        "webp" => rgb_img
+            .write_with_encoder(WebPEncoder::new_lossless(&mut buffer))
+            .map_err(|e| format!("WebP编码失败: {}", e))?,
+        "png" => rgb_img
+            .write_with_encoder(PngEncoder::new(&mut buffer))
+            .map_err(|e| format!("PNG编码失败: {}", e))?,
+        _ => JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+            .encode_image(&rgb_img)
+            .map_err(|e| format!("JPEG编码失败: {}", e))?,
+    }
+    Ok(buffer.into_inner())
+}
+
+/// IJG 标准亮度量化表，按 DQT 段中的之字形顺序排列（JPEG 编码器按质量缩放这张表）
+const STD_LUMINANCE_QTABLE: [u16; 64] = [
+    16, 11, 12, 14, 12, 10, 16, 14, 13, 14, 18, 17, 16, 19, 24, 40,
+    26, 24, 22, 22, 24, 49, 35, 37, 29, 40, 58, 51, 61, 60, 57, 51,
+    56, 55, 64, 72, 92, 78, 64, 68, 87, 69, 55, 56, 80, 109, 81, 87,
+    95, 98, 103, 104, 103, 62, 77, 113, 121, 112, 100, 120, 92, 101, 103, 99,
+];
+
+/// 由亮度量化表估算 JPEG 的编码质量（1-100），不是标准 JPEG 时返回 None
+///
+/// 比较表项之和与标准表的比例；被截到 1 或上限的表项不参与计算。
+fn jpeg_quality(data: &[u8]) -> Option<u8> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= data.len() && data[pos] == 0xFF {
+        let marker = data[pos + 1];
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let segment = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xDA {
+            break;
+        }
+        if marker == 0xDB {
+            let mut table = segment;
+            while let Some((&info, rest)) = table.split_first() {
+                let wide = info >> 4 != 0;
+                let size = if wide { 128 } else { 64 };
+                let values = rest.get(..size)?;
+                if info & 0x0F == 0 {
+                    let (values, max): (Vec<u32>, u32) = if wide {
+                        (values.chunks(2).map(|v| u16::from_be_bytes([v[0], v[1]]) as u32).collect(), 32767)
+                    } else {
+                        (values.iter().map(|&v| v as u32).collect(), 255)
+                    };
+                    let (sum, std_sum) = values
+                        .iter()
+                        .zip(STD_LUMINANCE_QTABLE)
+                        .filter(|&(&v, _)| v > 1 && v < max)
+                        .fold((0, 0), |(sum, std_sum), (&v, std)| (sum + v, std_sum + std as u32));
+                    if std_sum == 0 {
+                        return Some(if values.iter().all(|&v| v <= 1) { 100 } else { 1 });
+                    }
+                    let scale = sum as f32 * 100.0 / std_sum as f32;
+                    let quality = if scale <= 100.0 { (200.0 - scale) / 2.0 } else { 5000.0 / scale };
+                    return Some(quality.round().clamp(1.0, 100.0) as u8);
+                }
+                table = &rest[size..];
+            }
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+/// 生成缩略图
+fn save_thumbnail(img: &DynamicImage, image_path: &Path, size: u32) -> Result<(), String> {
+    if size == 0 {
+        return Ok(());
+    }
+    let path = thumbnail_path(image_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建缩略图目录失败: {}", e))?;
+    }
+    let data = encode_image(&img.thumbnail(size, size), "jpeg", THUMBNAIL_QUALITY)?;
+    fs::write(&path, data).map_err(|e| format!("保存缩略图失败: {}", e))
+}
+
+/// 逐个显示器截图的结果：单个显示器失败时记录错误并保留其余截图，全部失败才返回错误
+fn successful_captures<T>(results: Vec<Result<T, String>>) -> Result<Vec<T>, String> {
+    let mut outcomes = Vec::new();
+    let mut errors = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(e) => {
+                eprintln!("显示器 {} 截图失败: {}", index + 1, e);
+                errors.push(e);
+            }
+        }
+    }
+    if outcomes.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(outcomes)
+}
+
+/// 重新编码结果
+#[derive(Debug, Clone)]
+pub struct RecompressResult {
+    pub old_path: PathBuf,
+    pub new_path: PathBuf,
+    pub old_bytes: u64,
+    pub new_bytes: u64,
+    pub width: u32,
+    pub height: u32,
+}
+
 /// 截图服务
 pub struct ScreenshotService {
     screenshots_dir: PathBuf,
+    /// 近似去重距离阈值（距离小于阈值视为重复）
+    dedup: Option<u32>,
+    settings: ImageSettings,
+    /// 先写入暂存目录，脱敏后再由 `finalize_staged` 落到正式路径
+    staged: bool,
 }
 
 impl ScreenshotService {
     pub fn new(screenshots_dir: PathBuf) -> Self {
-        Self { screenshots_dir }
+        Self {
+            screenshots_dir,
+            dedup: None,
+            settings: ImageSettings::default(),
+            staged: false,
+        }
+    }
+
+    /// 设置保存格式、质量和尺寸
+    pub fn with_image_settings(mut self, settings: ImageSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// 新截图先保存到暂存目录（需要脱敏时使用）
+    pub fn with_staging(mut self, staged: bool) -> Self {
+        self.staged = staged;
+        self
+    }
+
+    /// 启用近似截图去重，与同一应用在同一显示器上的上一张截图比较
+    pub fn with_dedup(mut self, max_distance: u32) -> Self {
+        if max_distance > 0 {
+            self.dedup = Some(max_distance);
+        }
+        self
     }
 
     /// 确保截图目录存在
@@ -29,86 +326,409 @@ impl ScreenshotService {
             .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
             .take(30)
             .collect();
-        self.screenshots_dir.join(format!("{}_{}.jpg", timestamp, safe_app_name))
+        self.screenshots_dir.join(format!("{}_{}.{}", timestamp, safe_app_name, self.settings.extension()))
     }
 
-    /// 压缩并保存图片为JPEG格式（质量70%）
-    #[cfg(windows)]
-    fn save_as_jpeg(&self, width: u32, height: u32, rgba_data: Vec<u8>, filepath: &PathBuf) -> Result<(), String> {
-        use image::{ImageBuffer, Rgba, codecs::jpeg::JpegEncoder};
-        
+    /// 去重检查后保存截图（按应用和显示器分别与上一张比较）
+    #[cfg(any(windows, target_os = "linux"))]
+    fn finish(&self, app_name: &str, monitor: Option<String>, width: u32, height: u32, rgba_data: Vec<u8>) -> Result<CaptureOutcome, String> {
+        use image::{ImageBuffer, Rgba};
+
         // 从原始数据创建ImageBuffer
-        let img_buffer: ImageBuffer<Rgba<u8>, Vec<u8>> = 
+        let img_buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
             ImageBuffer::from_raw(width, height, rgba_data)
                 .ok_or("创建图像缓冲区失败")?;
-        
-        // 转换为RGB（JPEG不支持透明度）
-        let rgb_img = image::DynamicImage::ImageRgba8(img_buffer).to_rgb8();
-        
-        // 使用JPEG编码器，质量设为70
-        let mut buffer = Cursor::new(Vec::new());
-        let mut encoder = JpegEncoder::new_with_quality(&mut buffer, 70);
-        encoder.encode_image(&rgb_img)
-            .map_err(|e| format!("JPEG编码失败: {}", e))?;
-        
-        fs::write(filepath, buffer.into_inner())
+        let img = DynamicImage::ImageRgba8(img_buffer);
+        let hash = perceptual_hash(&img);
+
+        let key = format!("{}@{}", app_name, monitor.as_deref().unwrap_or_default());
+        // 只在读写记录时持锁，文件操作和编码不占用
+        let previous = self
+            .dedup
+            .and_then(|_| LAST_CAPTURES.lock().unwrap().get(&key).map(|prev| (prev.hash, prev.path.clone())));
+        if let (Some(max_distance), Some((prev_hash, prev_path))) = (self.dedup, previous) {
+            let distance = hash_distance(prev_hash, hash);
+            // 上一张已被删除时照常保存
+            if distance < max_distance && prev_path.exists() {
+                // 以文件修改时间作为上一张截图的结束时间
+                if let Err(e) = fs::File::options()
+                    .write(true)
+                    .open(&prev_path)
+                    .and_then(|f| f.set_modified(SystemTime::now()))
+                {
+                    eprintln!("更新截图时间范围失败: {}", e);
+                }
+                return Ok(CaptureOutcome::Duplicate { previous: prev_path, distance });
+            }
+        }
+
+        let path = self.generate_filename(app_name);
+        let img = fit_within(img, self.settings.max_width, self.settings.max_height);
+        if self.staged {
+            let staged = staging_path(&path);
+            if let Some(parent) = staged.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建暂存目录失败: {}", e))?;
+            }
+            let data = encode_image(&img, "png", 0)?;
+            fs::write(&staged, data).map_err(|e| format!("保存文件失败: {}", e))?;
+        } else {
+            self.save_image(&img, &path)?;
+        }
+        if self.dedup.is_some() {
+            LAST_CAPTURES.lock().unwrap().insert(key, LastCapture { hash, path: path.clone() });
+        }
+        Ok(CaptureOutcome::Saved {
+            path,
+            hash,
+            width: img.width(),
+            height: img.height(),
+            monitor,
+            staged: self.staged,
+        })
+    }
+
+    /// 按设置编码并保存图片，同时生成缩略图
+    fn save_image(&self, img: &DynamicImage, filepath: &Path) -> Result<(), String> {
+        let data = encode_image(img, &self.settings.format, self.settings.quality)?;
+        fs::write(filepath, data)
             .map_err(|e| format!("保存文件失败: {}", e))?;
-        
+
+        if let Err(e) = save_thumbnail(img, filepath, self.settings.thumbnail_size) {
+            eprintln!("{}", e);
+        }
         Ok(())
     }
 
-    /// 截取全屏
+    /// 将暂存的截图处理（如打码）后按设置编码保存到正式路径，并生成缩略图
+    pub fn finalize_staged(&self, image_path: &Path, edit: impl FnOnce(&mut DynamicImage)) -> Result<(), String> {
+        let staged = staging_path(image_path);
+        let mut img = image::open(&staged).map_err(|e| format!("读取暂存截图失败: {}", e))?;
+        edit(&mut img);
+        self.save_image(&img, image_path)?;
+        fs::remove_file(&staged).map_err(|e| format!("删除暂存截图失败: {}", e))
+    }
+
+    /// 暂存目录中遗留的截图（程序在脱敏完成前退出），返回对应的正式路径
+    pub fn leftover_staged(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(self.screenshots_dir.join(STAGING_DIR)) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
+            .filter_map(|p| p.file_stem().map(|name| self.screenshots_dir.join(name)))
+            .filter(|p| is_screenshot_file(p))
+            .collect()
+    }
+
+    /// 按当前设置重新编码已有截图（可能缩小尺寸或更换格式）
+    ///
+    /// 已符合设置的文件不再编码，避免有损格式反复压缩；只有新文件更小时才替换，
+    /// 返回 None 表示保留原文件；缩略图总是补齐。
+    pub fn recompress(&self, path: &Path) -> Result<Option<RecompressResult>, String> {
+        let old_bytes = fs::metadata(path)
+            .map_err(|e| format!("读取文件信息失败: {}", e))?
+            .len();
+        if self.meets_settings(path) {
+            if !thumbnail_path(path).exists() {
+                let img = image::open(path).map_err(|e| format!("读取图片失败: {}", e))?;
+                save_thumbnail(&img, path, self.settings.thumbnail_size)?;
+            }
+            return Ok(None);
+        }
+        let img = image::open(path).map_err(|e| format!("读取图片失败: {}", e))?;
+        let img = fit_within(img, self.settings.max_width, self.settings.max_height);
+        let thumbnail = |image_path: &Path| {
+            if let Err(e) = save_thumbnail(&img, image_path, self.settings.thumbnail_size) {
+                eprintln!("{}", e);
+            }
+        };
+
+        let data = encode_image(&img, &self.settings.format, self.settings.quality)?;
+        let new_bytes = data.len() as u64;
+        let new_path = path.with_extension(self.settings.extension());
+        // 同名的其他格式截图已存在时不覆盖
+        if new_bytes >= old_bytes || (new_path != path && new_path.exists()) {
+            thumbnail(path);
+            return Ok(None);
+        }
+
+        // 先写临时文件再重命名，避免中途失败损坏原图
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, data).map_err(|e| format!("保存文件失败: {}", e))?;
+        fs::rename(&tmp_path, &new_path).map_err(|e| format!("替换文件失败: {}", e))?;
+        if new_path != path {
+            fs::remove_file(path).map_err(|e| format!("删除原文件失败: {}", e))?;
+            let _ = fs::remove_file(thumbnail_path(path));
+        }
+        thumbnail(&new_path);
+
+        Ok(Some(RecompressResult {
+            old_path: path.to_path_buf(),
+            new_path,
+            old_bytes,
+            new_bytes,
+            width: img.width(),
+            height: img.height(),
+        }))
+    }
+
+    /// 文件是否已是目标格式、尺寸不超过限制且（JPEG）质量不高于设置
+    fn meets_settings(&self, path: &Path) -> bool {
+        let extension = self.settings.extension();
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension)) {
+            return false;
+        }
+        let Ok((width, height)) = image::image_dimensions(path) else {
+            return false;
+        };
+        let fits = |size: u32, max: u32| max == 0 || size <= max;
+        if !fits(width, self.settings.max_width) || !fits(height, self.settings.max_height) {
+            return false;
+        }
+        match extension {
+            "jpg" => fs::read(path)
+                .ok()
+                .and_then(|data| jpeg_quality(&data))
+                .is_some_and(|quality| quality <= self.settings.quality.clamp(1, 100)),
+            // 无损格式重新编码不会更小
+            _ => true,
+        }
+    }
+
+    /// 截取活动窗口所在的显示器（取不到活动窗口时为主显示器）
     #[cfg(windows)]
-    pub fn capture_full_screen(&self, app_name: &str) -> Result<PathBuf, String> {
+    pub fn capture_full_screen(&self, app_name: &str) -> Result<CaptureOutcome, String> {
         self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
-        
-        let screens = Screen::all().map_err(|e| format!("获取屏幕失败: {}", e))?;
-        if screens.is_empty() {
-            return Err("未找到屏幕".to_string());
-        }
-        
-        // 获取主屏幕
-        let screen = &screens[0];
-        let capture = screen.capture().map_err(|e| format!("截图失败: {}", e))?;
-        
-        let filepath = self.generate_filename(app_name);
-        // 直接保存为JPEG压缩格式
+
+        let screens = all_screens()?;
+        let center = WindowTracker::new()
+            .get_active_window_rect()
+            .ok()
+            .filter(|rect| rect.width > 0 && rect.height > 0)
+            .map(|rect| rect.center());
+        let index = center
+            .and_then(|(x, y)| screens.iter().position(|s| screen_contains(s, x, y)))
+            .or_else(|| screens.iter().position(|s| s.display_info.is_primary))
+            .unwrap_or(0);
+
+        let capture = screens[index].capture().map_err(|e| format!("截图失败: {}", e))?;
         let (w, h) = capture.dimensions();
-        self.save_as_jpeg(w, h, capture.into_raw(), &filepath)?;
-        
-        Ok(filepath)
+        self.finish(app_name, screen_name(&screens[index]), w, h, capture.into_raw())
+    }
+
+    /// Linux (X11) 实现
+    #[cfg(target_os = "linux")]
+    pub fn capture_full_screen(&self, app_name: &str) -> Result<CaptureOutcome, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let x = x11_capture::X11Capture::connect()?;
+        let monitors = x.monitors();
+        let center = WindowTracker::new()
+            .get_active_window_id()
+            .ok()
+            .and_then(|window| x.window_rect(window).ok())
+            .map(|(rx, ry, width, height)| (rx + width as i32 / 2, ry + height as i32 / 2));
+        let monitor = center
+            .and_then(|c| monitors.iter().find(|m| m.contains(c)))
+            .or_else(|| monitors.iter().find(|m| m.primary))
+            .or(monitors.first());
+
+        match monitor {
+            Some(m) => {
+                let capture = x.capture_screen_area(m.rect.x, m.rect.y, m.rect.width, m.rect.height)?;
+                self.save_capture(app_name, Some(m.name.clone()), capture)
+            }
+            // RandR 不可用时截取整个根窗口
+            None => self.capture_all_monitors(app_name),
+        }
     }
 
-    #[cfg(not(windows))]
-    pub fn capture_full_screen(&self, app_name: &str) -> Result<PathBuf, String> {
-        Err("截图功能仅支持Windows".to_string())
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn capture_full_screen(&self, _app_name: &str) -> Result<CaptureOutcome, String> {
+        Err("当前平台不支持截图".to_string())
     }
 
-    /// 截取指定区域（用于应用窗口截图）
+    /// 截取所有显示器并按实际排布拼接为一张图
     #[cfg(windows)]
-    pub fn capture_area(&self, app_name: &str, x: i32, y: i32, width: u32, height: u32) -> Result<PathBuf, String> {
+    pub fn capture_all_monitors(&self, app_name: &str) -> Result<CaptureOutcome, String> {
+        use image::{RgbaImage, imageops};
+
         self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
-        
-        let screens = Screen::all().map_err(|e| format!("获取屏幕失败: {}", e))?;
-        if screens.is_empty() {
-            return Err("未找到屏幕".to_string());
+
+        let screens = all_screens()?;
+        let mut parts = Vec::with_capacity(screens.len());
+        for screen in &screens {
+            let capture = screen.capture().map_err(|e| format!("截图失败: {}", e))?;
+            // screenshots 依赖的 image 版本与本 crate 不同，按原始像素重建
+            let (w, h) = capture.dimensions();
+            let img = RgbaImage::from_raw(w, h, capture.into_raw()).ok_or("创建图像缓冲区失败")?;
+            let (x, y, _, _) = physical_bounds(screen);
+            parts.push((x, y, img));
         }
-        
-        let screen = &screens[0];
-        let capture = screen.capture_area(x, y, width, height)
+
+        let left = parts.iter().map(|(x, _, _)| *x).min().unwrap_or(0);
+        let top = parts.iter().map(|(_, y, _)| *y).min().unwrap_or(0);
+        let right = parts.iter().map(|(x, _, img)| x + img.width() as i32).max().unwrap_or(0);
+        let bottom = parts.iter().map(|(_, y, img)| y + img.height() as i32).max().unwrap_or(0);
+
+        let mut canvas = RgbaImage::new((right - left) as u32, (bottom - top) as u32);
+        for (x, y, img) in &parts {
+            imageops::replace(&mut canvas, img, (x - left) as i64, (y - top) as i64);
+        }
+        let (w, h) = canvas.dimensions();
+        self.finish(app_name, None, w, h, canvas.into_raw())
+    }
+
+    /// X11 的根窗口就是所有显示器拼接后的虚拟屏幕
+    #[cfg(target_os = "linux")]
+    pub fn capture_all_monitors(&self, app_name: &str) -> Result<CaptureOutcome, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let x = x11_capture::X11Capture::connect()?;
+        let (width, height) = x.screen_size();
+        let capture = x.capture_rect(x.root, 0, 0, width, height)?;
+        self.save_capture(app_name, None, capture)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn capture_all_monitors(&self, _app_name: &str) -> Result<CaptureOutcome, String> {
+        Err("当前平台不支持截图".to_string())
+    }
+
+    /// 每个显示器分别截图，文件名追加显示器序号（`_m1`、`_m2`…）
+    #[cfg(windows)]
+    pub fn capture_each_monitor(&self, app_name: &str) -> Result<Vec<CaptureOutcome>, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let screens = all_screens()?;
+        let results = screens.iter().enumerate().map(|(index, screen)| {
+            let capture = screen.capture().map_err(|e| format!("截图失败: {}", e))?;
+            let (w, h) = capture.dimensions();
+            let name = format!("{}_m{}", app_name, index + 1);
+            self.finish(&name, screen_name(screen), w, h, capture.into_raw())
+        });
+        successful_captures(results.collect())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn capture_each_monitor(&self, app_name: &str) -> Result<Vec<CaptureOutcome>, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let x = x11_capture::X11Capture::connect()?;
+        let monitors = x.monitors();
+        if monitors.is_empty() {
+            return self.capture_all_monitors(app_name).map(|outcome| vec![outcome]);
+        }
+
+        let results = monitors.iter().enumerate().map(|(index, m)| {
+            let capture = x.capture_screen_area(m.rect.x, m.rect.y, m.rect.width, m.rect.height)?;
+            let name = format!("{}_m{}", app_name, index + 1);
+            self.save_capture(&name, Some(m.name.clone()), capture)
+        });
+        successful_captures(results.collect())
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn capture_each_monitor(&self, _app_name: &str) -> Result<Vec<CaptureOutcome>, String> {
+        Err("当前平台不支持截图".to_string())
+    }
+
+    /// 截取指定区域（虚拟屏幕坐标，用于应用窗口截图）
+    /// 以区域中心所在的显示器为准，超出该显示器的部分会被裁掉
+    #[cfg(windows)]
+    pub fn capture_area(&self, app_name: &str, x: i32, y: i32, width: u32, height: u32) -> Result<CaptureOutcome, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let screens = all_screens()?;
+        let (cx, cy) = (x + width as i32 / 2, y + height as i32 / 2);
+        let screen = screens
+            .iter()
+            .find(|s| screen_contains(s, cx, cy))
+            .ok_or("截图区域不在任何屏幕内")?;
+
+        // 转换为相对该屏幕的逻辑坐标并裁剪到屏幕范围
+        let (sx, sy, sw, sh) = physical_bounds(screen);
+        let left = x.max(sx);
+        let top = y.max(sy);
+        let right = (x + width as i32).min(sx + sw as i32);
+        let bottom = (y + height as i32).min(sy + sh as i32);
+        let scale = screen.display_info.scale_factor;
+        let to_logical = |v: i32| (v as f32 / scale).round() as i32;
+        let capture = screen
+            .capture_area(
+                to_logical(left - sx),
+                to_logical(top - sy),
+                to_logical(right - left) as u32,
+                to_logical(bottom - top) as u32,
+            )
             .map_err(|e| format!("截图失败: {}", e))?;
-        
-        let filepath = self.generate_filename(app_name);
-        // 直接保存为JPEG压缩格式
+
         let (w, h) = capture.dimensions();
-        self.save_as_jpeg(w, h, capture.into_raw(), &filepath)?;
-        
-        Ok(filepath)
+        self.finish(app_name, screen_name(screen), w, h, capture.into_raw())
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn capture_area(&self, app_name: &str, x: i32, y: i32, width: u32, height: u32) -> Result<CaptureOutcome, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let conn = x11_capture::X11Capture::connect()?;
+        let monitor = conn.monitor_at((x + width as i32 / 2, y + height as i32 / 2));
+        let capture = conn.capture_screen_area(x, y, width, height)?;
+        self.save_capture(app_name, monitor, capture)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn capture_area(&self, _app_name: &str, _x: i32, _y: i32, _width: u32, _height: u32) -> Result<CaptureOutcome, String> {
+        Err("当前平台不支持截图".to_string())
+    }
+
+    /// 只截取当前活动窗口
+    ///
+    /// X11 下通过 XComposite 读取窗口自身的离屏内容，不受遮挡影响；
+    /// 合成扩展不可用时退化为按窗口矩形截取屏幕区域。
+    #[cfg(target_os = "linux")]
+    pub fn capture_active_window(&self, app_name: &str) -> Result<CaptureOutcome, String> {
+        self.ensure_dir().map_err(|e| format!("创建截图目录失败: {}", e))?;
+
+        let window = WindowTracker::new()
+            .get_active_window_id()
+            .map_err(|e| format!("获取活动窗口失败: {}", e))?;
+        let x = x11_capture::X11Capture::connect()?;
+        let (rx, ry, width, height) = x.window_rect(window)?;
+        let monitor = x.monitor_at((rx + width as i32 / 2, ry + height as i32 / 2));
+        let capture = match x.capture_window(window) {
+            Ok(capture) => capture,
+            Err(e) => {
+                eprintln!("窗口合成截图失败，改为区域截图: {}", e);
+                x.capture_screen_area(rx, ry, width, height)?
+            }
+        };
+        self.save_capture(app_name, monitor, capture)
+    }
+
+    /// Windows 下按活动窗口矩形截取所在屏幕的区域
+    #[cfg(windows)]
+    pub fn capture_active_window(&self, app_name: &str) -> Result<CaptureOutcome, String> {
+        let rect = WindowTracker::new()
+            .get_active_window_rect()
+            .map_err(|e| format!("获取窗口位置失败: {}", e))?;
+        if rect.width == 0 || rect.height == 0 {
+            return Err("活动窗口尺寸为空".to_string());
+        }
+        self.capture_area(app_name, rect.x, rect.y, rect.width, rect.height)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    pub fn capture_active_window(&self, _app_name: &str) -> Result<CaptureOutcome, String> {
+        Err("当前平台不支持截图".to_string())
     }
 
-    #[cfg(not(windows))]
-    pub fn capture_area(&self, _app_name: &str, _x: i32, _y: i32, _width: u32, _height: u32) -> Result<PathBuf, String> {
-        Err("截图功能仅支持Windows".to_string())
+    /// 保存 X11 截取的图像
+    #[cfg(target_os = "linux")]
+    fn save_capture(&self, app_name: &str, monitor: Option<String>, capture: x11_capture::Capture) -> Result<CaptureOutcome, String> {
+        self.finish(app_name, monitor, capture.width, capture.height, capture.rgba)
     }
 
     /// 获取今日截图列表
@@ -122,9 +742,446 @@ impl ScreenshotService {
                 entries
                     .filter_map(|e| e.ok())
                     .map(|e| e.path())
-                    .filter(|p| p.extension().map(|ext| ext == "jpg" || ext == "png").unwrap_or(false))
+                    .filter(|p| is_screenshot_file(p))
                     .collect()
             })
             .unwrap_or_default()
     }
 }
+
+/// 所有屏幕
+#[cfg(windows)]
+fn all_screens() -> Result<Vec<Screen>, String> {
+    let screens = Screen::all().map_err(|e| format!("获取屏幕失败: {}", e))?;
+    if screens.is_empty() {
+        return Err("未找到屏幕".to_string());
+    }
+    Ok(screens)
+}
+
+/// 屏幕的物理像素范围（display_info 为逻辑坐标，窗口矩形为物理坐标）
+#[cfg(windows)]
+fn physical_bounds(screen: &Screen) -> (i32, i32, u32, u32) {
+    let info = &screen.display_info;
+    let scale = info.scale_factor;
+    (
+        (info.x as f32 * scale).round() as i32,
+        (info.y as f32 * scale).round() as i32,
+        (info.width as f32 * scale).round() as u32,
+        (info.height as f32 * scale).round() as u32,
+    )
+}
+
+/// 物理坐标点是否在屏幕内
+#[cfg(windows)]
+fn screen_contains(screen: &Screen, x: i32, y: i32) -> bool {
+    let (sx, sy, sw, sh) = physical_bounds(screen);
+    x >= sx && y >= sy && x < sx + sw as i32 && y < sy + sh as i32
+}
+
+/// 屏幕设备名（如 `\\.\DISPLAY1`），与窗口跟踪记录的显示器名一致
+#[cfg(windows)]
+fn screen_name(screen: &Screen) -> Option<String> {
+    super::monitor_device_name(screen.display_info.raw_handle.0)
+}
+
+/// X11 屏幕读取：优先 MIT-SHM 共享内存，不可用时回退为普通 GetImage
+#[cfg(target_os = "linux")]
+mod x11_capture {
+    use std::os::fd::AsRawFd;
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::composite::{self, ConnectionExt as _, Redirect};
+    use crate::services::window_tracker::{MonitorBounds, randr_monitors};
+    use x11rb::protocol::shm::{self, ConnectionExt as _};
+    use x11rb::protocol::xproto::{ConnectionExt as _, Drawable, ImageFormat, ImageOrder, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    fn err(e: impl std::fmt::Display) -> String {
+        format!("X11截图失败: {}", e)
+    }
+
+    /// 截取到的 RGBA 图像
+    pub struct Capture {
+        pub width: u32,
+        pub height: u32,
+        pub rgba: Vec<u8>,
+    }
+
+    pub struct X11Capture {
+        conn: RustConnection,
+        pub root: Window,
+        screen_width: u32,
+        screen_height: u32,
+        /// 服务器支持 MIT-SHM 1.2（可由服务器创建共享内存段）
+        shm: bool,
+        /// 像素为小端 BGRX 排列时为 false，大端 XRGB 时为 true
+        big_endian: bool,
+    }
+
+    impl X11Capture {
+        pub fn connect() -> Result<Self, String> {
+            let (conn, screen_num) = x11rb::connect(None).map_err(err)?;
+            let setup = conn.setup();
+            let screen = &setup.roots[screen_num];
+
+            // 只支持 24/32 位真彩色且红色位于高位的常见布局
+            let visual = screen
+                .allowed_depths
+                .iter()
+                .flat_map(|d| d.visuals.iter())
+                .find(|v| v.visual_id == screen.root_visual)
+                .ok_or("未找到根窗口视觉类型")?;
+            if screen.root_depth < 24 || visual.red_mask != 0x00ff_0000 || visual.blue_mask != 0x0000_00ff {
+                return Err(format!("不支持的X11像素格式: 深度{}", screen.root_depth));
+            }
+
+            let root = screen.root;
+            let screen_width = screen.width_in_pixels as u32;
+            let screen_height = screen.height_in_pixels as u32;
+            let big_endian = setup.image_byte_order == ImageOrder::MSB_FIRST;
+
+            let shm = conn
+                .extension_information(shm::X11_EXTENSION_NAME)
+                .ok()
+                .flatten()
+                .is_some()
+                && conn
+                    .shm_query_version()
+                    .ok()
+                    .and_then(|c| c.reply().ok())
+                    .is_some_and(|v| (v.major_version, v.minor_version) >= (1, 2));
+
+            Ok(Self {
+                conn,
+                root,
+                screen_width,
+                screen_height,
+                shm,
+                big_endian,
+            })
+        }
+
+        /// 根窗口（所有显示器拼接后的虚拟屏幕）尺寸
+        pub fn screen_size(&self) -> (u32, u32) {
+            (self.screen_width, self.screen_height)
+        }
+
+        /// 通过 RandR 获取所有已启用的显示器，扩展不可用时为空
+        pub fn monitors(&self) -> Vec<MonitorBounds> {
+            randr_monitors(&self.conn, self.root)
+        }
+
+        /// 点所在显示器的名称
+        pub fn monitor_at(&self, point: (i32, i32)) -> Option<String> {
+            self.monitors().into_iter().find(|m| m.contains(point)).map(|m| m.name)
+        }
+
+        /// 截取屏幕区域，超出屏幕的部分会被裁掉
+        pub fn capture_screen_area(&self, x: i32, y: i32, width: u32, height: u32) -> Result<Capture, String> {
+            let left = x.max(0);
+            let top = y.max(0);
+            let right = (x + width as i32).min(self.screen_width as i32);
+            let bottom = (y + height as i32).min(self.screen_height as i32);
+            if right <= left || bottom <= top {
+                return Err("截图区域不在屏幕内".to_string());
+            }
+            self.capture_rect(self.root, left, top, (right - left) as u32, (bottom - top) as u32)
+        }
+
+        /// 读取 drawable 中的矩形区域
+        pub fn capture_rect(&self, drawable: Drawable, x: i32, y: i32, width: u32, height: u32) -> Result<Capture, String> {
+            let data = if self.shm {
+                match self.shm_get_image(drawable, x, y, width, height) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        eprintln!("共享内存截图失败，回退为GetImage: {}", e);
+                        self.get_image(drawable, x, y, width, height)?
+                    }
+                }
+            } else {
+                self.get_image(drawable, x, y, width, height)?
+            };
+
+            if data.len() < (width * height * 4) as usize {
+                return Err("X11返回的图像数据长度不符".to_string());
+            }
+            Ok(Capture {
+                width,
+                height,
+                rgba: to_rgba(&data, (width * height) as usize, self.big_endian),
+            })
+        }
+
+        /// 普通 GetImage（数据经过 socket 传输）
+        fn get_image(&self, drawable: Drawable, x: i32, y: i32, width: u32, height: u32) -> Result<Vec<u8>, String> {
+            let reply = self
+                .conn
+                .get_image(ImageFormat::Z_PIXMAP, drawable, x as i16, y as i16, width as u16, height as u16, !0)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+            Ok(reply.data)
+        }
+
+        /// MIT-SHM 读取：由服务器创建共享内存段并通过 fd 传回，避免 socket 拷贝
+        fn shm_get_image(&self, drawable: Drawable, x: i32, y: i32, width: u32, height: u32) -> Result<Vec<u8>, String> {
+            let size = (width * height * 4) as usize;
+            let seg = self.conn.generate_id().map_err(err)?;
+            let reply = self
+                .conn
+                .shm_create_segment(seg, size as u32, false)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+
+            let ptr = unsafe {
+                libc::mmap(
+                    std::ptr::null_mut(),
+                    size,
+                    libc::PROT_READ,
+                    libc::MAP_SHARED,
+                    reply.shm_fd.as_raw_fd(),
+                    0,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                let _ = self.conn.shm_detach(seg);
+                return Err("映射共享内存失败".to_string());
+            }
+
+            let result = self
+                .conn
+                .shm_get_image(
+                    drawable,
+                    x as i16,
+                    y as i16,
+                    width as u16,
+                    height as u16,
+                    !0,
+                    ImageFormat::Z_PIXMAP.into(),
+                    seg,
+                    0,
+                )
+                .map_err(err)
+                .and_then(|cookie| cookie.reply().map_err(err))
+                .map(|_| unsafe { std::slice::from_raw_parts(ptr as *const u8, size) }.to_vec());
+
+            unsafe {
+                libc::munmap(ptr, size);
+            }
+            let _ = self.conn.shm_detach(seg);
+            let _ = self.conn.flush();
+            result
+        }
+
+        /// 通过 XComposite 截取窗口（含窗口管理器边框）
+        pub fn capture_window(&self, window: Window) -> Result<Capture, String> {
+            self.conn
+                .extension_information(composite::X11_EXTENSION_NAME)
+                .map_err(err)?
+                .ok_or("X服务器不支持Composite扩展")?;
+            self.conn
+                .composite_query_version(0, 2)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+
+            let frame = self.top_level(window);
+            let geometry = self.conn.get_geometry(frame).map_err(err)?.reply().map_err(err)?;
+            let (width, height) = (geometry.width as u32, geometry.height as u32);
+            if width == 0 || height == 0 {
+                return Err("活动窗口尺寸为空".to_string());
+            }
+
+            // 自动重定向不影响显示；已有合成器时重复重定向是无害的
+            self.conn
+                .composite_redirect_window(frame, Redirect::AUTOMATIC)
+                .map_err(err)?;
+            let pixmap = self.conn.generate_id().map_err(err)?;
+            let result = self
+                .conn
+                .composite_name_window_pixmap(frame, pixmap)
+                .map_err(err)
+                .and_then(|_| self.get_image(pixmap, 0, 0, width, height));
+            let _ = self.conn.free_pixmap(pixmap);
+            let _ = self.conn.composite_unredirect_window(frame, Redirect::AUTOMATIC);
+            let _ = self.conn.flush();
+
+            let data = result?;
+            if data.len() < (width * height * 4) as usize {
+                return Err("X11返回的图像数据长度不符".to_string());
+            }
+            Ok(Capture {
+                width,
+                height,
+                rgba: to_rgba(&data, (width * height) as usize, self.big_endian),
+            })
+        }
+
+        /// 窗口相对根窗口的位置和大小（以顶层框架窗口为准）
+        pub fn window_rect(&self, window: Window) -> Result<(i32, i32, u32, u32), String> {
+            let frame = self.top_level(window);
+            let geometry = self.conn.get_geometry(frame).map_err(err)?.reply().map_err(err)?;
+            let translated = self
+                .conn
+                .translate_coordinates(frame, self.root, 0, 0)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+            Ok((
+                translated.dst_x as i32,
+                translated.dst_y as i32,
+                geometry.width as u32,
+                geometry.height as u32,
+            ))
+        }
+
+        /// 向上查找根窗口的直接子窗口（重排父窗口的窗口管理器会把客户窗口嵌入框架中）
+        fn top_level(&self, window: Window) -> Window {
+            let mut current = window;
+            loop {
+                let Some(tree) = self.conn.query_tree(current).ok().and_then(|c| c.reply().ok()) else {
+                    return current;
+                };
+                if tree.parent == tree.root || tree.parent == 0 {
+                    return current;
+                }
+                current = tree.parent;
+            }
+        }
+    }
+
+    /// 32 位 ZPixmap 转为 RGBA（忽略 alpha，JPEG 不需要）
+    /// 小端为 BGRX 排列，大端为 XRGB 排列
+    pub(super) fn to_rgba(data: &[u8], pixels: usize, big_endian: bool) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(pixels * 4);
+        for px in data.chunks_exact(4).take(pixels) {
+            let (r, g, b) = if big_endian {
+                (px[1], px[2], px[3])
+            } else {
+                (px[2], px[1], px[0])
+            };
+            rgba.extend_from_slice(&[r, g, b, 255]);
+        }
+        rgba
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gradient(width: u32, height: u32, reverse: bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+            let v = (x * 255 / (width - 1)) as u8;
+            Luma([if reverse { 255 - v } else { v }])
+        }))
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn x11_pixels_convert_for_both_byte_orders() {
+        // 红、绿两个像素，末尾多出的填充字节不计入
+        let lsb = [0x00, 0x00, 0xff, 0x00, 0x00, 0xff, 0x00, 0x00, 0x11, 0x22];
+        assert_eq!(x11_capture::to_rgba(&lsb, 2, false), [255, 0, 0, 255, 0, 255, 0, 255]);
+        let msb = [0x00, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0x00];
+        assert_eq!(x11_capture::to_rgba(&msb, 2, true), [255, 0, 0, 255, 0, 255, 0, 255]);
+        // 只取前 pixels 个像素
+        assert_eq!(x11_capture::to_rgba(&msb, 1, true), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn monitor_failures_keep_other_captures() {
+        let partial = successful_captures(vec![Ok(1), Err("m2".to_string()), Ok(3)]);
+        assert_eq!(partial.unwrap(), [1, 3]);
+        let failed = successful_captures::<i32>(vec![Err("m1".to_string()), Err("m2".to_string())]);
+        assert_eq!(failed.unwrap_err(), "m1; m2");
+        assert!(successful_captures::<i32>(Vec::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn jpeg_quality_matches_encoder_setting() {
+        let img = gradient(64, 64, false);
+        for quality in 1..=100 {
+            let data = encode_image(&img, "jpeg", quality).unwrap();
+            assert_eq!(jpeg_quality(&data), Some(quality));
+        }
+        assert_eq!(jpeg_quality(&encode_image(&img, "png", 80).unwrap()), None);
+    }
+
+    #[test]
+    fn recompress_skips_files_that_meet_settings() {
+        let dir = std::env::temp_dir().join(format!("screenshot_recompress_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("09-00-00_Code.jpg");
+        let img = gradient(64, 48, false);
+        fs::write(&path, encode_image(&img, "jpeg", 60).unwrap()).unwrap();
+        let service = |format: &str, quality: u8, max_width: u32| {
+            let settings = ImageSettings { format: format.to_string(), quality, max_width, ..ImageSettings::default() };
+            ScreenshotService::new(dir.clone()).with_image_settings(settings)
+        };
+
+        assert!(service("jpeg", 60, 0).meets_settings(&path));
+        assert!(service("jpeg", 80, 64).meets_settings(&path));
+        assert!(service("jpeg", 80, 0).recompress(&path).unwrap().is_none());
+        assert!(thumbnail_path(&path).exists());
+        assert!(!service("jpeg", 40, 0).meets_settings(&path));
+        assert!(!service("jpeg", 80, 32).meets_settings(&path));
+        assert!(!service("png", 80, 0).meets_settings(&path));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn thumbnail_path_keeps_extension() {
+        let jpg = thumbnail_path(Path::new("/shots/09-00-00_Code.jpg"));
+        let png = thumbnail_path(Path::new("/shots/09-00-00_Code.png"));
+        assert_eq!(jpg, Path::new("/shots/thumbs/09-00-00_Code.jpg.jpg"));
+        assert_ne!(jpg, png);
+    }
+
+    #[test]
+    fn leftover_staged_maps_back_to_final_paths() {
+        let dir = std::env::temp_dir().join(format!("screenshot_staging_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let image_path = dir.join("09-00-00_Code.webp");
+        let staged = staging_path(&image_path);
+        assert_eq!(staged, dir.join(STAGING_DIR).join("09-00-00_Code.webp.png"));
+
+        fs::create_dir_all(staged.parent().unwrap()).unwrap();
+        fs::write(&staged, b"").unwrap();
+        fs::write(dir.join(STAGING_DIR).join("notes.txt"), b"").unwrap();
+        let service = ScreenshotService::new(dir.clone());
+        assert_eq!(service.leftover_staged(), vec![image_path]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_distance_counts_differing_bits() {
+        assert_eq!(hash_distance(0, 0), 0);
+        assert_eq!(hash_distance(0b1011, 0b0001), 2);
+        assert_eq!(hash_distance(0, u64::MAX), 64);
+    }
+
+    #[test]
+    fn perceptual_hash_ignores_scale_and_small_noise() {
+        let base = gradient(320, 200, false);
+        let scaled = gradient(640, 400, false);
+        assert_eq!(hash_distance(perceptual_hash(&base), perceptual_hash(&scaled)), 0);
+
+        let mut noisy = base.to_luma8();
+        for (x, y) in [(10, 10), (100, 50), (200, 150)] {
+            noisy.put_pixel(x, y, Luma([0]));
+        }
+        let noisy = DynamicImage::ImageLuma8(noisy);
+        assert!(hash_distance(perceptual_hash(&base), perceptual_hash(&noisy)) <= 2);
+    }
+
+    #[test]
+    fn perceptual_hash_separates_different_images() {
+        let a = perceptual_hash(&gradient(320, 200, false));
+        let b = perceptual_hash(&gradient(320, 200, true));
+        assert!(hash_distance(a, b) > 32);
+    }
+}
@@ -0,0 +1,459 @@
+use crate::error::Result;
+use crate::services::OcrRepository;
+use crate::services::screenshot::{is_screenshot_file, thumbnail_path};
+use crate::models::{AppConfig, OcrStatus, ScreenshotPage, ScreenshotQuery, ScreenshotRecord, SortOrder};
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// 截图目录（按日期存放在当日 events.db 的 screenshots 表）
+pub struct ScreenshotCatalog {
+    conn: Connection,
+}
+
+impl ScreenshotCatalog {
+    /// 打开指定日期的截图目录，不存在时创建
+    pub fn open(config: &AppConfig, date: &str) -> Result<Self> {
+        let data_dir = config.get_date_dir(date);
+        fs::create_dir_all(&data_dir)?;
+
+        let conn = Connection::open(data_dir.join("events.db"))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS screenshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                end_timestamp TEXT NOT NULL,
+                image_path TEXT NOT NULL UNIQUE,
+                app_name TEXT NOT NULL,
+                window_title TEXT,
+                exe_path TEXT,
+                monitor TEXT,
+                width INTEGER NOT NULL DEFAULT 0,
+                height INTEGER NOT NULL DEFAULT 0,
+                hash TEXT,
+                ocr_status TEXT NOT NULL DEFAULT 'pending',
+                segment_id TEXT,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                note TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_screenshots_timestamp ON screenshots(timestamp);
+            CREATE INDEX IF NOT EXISTS idx_screenshots_app ON screenshots(app_name);",
+        )?;
+
+        // 旧版本的表没有收藏字段
+        let has_pinned: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM pragma_table_info('screenshots') WHERE name = 'pinned')",
+                [],
+                |row| row.get(0),
+            )?;
+        if !has_pinned {
+            conn.execute_batch(
+                "ALTER TABLE screenshots ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
+                 ALTER TABLE screenshots ADD COLUMN note TEXT;",
+            )?;
+        }
+
+        Ok(Self { conn })
+    }
+
+    /// 新增截图记录，返回行ID
+    pub fn insert(&self, record: &ScreenshotRecord) -> Result<i64> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO screenshots
+                (timestamp, end_timestamp, image_path, app_name, window_title, exe_path, monitor,
+                 width, height, hash, ocr_status, segment_id, pinned, note)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                record.timestamp,
+                record.end_timestamp,
+                record.image_path,
+                record.app_name,
+                record.window_title,
+                record.exe_path,
+                record.monitor,
+                record.width,
+                record.height,
+                record.hash,
+                record.ocr_status.as_str(),
+                record.segment_id,
+                record.pinned,
+                record.note,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 延长截图的时间范围（近似截图未保存时调用）
+    pub fn extend(&self, image_path: &str, end_timestamp: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE screenshots SET end_timestamp = ?1 WHERE image_path = ?2",
+            params![end_timestamp, image_path],
+        )?;
+        Ok(())
+    }
+
+    /// 更新OCR状态
+    pub fn set_ocr_status(&self, image_path: &str, status: OcrStatus) -> Result<()> {
+        self.conn.execute(
+            "UPDATE screenshots SET ocr_status = ?1 WHERE image_path = ?2",
+            params![status.as_str(), image_path],
+        )?;
+        Ok(())
+    }
+
+    /// 截图文件被重新编码后更新路径和尺寸（OCR记录由 [`crate::services::OcrRepository::relocate`] 更新）
+    pub fn relocate(&self, old_path: &str, new_path: &str, width: u32, height: u32) -> Result<()> {
+        self.conn.execute(
+            "UPDATE screenshots SET image_path = ?1, width = ?2, height = ?3 WHERE image_path = ?4",
+            params![new_path, width, height, old_path],
+        )?;
+        Ok(())
+    }
+
+    /// 收藏或取消收藏，收藏的截图不会被配额清理和清理缓存删除
+    pub fn set_pinned(&self, image_path: &str, pinned: bool, note: Option<&str>) -> Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE screenshots SET pinned = ?1, note = ?2 WHERE image_path = ?3",
+            params![pinned, note.filter(|n| !n.is_empty()), image_path],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// 所有收藏截图的路径
+    pub fn pinned_paths(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT image_path FROM screenshots WHERE pinned = 1")?;
+        let paths = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<String>, _>>()?;
+        Ok(paths)
+    }
+
+    /// 删除截图记录（文件已删除时调用）
+    pub fn remove(&self, image_path: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM screenshots WHERE image_path = ?1", params![image_path])?;
+        Ok(())
+    }
+
+    /// 按路径查询单条记录
+    pub fn get(&self, image_path: &str) -> Result<Option<ScreenshotRecord>> {
+        Ok(self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM screenshots WHERE image_path = ?1", COLUMNS),
+                params![image_path],
+                read_record,
+            )
+            .optional()?)
+    }
+
+    /// 分页查询
+    pub fn query(&self, query: &ScreenshotQuery) -> Result<ScreenshotPage> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut args: Vec<String> = Vec::new();
+
+        if let Some(app) = &query.app_name {
+            conditions.push("app_name = ?");
+            args.push(app.clone());
+        }
+        if let Some(title) = &query.title_contains {
+            conditions.push("window_title LIKE ? ESCAPE '\\'");
+            args.push(format!("%{}%", escape_like(title)));
+        }
+        if let Some(pinned) = query.pinned {
+            conditions.push("pinned = ?");
+            args.push((pinned as i32).to_string());
+        }
+        if let Some(status) = query.ocr_status {
+            conditions.push("ocr_status = ?");
+            args.push(status.as_str().to_string());
+        }
+        // timestamp 形如 2024-01-01T09:30:00，比较时间部分
+        if let Some(start) = &query.start_time {
+            conditions.push("substr(timestamp, 12) >= ?");
+            args.push(start.clone());
+        }
+        if let Some(end) = &query.end_time {
+            conditions.push("substr(timestamp, 12) <= ?");
+            args.push(end.clone());
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let total: u32 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM screenshots {}", where_clause),
+            params_from_iter(args.iter()),
+            |row| row.get(0),
+        )?;
+
+        let order = match query.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        let sql = format!(
+            "SELECT {} FROM screenshots {} ORDER BY timestamp {}, id {} LIMIT {} OFFSET {}",
+            COLUMNS, where_clause, order, order, query.limit, query.offset
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let items = stmt
+            .query_map(params_from_iter(args.iter()), read_record)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(ScreenshotPage { total, items })
+    }
+
+    /// 还没有OCR记录的截图（按时间升序）
+    ///
+    /// 识别过但没有文本的、按配置跳过的和等待脱敏的除外；`pending_before` 之后截取的待识别截图
+    /// 仍在正常OCR队列中，也不返回。
+    pub fn without_ocr(&self, ocr: &OcrRepository, pending_before: &str) -> Result<Vec<ScreenshotRecord>> {
+        let recognized = ocr.paths_with_records()?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM screenshots
+             WHERE ocr_status NOT IN ('empty', 'skipped', 'redacting')
+               AND NOT (ocr_status = 'pending' AND timestamp >= ?1)
+             ORDER BY timestamp, id",
+            COLUMNS
+        ))?;
+        let items = stmt
+            .query_map([pending_before], read_record)?
+            .filter(|record| !record.as_ref().is_ok_and(|r| recognized.contains(&r.image_path)))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(items)
+    }
+
+    /// 当日出现过的应用（按截图数量降序）
+    pub fn apps(&self) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT app_name FROM screenshots GROUP BY app_name ORDER BY COUNT(*) DESC")?;
+        let apps = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+        Ok(apps)
+    }
+
+    /// 将目录中尚未登记的截图文件补录进目录（升级前的截图）
+    ///
+    /// 时间和应用名从文件名 `HH-MM-SS_app.jpg` 解析，已有OCR记录的标记为已识别。
+    pub fn import_untracked(&self, date: &str, screenshots_dir: &Path, ocr: &OcrRepository) -> Result<usize> {
+        let Ok(entries) = fs::read_dir(screenshots_dir) else {
+            return Ok(0);
+        };
+
+        let mut imported = 0;
+        for path in entries.flatten().map(|e| e.path()) {
+            let is_image = is_screenshot_file(&path);
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if !is_image || !stem.is_char_boundary(8) {
+                continue;
+            }
+            let (time, app) = stem.split_at(8);
+            if !time.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+                continue;
+            }
+
+            let image_path = path.to_string_lossy().to_string();
+            if self.get(&image_path)?.is_some() {
+                continue;
+            }
+
+            let timestamp = format!("{}T{}", date, time.replace('-', ":"));
+            let (width, height) = image::image_dimensions(&path).unwrap_or((0, 0));
+            let ocr_done = ocr.has_record(&image_path)?;
+
+            self.insert(&ScreenshotRecord {
+                timestamp: timestamp.clone(),
+                end_timestamp: timestamp,
+                image_path,
+                app_name: app.trim_start_matches('_').to_string(),
+                width,
+                height,
+                ocr_status: if ocr_done { OcrStatus::Done } else { OcrStatus::Pending },
+                ..Default::default()
+            })?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
+
+const COLUMNS: &str = "id, timestamp, end_timestamp, image_path, app_name, window_title, exe_path, monitor, \
+                       width, height, hash, ocr_status, segment_id, pinned, note";
+
+/// 转义 LIKE 模式中的通配符，配合 `ESCAPE '\'` 按字面匹配用户输入
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn read_record(row: &Row) -> rusqlite::Result<ScreenshotRecord> {
+    let image_path: String = row.get(3)?;
+    let thumbnail = thumbnail_path(Path::new(&image_path));
+    Ok(ScreenshotRecord {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        end_timestamp: row.get(2)?,
+        image_path,
+        app_name: row.get(4)?,
+        window_title: row.get(5)?,
+        exe_path: row.get(6)?,
+        monitor: row.get(7)?,
+        width: row.get(8)?,
+        height: row.get(9)?,
+        hash: row.get(10)?,
+        ocr_status: OcrStatus::parse(&row.get::<_, String>(11)?),
+        segment_id: row.get(12)?,
+        thumbnail_path: thumbnail.exists().then(|| thumbnail.to_string_lossy().to_string()),
+        pinned: row.get(13)?,
+        note: row.get(14)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(name: &str) -> AppConfig {
+        let mut config = AppConfig::default();
+        config.data_dir = std::env::temp_dir().join(format!("screenshot_catalog_{}_{}", name, std::process::id()));
+        config
+    }
+
+    fn open_catalog(name: &str) -> ScreenshotCatalog {
+        let config = test_config(name);
+        let _ = fs::remove_dir_all(&config.data_dir);
+        ScreenshotCatalog::open(&config, "2024-01-01").unwrap()
+    }
+
+    fn record(time: &str, app: &str, title: &str, pinned: bool, ocr_status: OcrStatus) -> ScreenshotRecord {
+        let timestamp = format!("2024-01-01T{}", time);
+        ScreenshotRecord {
+            timestamp: timestamp.clone(),
+            end_timestamp: timestamp,
+            image_path: format!("/shots/{}_{}.jpg", time.replace(':', "-"), app),
+            app_name: app.to_string(),
+            window_title: Some(title.to_string()),
+            ocr_status,
+            pinned,
+            ..Default::default()
+        }
+    }
+
+    fn seeded(name: &str) -> ScreenshotCatalog {
+        let catalog = open_catalog(name);
+        for r in [
+            record("09:00:00", "Code", "main.rs - crate", false, OcrStatus::Done),
+            record("10:30:00", "Firefox", "Rust docs", true, OcrStatus::Pending),
+            record("12:00:00", "Code", "lib.rs - crate", true, OcrStatus::Empty),
+            record("18:45:00", "Slack", "general", false, OcrStatus::Done),
+        ] {
+            catalog.insert(&r).unwrap();
+        }
+        catalog
+    }
+
+    fn times(page: &ScreenshotPage) -> Vec<&str> {
+        page.items.iter().map(|r| &r.timestamp[11..]).collect()
+    }
+
+    #[test]
+    fn query_filters_combine() {
+        let catalog = seeded("filters");
+
+        let page = catalog
+            .query(&ScreenshotQuery { app_name: Some("Code".into()), ..Default::default() })
+            .unwrap();
+        assert_eq!(times(&page), ["09:00:00", "12:00:00"]);
+
+        let page = catalog
+            .query(&ScreenshotQuery { title_contains: Some("rs".into()), pinned: Some(true), ..Default::default() })
+            .unwrap();
+        assert_eq!(times(&page), ["12:00:00"]);
+
+        let page = catalog
+            .query(&ScreenshotQuery { ocr_status: Some(OcrStatus::Done), ..Default::default() })
+            .unwrap();
+        assert_eq!(times(&page), ["09:00:00", "18:45:00"]);
+
+        // 时间范围含边界
+        let page = catalog
+            .query(&ScreenshotQuery {
+                start_time: Some("10:30:00".into()),
+                end_time: Some("12:00:00".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(times(&page), ["10:30:00", "12:00:00"]);
+    }
+
+    #[test]
+    fn title_filter_matches_wildcards_literally() {
+        let catalog = open_catalog("like_escape");
+        for r in [
+            record("09:00:00", "Code", "100% done", false, OcrStatus::Done),
+            record("10:00:00", "Code", "1000 done", false, OcrStatus::Done),
+            record("11:00:00", "Code", "my_file.rs", false, OcrStatus::Done),
+            record("12:00:00", "Code", "myXfile.rs", false, OcrStatus::Done),
+            record("13:00:00", "Code", "C:\\temp", false, OcrStatus::Done),
+        ] {
+            catalog.insert(&r).unwrap();
+        }
+        let titled = |title: &str| {
+            let query = ScreenshotQuery { title_contains: Some(title.into()), ..Default::default() };
+            times(&catalog.query(&query).unwrap()).into_iter().map(String::from).collect::<Vec<_>>()
+        };
+        assert_eq!(titled("0%"), ["09:00:00"]);
+        assert_eq!(titled("y_f"), ["11:00:00"]);
+        assert_eq!(titled(":\\t"), ["13:00:00"]);
+    }
+
+    #[test]
+    fn query_pages_and_orders() {
+        let catalog = seeded("paging");
+
+        let page = catalog
+            .query(&ScreenshotQuery { order: SortOrder::Desc, offset: 1, limit: 2, ..Default::default() })
+            .unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(times(&page), ["12:00:00", "10:30:00"]);
+    }
+
+    #[test]
+    fn without_ocr_skips_finished_skipped_and_queued() {
+        let catalog = seeded("without_ocr");
+        for r in [
+            record("20:00:00", "Mail", "inbox", false, OcrStatus::Redacting),
+            record("21:00:00", "Game", "fullscreen", false, OcrStatus::Skipped),
+            record("22:00:00", "Code", "queued", false, OcrStatus::Pending),
+        ] {
+            catalog.insert(&r).unwrap();
+        }
+        let ocr = OcrRepository::open(&test_config("without_ocr"), "2024-01-01").unwrap();
+        let cutoff = "2024-01-01T21:30:00";
+        let pending: Vec<_> = catalog.without_ocr(&ocr, cutoff).unwrap().into_iter().map(|r| r.app_name).collect();
+        assert_eq!(pending, ["Code", "Firefox", "Slack"]);
+        assert_eq!(catalog.without_ocr(&ocr, "2024-01-02T00:00:00").unwrap().len(), 4);
+
+        let slack = catalog.without_ocr(&ocr, cutoff).unwrap().pop().unwrap();
+        ocr.insert(&crate::services::OcrRecord {
+            id: 0,
+            timestamp: slack.timestamp,
+            image_path: slack.image_path,
+            text: "general".to_string(),
+            app_name: Some(slack.app_name),
+            lines: Vec::new(),
+            base_id: None,
+        })
+        .unwrap();
+        let pending: Vec<_> = catalog.without_ocr(&ocr, cutoff).unwrap().into_iter().map(|r| r.app_name).collect();
+        assert_eq!(pending, ["Code", "Firefox"]);
+    }
+}
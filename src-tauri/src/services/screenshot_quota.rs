@@ -0,0 +1,195 @@
+use crate::error::Result;
+use crate::models::AppConfig;
+use crate::services::{OcrRepository, ScreenshotCatalog, is_screenshot_file, thumbnail_path};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 截图占用空间
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScreenshotUsage {
+    /// 全部截图（含缩略图）占用的字节数
+    pub used_bytes: u64,
+    /// 其中收藏截图占用的字节数
+    pub pinned_bytes: u64,
+    /// 配额（字节，0=不限制）
+    pub quota_bytes: u64,
+    pub files: u32,
+    pub pinned_files: u32,
+}
+
+/// 清理结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvictionReport {
+    pub deleted: u32,
+    pub freed_bytes: u64,
+}
+
+/// 一张截图及其缩略图
+struct ShotFile {
+    date: String,
+    path: PathBuf,
+    bytes: u64,
+    pinned: bool,
+}
+
+/// 含截图目录的日期（升序）
+fn screenshot_dates(config: &AppConfig) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(&config.data_dir) else {
+        return Vec::new();
+    };
+    let mut dates: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.path().join("screenshots").is_dir())
+        .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+        .collect();
+    dates.sort();
+    dates
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// 按时间从早到晚列出所有截图
+fn scan(config: &AppConfig) -> Result<Vec<ShotFile>> {
+    let mut files = Vec::new();
+    for date in screenshot_dates(config) {
+        let pinned = ScreenshotCatalog::open(config, &date)?.pinned_paths()?;
+        let mut paths: Vec<PathBuf> = fs::read_dir(config.get_date_dir(&date).join("screenshots"))?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| is_screenshot_file(p))
+            .collect();
+        // 文件名以 HH-MM-SS 开头，按名称排序即按时间排序
+        paths.sort();
+
+        for path in paths {
+            files.push(ShotFile {
+                date: date.clone(),
+                bytes: file_size(&path) + file_size(&thumbnail_path(&path)),
+                pinned: pinned.contains(path.to_string_lossy().as_ref()),
+                path,
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// 统计截图占用空间
+pub fn screenshot_usage(config: &AppConfig) -> Result<ScreenshotUsage> {
+    let mut usage = ScreenshotUsage {
+        quota_bytes: config.screenshot_quota_mb * 1024 * 1024,
+        ..Default::default()
+    };
+    for file in scan(config)? {
+        usage.used_bytes += file.bytes;
+        usage.files += 1;
+        if file.pinned {
+            usage.pinned_bytes += file.bytes;
+            usage.pinned_files += 1;
+        }
+    }
+    Ok(usage)
+}
+
+/// 超出配额时从最早的截图开始删除（跳过收藏），直到回到配额以内
+pub fn enforce_screenshot_quota(config: &AppConfig) -> Result<EvictionReport> {
+    let quota = config.screenshot_quota_mb * 1024 * 1024;
+    if quota == 0 {
+        return Ok(EvictionReport::default());
+    }
+
+    let victims = select_victims(scan(config)?, quota);
+    delete_files(config, victims.into_iter())
+}
+
+/// 从最早的未收藏截图开始选出要删除的文件，直到总占用回到配额以内
+fn select_victims(files: Vec<ShotFile>, quota: u64) -> Vec<ShotFile> {
+    let mut used: u64 = files.iter().map(|f| f.bytes).sum();
+    files
+        .into_iter()
+        .filter(|f| !f.pinned)
+        .take_while(|f| {
+            if used <= quota {
+                return false;
+            }
+            used = used.saturating_sub(f.bytes);
+            true
+        })
+        .collect()
+}
+
+/// 删除所有未收藏的截图（清理缓存）
+///
+/// 暂存目录中等待脱敏的截图不在此列，由识别任务或下次启动时处理。
+pub fn clear_unpinned_screenshots(config: &AppConfig) -> Result<EvictionReport> {
+    let files = scan(config)?;
+    delete_files(config, files.into_iter().filter(|f| !f.pinned))
+}
+
+/// 删除截图文件、缩略图、目录记录和OCR记录；单个文件删除失败时跳过
+fn delete_files(config: &AppConfig, files: impl Iterator<Item = ShotFile>) -> Result<EvictionReport> {
+    let mut report = EvictionReport::default();
+    let mut stores: Option<(String, ScreenshotCatalog, OcrRepository)> = None;
+
+    for file in files {
+        if let Err(e) = fs::remove_file(&file.path) {
+            eprintln!("删除截图失败 {}: {}", file.path.display(), e);
+            continue;
+        }
+        let _ = fs::remove_file(thumbnail_path(&file.path));
+        report.deleted += 1;
+        report.freed_bytes += file.bytes;
+
+        if stores.as_ref().is_none_or(|(date, _, _)| *date != file.date) {
+            stores = Some((
+                file.date.clone(),
+                ScreenshotCatalog::open(config, &file.date)?,
+                OcrRepository::open(config, &file.date)?,
+            ));
+        }
+        if let Some((_, catalog, ocr)) = &stores {
+            let path = file.path.to_string_lossy();
+            catalog.remove(&path)?;
+            ocr.delete_by_image_path(&path)?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shot(name: &str, bytes: u64, pinned: bool) -> ShotFile {
+        ShotFile { date: "2024-01-01".to_string(), path: PathBuf::from(name), bytes, pinned }
+    }
+
+    fn names(files: &[ShotFile]) -> Vec<&str> {
+        files.iter().map(|f| f.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn select_victims_removes_oldest_until_within_quota() {
+        let files = vec![shot("a", 40, false), shot("b", 30, false), shot("c", 20, false), shot("d", 10, false)];
+        // 共 100，配额 50：删除 a(40) 后 60 仍超出，再删除 b(30)
+        assert_eq!(names(&select_victims(files, 50)), ["a", "b"]);
+    }
+
+    #[test]
+    fn select_victims_skips_pinned() {
+        let files = vec![shot("a", 40, true), shot("b", 30, false), shot("c", 20, false), shot("d", 10, false)];
+        assert_eq!(names(&select_victims(files, 50)), ["b", "c"]);
+    }
+
+    #[test]
+    fn select_victims_within_quota_or_all_pinned() {
+        let files = vec![shot("a", 40, false), shot("b", 30, false)];
+        assert!(select_victims(files, 70).is_empty());
+
+        // 收藏的截图超出配额也不删除
+        let files = vec![shot("a", 40, true), shot("b", 30, true)];
+        assert!(select_victims(files, 10).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::models::AppConfig;
+use regex::Regex;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 自动截图的触发原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenshotTrigger {
+    /// 在同一应用停留达到触发时长
+    Dwell,
+    /// 距上次截图达到间隔
+    Interval,
+    /// 切换了前台应用
+    AppSwitch,
+    /// 窗口标题匹配规则
+    TitleMatch,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    app: String,
+    title: String,
+    stay_start: Option<Instant>,
+    last_shot: Option<Instant>,
+    pending: Option<ScreenshotTrigger>,
+    /// 已编译的标题规则（配置中的原始规则变化时重新编译）
+    pattern_source: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl SchedulerState {
+    fn title_matches(&mut self, title: &str, patterns: &[String]) -> bool {
+        if self.pattern_source != patterns {
+            self.patterns = patterns
+                .iter()
+                .filter_map(|p| match Regex::new(p) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        eprintln!("标题规则无效 {}: {}", p, e);
+                        None
+                    }
+                })
+                .collect();
+            self.pattern_source = patterns.to_vec();
+        }
+        self.patterns.iter().any(|re| re.is_match(title))
+    }
+}
+
+/// 自动截图调度器（作为Tauri State管理）
+///
+/// 只决定何时截图：焦点变化通过 `on_focus_change` 输入，后台线程定期调用 `poll` 取出触发。
+/// 首张截图在同一应用停留 `screenshot_trigger_sec` 后触发，之后按 `screenshot_interval_sec` 间隔触发；
+/// 标题匹配触发与间隔触发共用间隔限制；空闲期间不触发。
+pub struct ScreenshotScheduler {
+    state: Mutex<SchedulerState>,
+}
+
+impl ScreenshotScheduler {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(SchedulerState::default()),
+        }
+    }
+
+    /// 记录前台窗口变化，并按配置登记切换应用/标题匹配触发
+    pub fn on_focus_change(&self, app: &str, title: &str, config: &AppConfig) {
+        self.on_focus_change_at(app, title, config, Instant::now());
+    }
+
+    fn on_focus_change_at(&self, app: &str, title: &str, config: &AppConfig, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+
+        let app_changed = state.app != app;
+        if app_changed {
+            let had_app = !state.app.is_empty();
+            state.stay_start = Some(now);
+            state.last_shot = None;
+            state.pending = (config.screenshot_on_app_switch && had_app).then_some(ScreenshotTrigger::AppSwitch);
+        }
+        let interval = Duration::from_secs(config.screenshot_interval_sec as u64);
+        if (app_changed || state.title != title)
+            && state.pending.is_none()
+            && state.last_shot.is_none_or(|last| now - last >= interval)
+            && state.title_matches(title, &config.screenshot_title_patterns)
+        {
+            state.pending = Some(ScreenshotTrigger::TitleMatch);
+        }
+
+        state.app = app.to_string();
+        state.title = title.to_string();
+    }
+
+    /// 检查当前是否应该截图，返回触发原因和当前应用名
+    pub fn poll(&self, config: &AppConfig, idle_seconds: u64) -> Option<(ScreenshotTrigger, String)> {
+        self.poll_at(config, idle_seconds, Instant::now())
+    }
+
+    fn poll_at(&self, config: &AppConfig, idle_seconds: u64, now: Instant) -> Option<(ScreenshotTrigger, String)> {
+        let mut state = self.state.lock().unwrap();
+
+        if !config.screenshot_enabled || state.app.is_empty() || idle_seconds >= config.idle_threshold_sec {
+            state.pending = None;
+            return None;
+        }
+
+        let trigger = state.pending.take().or_else(|| match state.last_shot {
+            None => state
+                .stay_start
+                .filter(|start| now - *start >= Duration::from_secs(config.screenshot_trigger_sec as u64))
+                .map(|_| ScreenshotTrigger::Dwell),
+            Some(last) if now - last >= Duration::from_secs(config.screenshot_interval_sec as u64) => {
+                Some(ScreenshotTrigger::Interval)
+            }
+            Some(_) => None,
+        })?;
+
+        state.last_shot = Some(now);
+        Some((trigger, state.app.clone()))
+    }
+}
+
+impl Default for ScreenshotScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.screenshot_enabled = true;
+        config.screenshot_trigger_sec = 10;
+        config.screenshot_interval_sec = 60;
+        config.screenshot_on_app_switch = true;
+        config.screenshot_title_patterns = vec!["(?i)invoice".to_string()];
+        config.idle_threshold_sec = 300;
+        config
+    }
+
+    fn at(start: Instant, secs: u64) -> Instant {
+        start + Duration::from_secs(secs)
+    }
+
+    fn trigger(scheduler: &ScreenshotScheduler, config: &AppConfig, now: Instant) -> Option<ScreenshotTrigger> {
+        scheduler.poll_at(config, 0, now).map(|(trigger, _)| trigger)
+    }
+
+    #[test]
+    fn dwell_then_interval() {
+        let (scheduler, config, start) = (ScreenshotScheduler::new(), config(), Instant::now());
+        assert_eq!(trigger(&scheduler, &config, start), None);
+        scheduler.on_focus_change_at("Code", "main.rs", &config, start);
+        assert_eq!(trigger(&scheduler, &config, at(start, 9)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 10)), Some(ScreenshotTrigger::Dwell));
+        assert_eq!(trigger(&scheduler, &config, at(start, 69)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 70)), Some(ScreenshotTrigger::Interval));
+        assert_eq!(trigger(&scheduler, &config, at(start, 71)), None);
+    }
+
+    #[test]
+    fn first_app_does_not_count_as_switch() {
+        let (scheduler, config, start) = (ScreenshotScheduler::new(), config(), Instant::now());
+        scheduler.on_focus_change_at("Code", "main.rs", &config, start);
+        assert_eq!(trigger(&scheduler, &config, at(start, 1)), None);
+
+        scheduler.on_focus_change_at("Firefox", "docs", &config, at(start, 2));
+        assert_eq!(trigger(&scheduler, &config, at(start, 2)), Some(ScreenshotTrigger::AppSwitch));
+        // 切换后停留计时重新开始
+        assert_eq!(trigger(&scheduler, &config, at(start, 11)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 62)), Some(ScreenshotTrigger::Interval));
+    }
+
+    #[test]
+    fn app_switch_respects_config() {
+        let (scheduler, mut config, start) = (ScreenshotScheduler::new(), config(), Instant::now());
+        config.screenshot_on_app_switch = false;
+        scheduler.on_focus_change_at("Code", "main.rs", &config, start);
+        scheduler.on_focus_change_at("Firefox", "docs", &config, at(start, 2));
+        assert_eq!(trigger(&scheduler, &config, at(start, 2)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 12)), Some(ScreenshotTrigger::Dwell));
+    }
+
+    #[test]
+    fn idle_drops_pending_trigger() {
+        let (scheduler, config, start) = (ScreenshotScheduler::new(), config(), Instant::now());
+        scheduler.on_focus_change_at("Code", "main.rs", &config, start);
+        scheduler.on_focus_change_at("Firefox", "docs", &config, at(start, 1));
+        assert_eq!(scheduler.poll_at(&config, 300, at(start, 1)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 2)), None);
+        assert_eq!(trigger(&scheduler, &config, at(start, 11)), Some(ScreenshotTrigger::Dwell));
+
+        let mut disabled = config.clone();
+        disabled.screenshot_enabled = false;
+        assert_eq!(scheduler.poll_at(&disabled, 0, at(start, 200)), None);
+    }
+
+    #[test]
+    fn title_match_is_limited_by_interval() {
+        let (scheduler, config, start) = (ScreenshotScheduler::new(), config(), Instant::now());
+        scheduler.on_focus_change_at("Mail", "inbox", &config, start);
+        assert_eq!(trigger(&scheduler, &config, at(start, 1)), None);
+
+        scheduler.on_focus_change_at("Mail", "Invoice 1", &config, at(start, 2));
+        assert_eq!(trigger(&scheduler, &config, at(start, 2)), Some(ScreenshotTrigger::TitleMatch));
+        // 间隔内标题再变化不再触发
+        scheduler.on_focus_change_at("Mail", "Invoice 2", &config, at(start, 3));
+        assert_eq!(trigger(&scheduler, &config, at(start, 3)), None);
+        scheduler.on_focus_change_at("Mail", "Invoice 3", &config, at(start, 62));
+        assert_eq!(trigger(&scheduler, &config, at(start, 62)), Some(ScreenshotTrigger::TitleMatch));
+        // 标题不变或不匹配时只有间隔触发
+        scheduler.on_focus_change_at("Mail", "Invoice 3", &config, at(start, 130));
+        scheduler.on_focus_change_at("Mail", "inbox", &config, at(start, 131));
+        assert_eq!(trigger(&scheduler, &config, at(start, 131)), Some(ScreenshotTrigger::Interval));
+        scheduler.on_focus_change_at("Mail", "drafts", &config, at(start, 150));
+        assert_eq!(trigger(&scheduler, &config, at(start, 150)), None);
+    }
+}
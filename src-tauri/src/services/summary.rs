@@ -0,0 +1,159 @@
+use crate::models::{
+    AppUsage, DailySummaryPack, EventType, MonitorUsage, RawEvent, StateSegment, WorkspaceUsage,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// 状态段ID：由开启该段的焦点事件时刻确定，重复生成汇总时保持不变，供截图等数据关联
+pub fn segment_id_at(timestamp: DateTime<Utc>) -> String {
+    format!("seg-{}", timestamp.timestamp_millis())
+}
+
+/// 由原始事件生成每日数据封包
+///
+/// 每个焦点事件开启一个状态段，持续到下一个焦点事件；段内与空闲区间重叠的时间不计入活跃时长。
+pub fn build_daily_summary(date: &str, events: &[RawEvent]) -> DailySummaryPack {
+    let idle_intervals = merge_intervals(
+        events
+            .iter()
+            .filter(|e| e.event_type == EventType::Idle)
+            .filter_map(|e| {
+                let end = e.timestamp.with_timezone(&Utc);
+                let duration = e.metadata.idle_duration_sec? as i64;
+                Some((end - Duration::seconds(duration), end))
+            })
+            .collect(),
+    );
+
+    // 按焦点事件切分状态段
+    let mut segments: Vec<StateSegment> = Vec::new();
+    for event in events.iter().filter(|e| e.event_type == EventType::AppFocus) {
+        let timestamp = event.timestamp.with_timezone(&Utc);
+        if let Some(last) = segments.last_mut() {
+            last.update_end_time(timestamp);
+        }
+        let mut segment = StateSegment::new(
+            event.app.clone().unwrap_or_default(),
+            event.window_title.clone().unwrap_or_default(),
+            timestamp,
+        );
+        segment.id = segment_id_at(timestamp);
+        segment.workspace = event.workspace;
+        segment.monitor = event.monitor.clone();
+        segment.fullscreen = event.fullscreen;
+        segments.push(segment);
+    }
+    if let (Some(last), Some(last_event)) = (segments.last_mut(), events.last()) {
+        let end = last_event.timestamp.with_timezone(&Utc);
+        if end > last.start_time {
+            last.update_end_time(end);
+        }
+    }
+
+    // 扣除空闲时间后的活跃秒数
+    let active_secs: Vec<i64> = segments
+        .iter()
+        .map(|s| {
+            let total = (s.end_time - s.start_time).num_seconds();
+            let idle: i64 = idle_intervals
+                .iter()
+                .map(|(start, end)| overlap_secs((s.start_time, s.end_time), (*start, *end)))
+                .sum();
+            (total - idle).max(0)
+        })
+        .collect();
+    for (segment, secs) in segments.iter_mut().zip(&active_secs) {
+        segment.duration_min = (*secs / 60) as u32;
+    }
+
+    // 输入统计归属到同一应用最近开始的状态段
+    for event in events {
+        let (keyboard, mouse) = match event.event_type {
+            EventType::Keyboard => (event.metadata.key_count.unwrap_or(0), 0),
+            EventType::Mouse => (0, event.metadata.click_count.unwrap_or(0)),
+            _ => continue,
+        };
+        let timestamp = event.timestamp.with_timezone(&Utc);
+        if let Some(segment) = segments
+            .iter_mut()
+            .rev()
+            .find(|s| s.start_time <= timestamp && event.app.as_deref() == Some(s.app.as_str()))
+        {
+            segment.add_input(keyboard, mouse);
+        }
+    }
+
+    let total_active: i64 = active_secs.iter().sum();
+    let total_idle: i64 = idle_intervals
+        .iter()
+        .map(|(start, end)| (*end - *start).num_seconds())
+        .sum();
+
+    let app_usage = breakdown(&segments, &active_secs, total_active, |s| Some(s.app.clone()))
+        .into_iter()
+        .map(|(app, total_minutes, percentage)| AppUsage { app, total_minutes, percentage })
+        .collect();
+    let workspace_usage = breakdown(&segments, &active_secs, total_active, |s| s.workspace)
+        .into_iter()
+        .map(|(workspace, total_minutes, percentage)| WorkspaceUsage { workspace, total_minutes, percentage })
+        .collect();
+    let monitor_usage = breakdown(&segments, &active_secs, total_active, |s| s.monitor.clone())
+        .into_iter()
+        .map(|(monitor, total_minutes, percentage)| MonitorUsage { monitor, total_minutes, percentage })
+        .collect();
+
+    DailySummaryPack {
+        date: date.to_string(),
+        segments,
+        total_active_minutes: (total_active / 60) as u32,
+        total_idle_minutes: (total_idle / 60) as u32,
+        app_usage,
+        workspace_usage,
+        monitor_usage,
+    }
+}
+
+/// 按指定维度汇总活跃时长，返回 (键, 分钟数, 百分比)，按时长降序
+fn breakdown<K, F>(segments: &[StateSegment], active_secs: &[i64], total: i64, key: F) -> Vec<(K, u32, f32)>
+where
+    K: Eq + Hash,
+    F: Fn(&StateSegment) -> Option<K>,
+{
+    let mut totals: HashMap<K, i64> = HashMap::new();
+    for (segment, secs) in segments.iter().zip(active_secs) {
+        if let Some(k) = key(segment) {
+            *totals.entry(k).or_default() += secs;
+        }
+    }
+
+    let mut result: Vec<(K, u32, f32)> = totals
+        .into_iter()
+        .map(|(k, secs)| {
+            let percentage = if total > 0 { secs as f32 / total as f32 * 100.0 } else { 0.0 };
+            (k, (secs / 60) as u32, percentage)
+        })
+        .collect();
+    result.sort_by(|a, b| b.2.total_cmp(&a.2));
+    result
+}
+
+/// 合并重叠的时间区间
+fn merge_intervals(mut intervals: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    intervals.sort_by_key(|(start, _)| *start);
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 两个区间重叠的秒数
+fn overlap_secs(a: (DateTime<Utc>, DateTime<Utc>), b: (DateTime<Utc>, DateTime<Utc>)) -> i64 {
+    let start = a.0.max(b.0);
+    let end = a.1.min(b.1);
+    (end - start).num_seconds().max(0)
+}
@@ -0,0 +1,445 @@
+use crate::models::{AppConfig, ScreenshotQuery, ScreenshotRecord, SortOrder};
+use crate::services::{OcrRepository, ScreenshotCatalog};
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use image::imageops::{self, FilterType};
+use image::{Delay, Frame, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+/// 延时视频格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelapseFormat {
+    /// 动图，无需外部工具
+    Gif,
+    /// 动态WebP（需要ffmpeg）
+    Webp,
+    /// H.264视频（需要ffmpeg）
+    Mp4,
+}
+
+impl TimelapseFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            TimelapseFormat::Gif => "gif",
+            TimelapseFormat::Webp => "webp",
+            TimelapseFormat::Mp4 => "mp4",
+        }
+    }
+}
+
+/// 延时视频导出选项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimelapseOptions {
+    pub format: TimelapseFormat,
+    /// 每秒帧数（每张截图一帧）
+    pub fps: u32,
+    /// 输出宽度，高度按第一张截图的比例计算
+    pub width: u32,
+    /// 在左上角叠加时间和应用名
+    pub overlay: bool,
+    /// 只导出该应用的截图
+    pub app_name: Option<String>,
+}
+
+impl Default for TimelapseOptions {
+    fn default() -> Self {
+        Self {
+            format: TimelapseFormat::Gif,
+            fps: 4,
+            width: 1280,
+            overlay: true,
+            app_name: None,
+        }
+    }
+}
+
+/// 导出进度
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelapseProgress {
+    /// render=生成帧, encode=编码, done=完成
+    pub stage: &'static str,
+    pub current: usize,
+    pub total: usize,
+}
+
+/// 将指定日期的截图按时间顺序导出为延时动图或视频，返回输出文件路径
+///
+/// GIF 直接编码；WebP 和 MP4 先把帧写入临时目录，再调用本机 ffmpeg 编码。
+pub fn export_timelapse(
+    config: &AppConfig,
+    date: &str,
+    options: &TimelapseOptions,
+    mut on_progress: impl FnMut(TimelapseProgress),
+) -> Result<PathBuf, String> {
+    let records = load_records(config, date, options.app_name.as_deref())?;
+    if records.is_empty() {
+        return Err("当天没有截图".to_string());
+    }
+
+    let ffmpeg = match options.format {
+        TimelapseFormat::Gif => None,
+        _ => Some(find_ffmpeg().ok_or("未找到ffmpeg，请安装后重试，或改为导出GIF")?),
+    };
+
+    let (width, height) = frame_size(&records, options.width)?;
+    let font = if options.overlay { load_overlay_font() } else { None };
+    if options.overlay && font.is_none() {
+        eprintln!("未找到可用字体，导出时不叠加文字");
+    }
+
+    let date_dir = config.get_date_dir(date);
+    let output = date_dir.join(output_name(date, &records, options));
+    let total = records.len();
+    let fps = options.fps.clamp(1, 60);
+
+    let mut encode = || -> Result<(), String> {
+        match &ffmpeg {
+            None => {
+                let file = fs::File::create(&output).map_err(|e| format!("创建输出文件失败: {}", e))?;
+                let mut writer = BufWriter::new(file);
+                let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(&mut writer, 10);
+                encoder
+                    .set_repeat(image::codecs::gif::Repeat::Infinite)
+                    .map_err(|e| format!("GIF编码失败: {}", e))?;
+                for (index, record) in records.iter().enumerate() {
+                    let frame = render_frame(record, width, height, font.as_ref());
+                    encoder
+                        .encode_frame(Frame::from_parts(frame, 0, 0, Delay::from_numer_denom_ms(1000, fps)))
+                        .map_err(|e| format!("GIF编码失败: {}", e))?;
+                    on_progress(TimelapseProgress { stage: "render", current: index + 1, total });
+                }
+                // 释放编码器时写入文件尾，之后再刷新缓冲以便发现写入错误
+                drop(encoder);
+                writer.flush().map_err(|e| format!("写入输出文件失败: {}", e))
+            }
+            Some(ffmpeg) => {
+                let frames_dir = FramesDir::create(date_dir.join("timelapse_frames"))?;
+                for (index, record) in records.iter().enumerate() {
+                    render_frame(record, width, height, font.as_ref())
+                        .save(frames_dir.0.join(format!("frame_{:05}.png", index)))
+                        .map_err(|e| format!("保存帧失败: {}", e))?;
+                    on_progress(TimelapseProgress { stage: "render", current: index + 1, total });
+                }
+                run_ffmpeg(ffmpeg, &frames_dir.0, &output, options.format, fps, |frame| {
+                    on_progress(TimelapseProgress { stage: "encode", current: frame.min(total), total })
+                })
+            }
+        }
+    };
+    // 编码失败时不留下不完整的输出文件
+    if let Err(e) = encode() {
+        let _ = fs::remove_file(&output);
+        return Err(e);
+    }
+
+    on_progress(TimelapseProgress { stage: "done", current: total, total });
+    Ok(output)
+}
+
+/// 输出文件名：日期、截图时间范围、应用筛选和格式，不同范围和格式的导出互不覆盖
+fn output_name(date: &str, records: &[ScreenshotRecord], options: &TimelapseOptions) -> String {
+    // timestamp 形如 2024-01-01T09:30:00，取 HHMM
+    let time = |record: Option<&ScreenshotRecord>| {
+        record
+            .and_then(|r| r.timestamp.get(11..16))
+            .map(|t| t.replace(':', ""))
+            .unwrap_or_default()
+    };
+    let app: String = options
+        .app_name
+        .iter()
+        .flat_map(|app| std::iter::once('_').chain(app.chars().take(30)))
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!(
+        "timelapse_{}_{}-{}{}.{}",
+        date,
+        time(records.first()),
+        time(records.last()),
+        app,
+        options.format.extension()
+    )
+}
+
+/// 临时帧目录，离开作用域时删除
+struct FramesDir(PathBuf);
+
+impl FramesDir {
+    fn create(path: PathBuf) -> Result<Self, String> {
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for FramesDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// 按时间升序读取截图目录中的全部记录（跳过文件已不存在的）
+fn load_records(config: &AppConfig, date: &str, app_name: Option<&str>) -> Result<Vec<ScreenshotRecord>, String> {
+    let catalog = ScreenshotCatalog::open(config, date).map_err(|e| format!("打开截图目录失败: {}", e))?;
+    let ocr = OcrRepository::open(config, date).map_err(|e| format!("打开OCR记录失败: {}", e))?;
+    catalog
+        .import_untracked(date, &config.get_date_dir(date).join("screenshots"), &ocr)
+        .map_err(|e| format!("补录截图失败: {}", e))?;
+
+    let mut records = Vec::new();
+    let mut query = ScreenshotQuery {
+        app_name: app_name.map(|s| s.to_string()),
+        order: SortOrder::Asc,
+        limit: 500,
+        ..Default::default()
+    };
+    loop {
+        let page = catalog.query(&query).map_err(|e| format!("查询截图失败: {}", e))?;
+        if page.items.is_empty() {
+            break;
+        }
+        query.offset += page.items.len() as u32;
+        records.extend(page.items);
+    }
+    records.retain(|r| Path::new(&r.image_path).exists());
+    Ok(records)
+}
+
+/// 输出尺寸：宽度取设置值（不超过原图），高度按第一张截图的比例；H.264 要求宽高为偶数
+fn frame_size(records: &[ScreenshotRecord], max_width: u32) -> Result<(u32, u32), String> {
+    let (src_w, src_h) = records
+        .iter()
+        .find_map(|r| image::image_dimensions(&r.image_path).ok())
+        .ok_or("读取截图尺寸失败")?;
+    let width = max_width.clamp(2, src_w.max(2));
+    let height = ((width as u64 * src_h as u64 / src_w.max(1) as u64) as u32).max(2);
+    Ok((width & !1, height & !1))
+}
+
+/// 生成一帧：截图等比缩放后居中放在黑底上，按需叠加时间和应用名
+fn render_frame(record: &ScreenshotRecord, width: u32, height: u32, font: Option<&FontVec>) -> RgbaImage {
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    match image::open(&record.image_path) {
+        Ok(img) => {
+            let img = img.resize(width, height, FilterType::Triangle).to_rgba8();
+            let x = (width - img.width()) / 2;
+            let y = (height - img.height()) / 2;
+            imageops::replace(&mut canvas, &img, x as i64, y as i64);
+        }
+        Err(e) => eprintln!("读取截图失败 {}: {}", record.image_path, e),
+    }
+
+    if let Some(font) = font {
+        let time = record.timestamp.get(11..19).unwrap_or(&record.timestamp);
+        let label = format!("{}  {}", time, record.app_name);
+        draw_label(&mut canvas, font, &label, (height as f32 / 28.0).max(14.0));
+    }
+    canvas
+}
+
+/// 在左上角绘制带半透明底色的文字
+fn draw_label(canvas: &mut RgbaImage, font: &FontVec, text: &str, size: f32) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let padding = size * 0.4;
+    let text_width: f32 = text.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum();
+
+    // 底色
+    let box_w = ((text_width + padding * 2.0) as u32).min(canvas.width());
+    let box_h = ((scaled.height() + padding * 2.0) as u32).min(canvas.height());
+    for y in 0..box_h {
+        for x in 0..box_w {
+            for channel in canvas.get_pixel_mut(x, y).0[..3].iter_mut() {
+                *channel = (*channel as u32 * 2 / 5) as u8;
+            }
+        }
+    }
+
+    let mut caret = padding;
+    let baseline = padding + scaled.ascent();
+    for c in text.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(glyph_id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let x = bounds.min.x as i32 + gx as i32;
+            let y = bounds.min.y as i32 + gy as i32;
+            if x < 0 || y < 0 || x as u32 >= canvas.width() || y as u32 >= canvas.height() {
+                return;
+            }
+            for channel in canvas.get_pixel_mut(x as u32, y as u32).0[..3].iter_mut() {
+                *channel = (*channel as f32 * (1.0 - coverage) + 255.0 * coverage) as u8;
+            }
+        });
+    }
+}
+
+/// 加载系统中可显示中文的字体
+fn load_overlay_font() -> Option<FontVec> {
+    const CANDIDATES: &[&str] = &[
+        "C:\\Windows\\Fonts\\msyh.ttc",
+        "C:\\Windows\\Fonts\\simhei.ttf",
+        "C:\\Windows\\Fonts\\arial.ttf",
+        "/System/Library/Fonts/PingFang.ttc",
+        "/System/Library/Fonts/Helvetica.ttc",
+        "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+        "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    ];
+    CANDIDATES.iter().find_map(|path| {
+        let data = fs::read(path).ok()?;
+        FontVec::try_from_vec_and_index(data, 0).ok()
+    })
+}
+
+/// 查找 ffmpeg：优先程序目录，其次 PATH
+fn find_ffmpeg() -> Option<PathBuf> {
+    let name = if cfg!(windows) { "ffmpeg.exe" } else { "ffmpeg" };
+    let bundled = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(name)))
+        .filter(|path| path.exists());
+    let candidate = bundled.unwrap_or_else(|| PathBuf::from(name));
+
+    let mut cmd = Command::new(&candidate);
+    cmd.arg("-version").stdout(Stdio::null()).stderr(Stdio::null());
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd.status().ok().filter(|s| s.success()).map(|_| candidate)
+}
+
+/// 调用 ffmpeg 将帧序列编码为视频，通过 -progress 输出回报已编码帧数
+fn run_ffmpeg(
+    ffmpeg: &Path,
+    frames_dir: &Path,
+    output: &Path,
+    format: TimelapseFormat,
+    fps: u32,
+    mut on_frame: impl FnMut(usize),
+) -> Result<(), String> {
+    let mut cmd = Command::new(ffmpeg);
+    cmd.args(["-y", "-loglevel", "error", "-progress", "pipe:1", "-framerate"])
+        .arg(fps.to_string())
+        .arg("-i")
+        .arg(frames_dir.join("frame_%05d.png"));
+    match format {
+        TimelapseFormat::Mp4 => cmd.args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-movflags", "+faststart"]),
+        _ => cmd.args(["-c:v", "libwebp", "-loop", "0", "-quality", "75"]),
+    };
+    cmd.arg(output).stdout(Stdio::piped()).stderr(Stdio::piped());
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = cmd.spawn().map_err(|e| format!("启动ffmpeg失败: {}", e))?;
+    // stderr 单独读取，避免输出较多时写满管道阻塞 ffmpeg
+    let stderr = child.stderr.take().map(|mut stderr| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stderr.read_to_string(&mut text);
+            text
+        })
+    });
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(frame) = line.strip_prefix("frame=").and_then(|v| v.trim().parse().ok()) {
+                on_frame(frame);
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| format!("等待ffmpeg失败: {}", e))?;
+    let stderr = stderr.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    if !status.success() {
+        return Err(format!("ffmpeg编码失败: {}", stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OcrStatus;
+
+    fn record(time: &str) -> ScreenshotRecord {
+        ScreenshotRecord {
+            timestamp: format!("2024-01-01T{}", time),
+            end_timestamp: format!("2024-01-01T{}", time),
+            ocr_status: OcrStatus::Done,
+            ..Default::default()
+        }
+    }
+
+    /// 在临时目录写一张指定尺寸的白色截图
+    fn screenshot(name: &str, width: u32, height: u32) -> ScreenshotRecord {
+        let dir = std::env::temp_dir().join(format!("timelapse_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.png", name));
+        RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255])).save(&path).unwrap();
+        ScreenshotRecord { image_path: path.to_string_lossy().to_string(), ..record("09:00:00") }
+    }
+
+    #[test]
+    fn frame_size_is_even_and_never_upscales() {
+        let odd = [screenshot("odd", 1001, 601)];
+        assert_eq!(frame_size(&odd, 640).unwrap(), (640, 384));
+        // 设置宽度大于原图时按原图宽度，并去掉奇数
+        assert_eq!(frame_size(&odd, 4000).unwrap(), (1000, 600));
+
+        // 读不到的截图跳过，按第一张可读的计算比例
+        let mixed = [record("08:00:00"), screenshot("wide", 400, 100)];
+        assert_eq!(frame_size(&mixed, 200).unwrap(), (200, 50));
+        assert!(frame_size(&[record("08:00:00")], 200).is_err());
+    }
+
+    #[test]
+    fn render_frame_letterboxes_on_black() {
+        let square = screenshot("square", 100, 100);
+        let frame = render_frame(&square, 200, 100, None);
+        assert_eq!(frame.dimensions(), (200, 100));
+        assert_eq!(frame.get_pixel(10, 50).0, [0, 0, 0, 255]);
+        assert_eq!(frame.get_pixel(100, 50).0, [255, 255, 255, 255]);
+        assert_eq!(frame.get_pixel(190, 50).0, [0, 0, 0, 255]);
+
+        let tall = render_frame(&square, 100, 200, None);
+        assert_eq!(tall.get_pixel(50, 10).0, [0, 0, 0, 255]);
+        assert_eq!(tall.get_pixel(50, 100).0, [255, 255, 255, 255]);
+
+        // 截图读取失败时为整帧黑色
+        let missing = render_frame(&record("08:00:00"), 20, 10, None);
+        assert!(missing.pixels().all(|p| p.0 == [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn output_name_includes_range_app_and_format() {
+        let records = [record("09:05:00"), record("12:00:00"), record("17:45:30")];
+        let gif = TimelapseOptions::default();
+        assert_eq!(output_name("2024-01-01", &records, &gif), "timelapse_2024-01-01_0905-1745.gif");
+
+        let mp4 = TimelapseOptions {
+            format: TimelapseFormat::Mp4,
+            app_name: Some("Visual Studio Code".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            output_name("2024-01-01", &records, &mp4),
+            "timelapse_2024-01-01_0905-1745_Visual_Studio_Code.mp4"
+        );
+    }
+}
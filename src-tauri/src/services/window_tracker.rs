@@ -1,4 +1,10 @@
 use crate::error::{AppError, Result};
+use crate::models::ProcessContext;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
 
 /// 窗口信息
 #[derive(Debug, Clone)]
@@ -7,10 +13,20 @@ pub struct WindowInfo {
     pub window_title: String,
     pub process_id: u32,
     pub exe_path: String,
+    /// 进程命令行与工作目录（目前仅Linux从/proc读取）
+    pub process: Option<ProcessContext>,
+    /// 前台子进程（终端中正在运行的命令）
+    pub foreground_process: Option<ProcessContext>,
+    /// 窗口所在的工作区/虚拟桌面序号（X11 `_NET_WM_DESKTOP`）
+    pub workspace: Option<u32>,
+    /// 窗口所在的显示器名称
+    pub monitor: Option<String>,
+    /// 是否全屏（演示、全屏视频或游戏）
+    pub fullscreen: bool,
 }
 
 /// 窗口矩形区域
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WindowRect {
     pub x: i32,
     pub y: i32,
@@ -18,6 +34,105 @@ pub struct WindowRect {
     pub height: u32,
 }
 
+impl WindowRect {
+    /// 矩形中心点
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width as i32 / 2, self.y + self.height as i32 / 2)
+    }
+
+    /// 两个矩形是否有重叠部分
+    pub fn intersects(&self, other: &WindowRect) -> bool {
+        self.x < other.x + other.width as i32
+            && other.x < self.x + self.width as i32
+            && self.y < other.y + other.height as i32
+            && other.y < self.y + self.height as i32
+    }
+}
+
+/// 显示器区域
+#[derive(Debug, Clone)]
+pub struct MonitorBounds {
+    pub name: String,
+    pub primary: bool,
+    pub rect: WindowRect,
+}
+
+impl MonitorBounds {
+    /// 判断点是否落在该显示器内
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.rect.x
+            && y >= self.rect.y
+            && x < self.rect.x + self.rect.width as i32
+            && y < self.rect.y + self.rect.height as i32
+    }
+}
+
+/// 焦点变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusChangeKind {
+    /// 前台窗口切换
+    Focus,
+    /// 前台窗口标题变化
+    Title,
+    /// 前台窗口进入或退出全屏
+    Fullscreen,
+}
+
+/// 焦点变化事件（带发生时刻）
+#[derive(Debug, Clone)]
+pub struct FocusChange {
+    pub kind: FocusChangeKind,
+    pub window: WindowInfo,
+    pub timestamp: DateTime<Local>,
+}
+
+impl FocusChange {
+    /// 轮询得到的变化没有事件时间，取当前时刻
+    fn new(kind: FocusChangeKind, window: WindowInfo) -> Self {
+        Self::at(kind, window, Local::now())
+    }
+
+    fn at(kind: FocusChangeKind, window: WindowInfo, timestamp: DateTime<Local>) -> Self {
+        Self {
+            kind,
+            window,
+            timestamp,
+        }
+    }
+}
+
+/// 事件送达超过这个时间视为本地时钟被调整，重新校准
+#[cfg(any(target_os = "linux", test))]
+const EVENT_CLOCK_MAX_DELAY_MS: u32 = 10_000;
+
+/// 把 X 服务器时间（32 位毫秒计数，会回绕）换算为本地时间
+///
+/// 服务器时间的起点未知，取观察到的最小“本地毫秒 - 事件毫秒”作为两者的差：
+/// 事件只会晚到、不会早到，所以最小差最接近真实偏移。
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Default)]
+struct EventClock {
+    offset: Option<u32>,
+}
+
+#[cfg(any(target_os = "linux", test))]
+impl EventClock {
+    fn local_time(&mut self, event_ms: u32, now: DateTime<Local>) -> DateTime<Local> {
+        let diff = (now.timestamp_millis() as u32).wrapping_sub(event_ms);
+        let delay = match self.offset {
+            Some(offset) => diff.wrapping_sub(offset) as i32,
+            None => -1,
+        };
+        // delay < 0：比之前的估计更早送达，说明偏移更小；过大则是本地时钟被调整
+        if delay < 0 || delay as u32 > EVENT_CLOCK_MAX_DELAY_MS {
+            self.offset = Some(diff);
+            return now;
+        }
+        now - chrono::Duration::milliseconds(delay as i64)
+    }
+}
+
 /// 窗口追踪服务
 pub struct WindowTracker;
 
@@ -29,24 +144,286 @@ impl WindowTracker {
     /// 获取当前活动窗口信息
     #[cfg(target_os = "windows")]
     pub fn get_active_window(&self) -> Result<WindowInfo> {
-        use std::ffi::OsString;
-        use std::os::windows::ffi::OsStringExt;
         use windows::Win32::Foundation::HWND;
-        use windows::Win32::UI::WindowsAndMessaging::{
-            GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
-        };
-        use windows::Win32::System::Threading::{
-            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
-            PROCESS_QUERY_LIMITED_INFORMATION,
-        };
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+        // 获取前台窗口句柄
+        let hwnd: HWND = unsafe { GetForegroundWindow() };
+        if hwnd.0 == std::ptr::null_mut() {
+            return Err(AppError::WindowTracker("No active window".to_string()));
+        }
+        win32::window_info(hwnd)
+    }
+
+    /// Linux (X11) 实现
+    #[cfg(target_os = "linux")]
+    pub fn get_active_window(&self) -> Result<WindowInfo> {
+        let x = x11::X11::connect()?;
+        let window = x.active_window()?;
+        x.window_info(window)
+    }
+
+    /// macOS 占位实现
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    pub fn get_active_window(&self) -> Result<WindowInfo> {
+        Err(AppError::WindowTracker(
+            "Window tracking not implemented for this platform".to_string(),
+        ))
+    }
+
+    /// 获取当前活动窗口的位置和大小
+    #[cfg(target_os = "windows")]
+    pub fn get_active_window_rect(&self) -> Result<WindowRect> {
+        use windows::Win32::Foundation::{HWND, RECT};
+        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+        use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
 
         unsafe {
-            // 获取前台窗口句柄
             let hwnd: HWND = GetForegroundWindow();
             if hwnd.0 == std::ptr::null_mut() {
                 return Err(AppError::WindowTracker("No active window".to_string()));
             }
 
+            let mut rect: RECT = std::mem::zeroed();
+            if GetWindowRect(hwnd, &mut rect).is_ok() {
+                let width = (rect.right - rect.left).max(0) as u32;
+                let height = (rect.bottom - rect.top).max(0) as u32;
+                Ok(WindowRect {
+                    x: rect.left,
+                    y: rect.top,
+                    width,
+                    height,
+                })
+            } else {
+                Err(AppError::WindowTracker("Failed to get window rect".to_string()))
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn get_active_window_rect(&self) -> Result<WindowRect> {
+        let x = x11::X11::connect()?;
+        let window = x.active_window()?;
+        x.window_rect(window)
+    }
+
+    /// 获取活动窗口的 X11 窗口ID（供截图使用）
+    #[cfg(target_os = "linux")]
+    pub fn get_active_window_id(&self) -> Result<u32> {
+        x11::X11::connect()?.active_window()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    pub fn get_active_window_rect(&self) -> Result<WindowRect> {
+        Err(AppError::WindowTracker(
+            "Window tracking not implemented for this platform".to_string(),
+        ))
+    }
+
+    /// 与指定区域相交的可见顶层窗口（不考虑遮挡）
+    #[cfg(target_os = "windows")]
+    pub fn windows_in_rect(&self, rect: &WindowRect) -> Vec<WindowInfo> {
+        win32::windows_in_rect(rect)
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn windows_in_rect(&self, rect: &WindowRect) -> Vec<WindowInfo> {
+        let Ok(x) = x11::X11::connect() else {
+            return Vec::new();
+        };
+        x.client_windows()
+            .into_iter()
+            .filter(|&window| x.is_viewable(window))
+            .filter(|&window| x.window_rect(window).is_ok_and(|r| r.intersects(rect)))
+            .filter_map(|window| x.window_info(window).ok())
+            .collect()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    pub fn windows_in_rect(&self, _rect: &WindowRect) -> Vec<WindowInfo> {
+        Vec::new()
+    }
+
+    /// 订阅焦点和标题变化
+    ///
+    /// 优先使用系统事件（X11 PropertyNotify / Windows WinEvent），
+    /// 不可用或中断时回退为按 `poll_interval` 轮询。接收端被丢弃后监听线程自动退出。
+    pub fn subscribe(&self, poll_interval: Duration) -> Receiver<FocusChange> {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            match watch_events(&tx) {
+                Ok(()) => return, // 接收端已关闭
+                Err(e) => eprintln!("焦点事件监听不可用，回退为轮询: {}", e),
+            }
+            poll_changes(&tx, poll_interval);
+        });
+
+        rx
+    }
+}
+
+impl Default for WindowTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 轮询模式：按固定间隔比较前台窗口
+fn poll_changes(tx: &Sender<FocusChange>, poll_interval: Duration) {
+    let tracker = WindowTracker::new();
+    let mut last: Option<WindowInfo> = None;
+
+    loop {
+        if let Ok(info) = tracker.get_active_window() {
+            let kind = match &last {
+                None => Some(FocusChangeKind::Focus),
+                Some(prev) if prev.process_id != info.process_id || prev.app_name != info.app_name => {
+                    Some(FocusChangeKind::Focus)
+                }
+                Some(prev) if prev.fullscreen != info.fullscreen => Some(FocusChangeKind::Fullscreen),
+                Some(prev) if prev.window_title != info.window_title => Some(FocusChangeKind::Title),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                last = Some(info.clone());
+                if tx.send(FocusChange::new(kind, info)).is_err() {
+                    return;
+                }
+            }
+        }
+        thread::sleep(poll_interval);
+    }
+}
+
+/// 事件模式：X11 监听根窗口 `_NET_ACTIVE_WINDOW` 与活动窗口 `_NET_WM_NAME` / `_NET_WM_STATE`
+#[cfg(target_os = "linux")]
+fn watch_events(tx: &Sender<FocusChange>) -> Result<()> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::Event;
+
+    let x = x11::X11::connect()?;
+    x.watch_properties(x.root)?;
+    let mut clock = EventClock::default();
+
+    let mut active = x.active_window().unwrap_or(0);
+    let mut fullscreen = false;
+    if active != 0 {
+        x.watch_properties(active)?;
+        if let Ok(info) = x.window_info(active) {
+            fullscreen = info.fullscreen;
+            if tx.send(FocusChange::new(FocusChangeKind::Focus, info)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    loop {
+        let event = x
+            .conn
+            .wait_for_event()
+            .map_err(|e| AppError::WindowTracker(e.to_string()))?;
+
+        let Event::PropertyNotify(notify) = event else {
+            continue;
+        };
+        let timestamp = clock.local_time(notify.time, Local::now());
+
+        let change = if notify.window == x.root && notify.atom == x.atoms.net_active_window {
+            let window = x.active_window().unwrap_or(0);
+            if window == active || window == 0 {
+                continue;
+            }
+            // 只关注当前活动窗口的标题变化
+            x.unwatch_properties(active);
+            x.watch_properties(window)?;
+            active = window;
+            FocusChangeKind::Focus
+        } else if notify.window == active
+            && (notify.atom == x.atoms.net_wm_name || notify.atom == x.atoms.wm_name)
+        {
+            FocusChangeKind::Title
+        } else if notify.window == active && notify.atom == x.atoms.net_wm_state {
+            FocusChangeKind::Fullscreen
+        } else {
+            continue;
+        };
+
+        let Ok(info) = x.window_info(active) else {
+            continue;
+        };
+        // _NET_WM_STATE 变化不一定涉及全屏，只在全屏状态翻转时推送
+        if change == FocusChangeKind::Fullscreen && info.fullscreen == fullscreen {
+            continue;
+        }
+        fullscreen = info.fullscreen;
+
+        if tx.send(FocusChange::at(change, info, timestamp)).is_err() {
+            x.unwatch_properties(active);
+            let _ = x.conn.flush();
+            return Ok(());
+        }
+    }
+}
+
+/// 事件模式：Windows 前台切换与标题变化 WinEvent 钩子
+#[cfg(target_os = "windows")]
+fn watch_events(tx: &Sender<FocusChange>) -> Result<()> {
+    win32::watch_events(tx)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn watch_events(_tx: &Sender<FocusChange>) -> Result<()> {
+    Err(AppError::WindowTracker(
+        "Focus events not implemented for this platform".to_string(),
+    ))
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) use win32::monitor_device_name;
+
+#[cfg(target_os = "linux")]
+pub(crate) use x11::randr_monitors;
+
+#[cfg(target_os = "windows")]
+mod win32 {
+    use super::{FocusChange, FocusChangeKind, WindowInfo, WindowRect};
+    use crate::error::{AppError, Result};
+    use chrono::Local;
+    use std::cell::RefCell;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::sync::mpsc::Sender;
+    use std::cell::Cell;
+    use windows::Win32::Foundation::{BOOL, HMODULE, HWND, LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        GetMonitorInfoW, MonitorFromWindow, HMONITOR, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST,
+    };
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, EnumWindows, GetDesktopWindow, GetForegroundWindow, GetMessageW, GetShellWindow,
+        IsIconic, IsWindowVisible,
+        GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, PostQuitMessage,
+        TranslateMessage, EVENT_OBJECT_LOCATIONCHANGE, EVENT_OBJECT_NAMECHANGE,
+        EVENT_SYSTEM_FOREGROUND, MSG, OBJID_WINDOW, WINEVENT_OUTOFCONTEXT,
+    };
+
+    thread_local! {
+        /// WinEvent 回调没有用户数据参数，通过线程局部变量传递发送端
+        static SENDER: RefCell<Option<Sender<FocusChange>>> = const { RefCell::new(None) };
+        /// 上次推送时前台窗口的全屏状态
+        static LAST_FULLSCREEN: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// 读取指定窗口的标题与进程信息
+    pub fn window_info(hwnd: HWND) -> Result<WindowInfo> {
+        unsafe {
             // 获取窗口标题
             let mut title_buf: [u16; 512] = [0; 512];
             let title_len = GetWindowTextW(hwnd, &mut title_buf);
@@ -58,6 +435,8 @@ impl WindowTracker {
                 String::new()
             };
 
+            let monitor = window_monitor(hwnd);
+
             // 获取进程ID
             let mut process_id: u32 = 0;
             GetWindowThreadProcessId(hwnd, Some(&mut process_id));
@@ -97,57 +476,515 @@ impl WindowTracker {
                 window_title,
                 process_id,
                 exe_path,
+                process: crate::services::get_process_context(process_id),
+                foreground_process: crate::services::get_foreground_child(process_id),
+                workspace: None,
+                monitor: monitor.as_ref().map(monitor_name),
+                fullscreen: monitor.as_ref().is_some_and(|m| is_fullscreen(hwnd, m)),
             })
         }
     }
 
-    /// macOS/Linux 占位实现
-    #[cfg(not(target_os = "windows"))]
-    pub fn get_active_window(&self) -> Result<WindowInfo> {
-        Err(AppError::WindowTracker(
-            "Window tracking not implemented for this platform".to_string(),
-        ))
+    unsafe extern "system" fn collect_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = unsafe { &mut *(lparam.0 as *mut Vec<HWND>) };
+        windows.push(hwnd);
+        BOOL(1)
     }
 
-    /// 获取当前活动窗口的位置和大小
-    #[cfg(target_os = "windows")]
-    pub fn get_active_window_rect(&self) -> Result<WindowRect> {
-        use windows::Win32::Foundation::{HWND, RECT};
-        use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
-        use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+    /// 与指定区域相交的可见顶层窗口
+    pub fn windows_in_rect(rect: &WindowRect) -> Vec<WindowInfo> {
+        let mut handles: Vec<HWND> = Vec::new();
+        unsafe {
+            let _ = EnumWindows(Some(collect_window), LPARAM(&mut handles as *mut Vec<HWND> as isize));
+        }
+        handles
+            .into_iter()
+            .filter(|&hwnd| unsafe { IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool() })
+            .filter(|&hwnd| {
+                let mut bounds = RECT::default();
+                unsafe { GetWindowRect(hwnd, &mut bounds) }.is_ok()
+                    && WindowRect {
+                        x: bounds.left,
+                        y: bounds.top,
+                        width: (bounds.right - bounds.left).max(0) as u32,
+                        height: (bounds.bottom - bounds.top).max(0) as u32,
+                    }
+                    .intersects(rect)
+            })
+            .filter_map(|hwnd| window_info(hwnd).ok())
+            .collect()
+    }
+
+    /// 读取窗口所在显示器的信息
+    fn window_monitor(hwnd: HWND) -> Option<MONITORINFOEXW> {
+        monitor_info(unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) })
+    }
+
+    /// 显示器句柄对应的设备名（截图按此标记显示器，与 [`WindowInfo::monitor`] 一致）
+    pub fn monitor_device_name(handle: isize) -> Option<String> {
+        monitor_info(HMONITOR(handle as _)).map(|info| monitor_name(&info))
+    }
 
+    fn monitor_info(hmonitor: HMONITOR) -> Option<MONITORINFOEXW> {
+        if hmonitor.is_invalid() {
+            return None;
+        }
         unsafe {
-            let hwnd: HWND = GetForegroundWindow();
-            if hwnd.0 == std::ptr::null_mut() {
-                return Err(AppError::WindowTracker("No active window".to_string()));
+            let mut info = MONITORINFOEXW::default();
+            info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+            if !GetMonitorInfoW(hmonitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+                return None;
             }
+            Some(info)
+        }
+    }
 
-            let mut rect: RECT = std::mem::zeroed();
-            if GetWindowRect(hwnd, &mut rect).is_ok() {
-                let width = (rect.right - rect.left).max(0) as u32;
-                let height = (rect.bottom - rect.top).max(0) as u32;
-                Ok(WindowRect {
-                    x: rect.left,
-                    y: rect.top,
-                    width,
-                    height,
-                })
-            } else {
-                Err(AppError::WindowTracker("Failed to get window rect".to_string()))
+    /// 显示器设备名（如 \\.\DISPLAY1）
+    fn monitor_name(info: &MONITORINFOEXW) -> String {
+        let len = info.szDevice.iter().position(|c| *c == 0).unwrap_or(info.szDevice.len());
+        OsString::from_wide(&info.szDevice[..len]).to_string_lossy().to_string()
+    }
+
+    /// 窗口矩形与显示器边界完全重合即视为全屏（排除桌面和Shell窗口）
+    fn is_fullscreen(hwnd: HWND, monitor: &MONITORINFOEXW) -> bool {
+        unsafe {
+            if hwnd == GetDesktopWindow() || hwnd == GetShellWindow() {
+                return false;
+            }
+            let mut rect = RECT::default();
+            if GetWindowRect(hwnd, &mut rect).is_err() {
+                return false;
             }
+            rect == monitor.monitorInfo.rcMonitor
         }
     }
 
-    #[cfg(not(target_os = "windows"))]
-    pub fn get_active_window_rect(&self) -> Result<WindowRect> {
-        Err(AppError::WindowTracker(
-            "Window tracking not implemented for this platform".to_string(),
-        ))
+    unsafe extern "system" fn win_event_proc(
+        _hook: HWINEVENTHOOK,
+        event: u32,
+        hwnd: HWND,
+        id_object: i32,
+        _id_child: i32,
+        _event_thread: u32,
+        event_time: u32,
+    ) {
+        let kind = match event {
+            EVENT_SYSTEM_FOREGROUND => FocusChangeKind::Focus,
+            // 只关心前台窗口自身的标题变化
+            EVENT_OBJECT_NAMECHANGE
+                if id_object == OBJID_WINDOW.0 && hwnd == unsafe { GetForegroundWindow() } =>
+            {
+                FocusChangeKind::Title
+            }
+            // 前台窗口位置/大小变化时检查全屏状态
+            EVENT_OBJECT_LOCATIONCHANGE
+                if id_object == OBJID_WINDOW.0 && hwnd == unsafe { GetForegroundWindow() } =>
+            {
+                FocusChangeKind::Fullscreen
+            }
+            _ => return,
+        };
+
+        let Ok(info) = window_info(hwnd) else {
+            return;
+        };
+        if kind == FocusChangeKind::Fullscreen && info.fullscreen == LAST_FULLSCREEN.get() {
+            return;
+        }
+        LAST_FULLSCREEN.set(info.fullscreen);
+
+        // dwmsEventTime 与 GetTickCount 同源，按事件发生到现在的间隔倒推
+        let age = unsafe { GetTickCount() }.wrapping_sub(event_time);
+        let timestamp = Local::now() - chrono::Duration::milliseconds(age as i64);
+
+        SENDER.with(|sender| {
+            let closed = match sender.borrow().as_ref() {
+                Some(tx) => tx.send(FocusChange::at(kind, info, timestamp)).is_err(),
+                None => true,
+            };
+            if closed {
+                unsafe { PostQuitMessage(0) };
+            }
+        });
+    }
+
+    pub fn watch_events(tx: &Sender<FocusChange>) -> Result<()> {
+        SENDER.with(|sender| *sender.borrow_mut() = Some(tx.clone()));
+
+        unsafe {
+            let foreground = SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            let name_change = SetWinEventHook(
+                EVENT_OBJECT_NAMECHANGE,
+                EVENT_OBJECT_NAMECHANGE,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            let location_change = SetWinEventHook(
+                EVENT_OBJECT_LOCATIONCHANGE,
+                EVENT_OBJECT_LOCATIONCHANGE,
+                HMODULE::default(),
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            if foreground.is_invalid() || name_change.is_invalid() || location_change.is_invalid() {
+                return Err(AppError::WindowTracker("SetWinEventHook failed".to_string()));
+            }
+
+            // 推送初始前台窗口
+            let hwnd = GetForegroundWindow();
+            if !hwnd.is_invalid()
+                && let Ok(info) = window_info(hwnd)
+            {
+                LAST_FULLSCREEN.set(info.fullscreen);
+                let _ = tx.send(FocusChange::new(FocusChangeKind::Focus, info));
+            }
+
+            // 钩子回调依赖本线程的消息循环
+            let mut msg = MSG::default();
+            while GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            let _ = UnhookWinEvent(foreground);
+            let _ = UnhookWinEvent(name_change);
+            let _ = UnhookWinEvent(location_change);
+        }
+
+        SENDER.with(|sender| *sender.borrow_mut() = None);
+        Ok(())
     }
 }
 
-impl Default for WindowTracker {
-    fn default() -> Self {
-        Self::new()
+#[cfg(target_os = "linux")]
+mod x11 {
+    use super::{MonitorBounds, WindowInfo, WindowRect};
+    use crate::error::{AppError, Result};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, MapState, Window,
+    };
+    use x11rb::rust_connection::RustConnection;
+
+    fn err(e: impl std::fmt::Display) -> AppError {
+        AppError::WindowTracker(e.to_string())
+    }
+
+    /// 通过 RandR 获取所有已启用的显示器，扩展不可用时为空（截图也使用此结果）
+    pub fn randr_monitors(conn: &RustConnection, root: Window) -> Vec<MonitorBounds> {
+        let Ok(reply) = conn
+            .randr_get_monitors(root, true)
+            .map_err(err)
+            .and_then(|cookie| cookie.reply().map_err(err))
+        else {
+            return Vec::new();
+        };
+
+        reply
+            .monitors
+            .iter()
+            .map(|m| MonitorBounds {
+                name: conn
+                    .get_atom_name(m.name)
+                    .ok()
+                    .and_then(|cookie| cookie.reply().ok())
+                    .map(|r| String::from_utf8_lossy(&r.name).to_string())
+                    .unwrap_or_default(),
+                primary: m.primary,
+                rect: WindowRect {
+                    x: m.x as i32,
+                    y: m.y as i32,
+                    width: m.width as u32,
+                    height: m.height as u32,
+                },
+            })
+            .collect()
+    }
+
+    pub struct Atoms {
+        pub net_active_window: Atom,
+        pub net_client_list: Atom,
+        pub net_wm_name: Atom,
+        pub wm_name: Atom,
+        pub net_wm_pid: Atom,
+        pub net_wm_desktop: Atom,
+        pub net_current_desktop: Atom,
+        pub net_wm_state: Atom,
+        pub net_wm_state_fullscreen: Atom,
+        pub utf8_string: Atom,
+    }
+
+    /// X11 连接及常用 atom
+    pub struct X11 {
+        pub conn: RustConnection,
+        pub root: Window,
+        pub atoms: Atoms,
+    }
+
+    impl X11 {
+        pub fn connect() -> Result<Self> {
+            let (conn, screen_num) = x11rb::connect(None).map_err(err)?;
+            let root = conn.setup().roots[screen_num].root;
+
+            let intern = |name: &str| -> Result<Atom> {
+                Ok(conn
+                    .intern_atom(false, name.as_bytes())
+                    .map_err(err)?
+                    .reply()
+                    .map_err(err)?
+                    .atom)
+            };
+            let atoms = Atoms {
+                net_active_window: intern("_NET_ACTIVE_WINDOW")?,
+                net_client_list: intern("_NET_CLIENT_LIST")?,
+                net_wm_name: intern("_NET_WM_NAME")?,
+                wm_name: AtomEnum::WM_NAME.into(),
+                net_wm_pid: intern("_NET_WM_PID")?,
+                net_wm_desktop: intern("_NET_WM_DESKTOP")?,
+                net_current_desktop: intern("_NET_CURRENT_DESKTOP")?,
+                net_wm_state: intern("_NET_WM_STATE")?,
+                net_wm_state_fullscreen: intern("_NET_WM_STATE_FULLSCREEN")?,
+                utf8_string: intern("UTF8_STRING")?,
+            };
+
+            Ok(Self { conn, root, atoms })
+        }
+
+        /// 读取窗口的 32 位属性值
+        pub fn property_u32(&self, window: Window, property: Atom, type_: impl Into<Atom>) -> Option<u32> {
+            let reply = self
+                .conn
+                .get_property(false, window, property, type_.into(), 0, 1)
+                .ok()?
+                .reply()
+                .ok()?;
+            reply.value32()?.next()
+        }
+
+        /// 读取窗口的字符串属性
+        fn property_string(&self, window: Window, property: Atom, type_: Atom) -> Option<String> {
+            let reply = self
+                .conn
+                .get_property(false, window, property, type_, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            if reply.value.is_empty() {
+                return None;
+            }
+            Some(String::from_utf8_lossy(&reply.value).to_string())
+        }
+
+        pub fn active_window(&self) -> Result<Window> {
+            match self.property_u32(self.root, self.atoms.net_active_window, AtomEnum::WINDOW) {
+                Some(window) if window != 0 => Ok(window),
+                _ => Err(AppError::WindowTracker("No active window".to_string())),
+            }
+        }
+
+        /// 窗口管理器管理的所有顶层窗口
+        pub fn client_windows(&self) -> Vec<Window> {
+            self.conn
+                .get_property(false, self.root, self.atoms.net_client_list, AtomEnum::WINDOW, 0, 4096)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .and_then(|reply| reply.value32().map(|windows| windows.collect()))
+                .unwrap_or_default()
+        }
+
+        /// 窗口当前是否映射显示（最小化、其他工作区的窗口不可见）
+        pub fn is_viewable(&self, window: Window) -> bool {
+            self.conn
+                .get_window_attributes(window)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .is_some_and(|attrs| attrs.map_state == MapState::VIEWABLE)
+        }
+
+        pub fn window_title(&self, window: Window) -> String {
+            self.property_string(window, self.atoms.net_wm_name, self.atoms.utf8_string)
+                .or_else(|| self.property_string(window, self.atoms.wm_name, AtomEnum::STRING.into()))
+                .unwrap_or_default()
+        }
+
+        pub fn window_info(&self, window: Window) -> Result<WindowInfo> {
+            let window_title = self.window_title(window);
+            let process_id = self
+                .property_u32(window, self.atoms.net_wm_pid, AtomEnum::CARDINAL)
+                .unwrap_or(0);
+
+            // 从 /proc 读取进程名称和路径
+            let exe_path = if process_id > 0 {
+                std::fs::read_link(format!("/proc/{}/exe", process_id))
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let app_name = if process_id > 0 {
+                std::fs::read_to_string(format!("/proc/{}/comm", process_id))
+                    .map(|s| s.trim().to_string())
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| "Unknown".to_string())
+            } else {
+                "Unknown".to_string()
+            };
+
+            Ok(WindowInfo {
+                app_name,
+                window_title,
+                process_id,
+                exe_path,
+                process: crate::services::get_process_context(process_id),
+                foreground_process: crate::services::get_foreground_child(process_id),
+                workspace: self.window_workspace(window),
+                monitor: self.window_monitor(window),
+                fullscreen: self.is_fullscreen(window),
+            })
+        }
+
+        /// `_NET_WM_STATE_FULLSCREEN` 或窗口矩形与某个显示器完全重合
+        pub fn is_fullscreen(&self, window: Window) -> bool {
+            let has_state = self
+                .conn
+                .get_property(false, window, self.atoms.net_wm_state, AtomEnum::ATOM, 0, 64)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .and_then(|reply| {
+                    reply
+                        .value32()
+                        .map(|mut atoms| atoms.any(|a| a == self.atoms.net_wm_state_fullscreen))
+                })
+                .unwrap_or(false);
+            if has_state {
+                return true;
+            }
+
+            match self.window_rect(window) {
+                Ok(rect) => self.monitors().iter().any(|m| m.rect == rect),
+                Err(_) => false,
+            }
+        }
+
+        /// 窗口所在工作区；粘滞窗口（0xFFFFFFFF）取当前工作区
+        pub fn window_workspace(&self, window: Window) -> Option<u32> {
+            match self.property_u32(window, self.atoms.net_wm_desktop, AtomEnum::CARDINAL) {
+                Some(desktop) if desktop != u32::MAX => Some(desktop),
+                _ => self.property_u32(self.root, self.atoms.net_current_desktop, AtomEnum::CARDINAL),
+            }
+        }
+
+        /// 通过 RandR 获取所有显示器
+        pub fn monitors(&self) -> Vec<MonitorBounds> {
+            randr_monitors(&self.conn, self.root)
+        }
+
+        /// 窗口中心点所在的显示器名称
+        pub fn window_monitor(&self, window: Window) -> Option<String> {
+            let center = self.window_rect(window).ok()?.center();
+            self.monitors()
+                .into_iter()
+                .find(|m| m.contains(center))
+                .map(|m| m.name)
+        }
+
+        pub fn window_rect(&self, window: Window) -> Result<WindowRect> {
+            let geometry = self
+                .conn
+                .get_geometry(window)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+            // 转换为相对根窗口的坐标
+            let translated = self
+                .conn
+                .translate_coordinates(window, self.root, 0, 0)
+                .map_err(err)?
+                .reply()
+                .map_err(err)?;
+
+            Ok(WindowRect {
+                x: translated.dst_x as i32,
+                y: translated.dst_y as i32,
+                width: geometry.width as u32,
+                height: geometry.height as u32,
+            })
+        }
+
+        /// 订阅窗口属性变化
+        pub fn watch_properties(&self, window: Window) -> Result<()> {
+            let aux = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+            self.conn.change_window_attributes(window, &aux).map_err(err)?;
+            self.conn.flush().map_err(err)
+        }
+
+        /// 取消订阅窗口属性变化（窗口可能已销毁，忽略错误）
+        pub fn unwatch_properties(&self, window: Window) {
+            if window == 0 || window == self.root {
+                return;
+            }
+            let aux = ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT);
+            let _ = self.conn.change_window_attributes(window, &aux);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at_ms(ms: i64) -> DateTime<Local> {
+        Local.timestamp_millis_opt(ms).unwrap()
+    }
+
+    #[test]
+    fn rect_intersection() {
+        let rect = WindowRect { x: 0, y: 0, width: 100, height: 100 };
+        assert!(rect.intersects(&WindowRect { x: 50, y: 50, width: 100, height: 100 }));
+        assert!(rect.intersects(&WindowRect { x: -10, y: -10, width: 20, height: 20 }));
+        // 只有边相接不算重叠
+        assert!(!rect.intersects(&WindowRect { x: 100, y: 0, width: 50, height: 50 }));
+        assert!(!rect.intersects(&WindowRect { x: 0, y: 200, width: 50, height: 50 }));
+    }
+
+    #[test]
+    fn event_clock_backdates_by_delivery_delay() {
+        let mut clock = EventClock::default();
+        let base = 1_700_000_000_000;
+        // 第一个事件用来校准
+        assert_eq!(clock.local_time(5_000, at_ms(base)), at_ms(base));
+        // 事件发生在 1000ms 后，但 300ms 后才送达
+        assert_eq!(clock.local_time(6_000, at_ms(base + 1_300)), at_ms(base + 1_000));
+    }
+
+    #[test]
+    fn event_clock_recalibrates_on_earlier_delivery_and_wraparound() {
+        let mut clock = EventClock::default();
+        let base = 1_700_000_000_000;
+        clock.local_time(5_000, at_ms(base + 200));
+        // 比校准时送达得更及时：偏移更新为更小的值
+        assert_eq!(clock.local_time(6_000, at_ms(base + 1_000)), at_ms(base + 1_000));
+        assert_eq!(clock.local_time(7_000, at_ms(base + 2_050)), at_ms(base + 2_000));
+
+        // 服务器计数回绕
+        let mut clock = EventClock::default();
+        clock.local_time(u32::MAX - 499, at_ms(base));
+        assert_eq!(clock.local_time(500, at_ms(base + 1_100)), at_ms(base + 1_000));
     }
 }
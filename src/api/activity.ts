@@ -3,13 +3,15 @@ import { invoke } from '@tauri-apps/api/core';
 export interface EventForDisplay {
   id: string;
   timestamp: string;
-  event_type: 'app_focus' | 'keyboard' | 'mouse' | 'idle';
+  event_type: 'app_focus' | 'keyboard' | 'mouse' | 'idle' | 'screenshot_skipped';
   app?: string;
   window_title?: string;
   exe_path?: string;
   key_count?: number;
   mouse_distance?: number;
   click_count?: number;
+  cmdline?: string;
+  cwd?: string;
   time_display: string;
 }
 
@@ -20,12 +22,46 @@ export interface GroupedEvents {
   idle: EventForDisplay[];
 }
 
+export interface StateSegment {
+  id: string;
+  start_time: string;
+  end_time: string;
+  app: string;
+  window_title: string;
+  duration_min: number;
+  activity_level: 'high' | 'medium' | 'low' | 'idle';
+  input_density: { keyboard: number; mouse: number };
+  screenshot_path?: string;
+  workspace?: number;
+  monitor?: string;
+  fullscreen?: boolean;
+}
+
+export interface DailySummaryPack {
+  date: string;
+  segments: StateSegment[];
+  total_active_minutes: number;
+  total_idle_minutes: number;
+  app_usage: { app: string; total_minutes: number; percentage: number }[];
+  workspace_usage: { workspace: number; total_minutes: number; percentage: number }[];
+  monitor_usage: { monitor: string; total_minutes: number; percentage: number }[];
+}
+
 export interface ActiveWindowInfo {
   app_name: string;
   window_title: string;
   exe_path: string;
 }
 
+export interface FocusChange {
+  kind: 'focus' | 'title' | 'fullscreen';
+  app_name: string;
+  window_title: string;
+  exe_path: string;
+  fullscreen: boolean;
+  timestamp: string;
+}
+
 export interface InputStats {
   key_count: number;
   click_count: number;
@@ -33,22 +69,162 @@ export interface InputStats {
   idle_seconds: number;
 }
 
+// 敏感信息识别规则
+export interface PiiPattern {
+  name: string;
+  pattern: string;
+}
+
 export interface AppConfig {
   poll_interval_ms: number;
+  screenshot_blacklist: string[];
+  screenshot_blacklist_titles: string[];
+  screenshot_blacklist_exes: string[];
   idle_threshold_sec: number;
   screenshot_enabled: boolean;
   screenshot_trigger_sec: number;
   screenshot_interval_sec: number;
+  screenshot_on_app_switch: boolean;
+  screenshot_title_patterns: string[];
+  screenshot_dedup_distance: number;
+  screenshot_format: 'jpeg' | 'webp' | 'png';
+  screenshot_quality: number;
+  screenshot_max_width: number;
+  screenshot_max_height: number;
+  thumbnail_size: number;
+  screenshot_quota_mb: number;
   screenshot_mode: string;
+  screenshot_monitors: 'active' | 'all_stitched' | 'all_separate';
   screenshot_hotkey: string;
+  region_capture_hotkey: string;
+  pause_tracking_hotkey: string;
+  quick_note_hotkey: string;
+  pii_redaction_enabled: boolean;
+  pii_redaction_style: 'blur' | 'black';
+  pii_patterns: PiiPattern[];
+  fullscreen_pause_screenshot: boolean;
+  fullscreen_pause_ocr: boolean;
+  fullscreen_hide_pet: boolean;
+  ocr_engine: 'rapidocr' | 'tesseract' | 'mock';
+  ocr_python_path: string;
+  ocr_script_path: string;
+  ocr_tesseract_path: string;
+  ocr_timeout_sec: number;
+  ocr_languages: string;
+  ocr_det_model_path: string;
+  ocr_rec_model_path: string;
+  ocr_rec_keys_path: string;
+  ocr_threads: number;
 }
 
 export interface ScreenshotResponse {
   success: boolean;
   filepath: string | null;
+  filepaths: string[];
+  error: string | null;
+  skipped: string | null;
+  // 截图在暂存目录等待脱敏，识别完成前 filepath 不可读取
+  staged: boolean;
+}
+
+export type HotkeyAction = 'screenshot' | 'region_capture' | 'pause_tracking' | 'quick_note';
+
+// 全局快捷键注册结果（有失败时通过 hotkey-status 事件推送）
+export interface HotkeyStatus {
+  action: HotkeyAction;
+  accelerator: string;
+  registered: boolean;
   error: string | null;
 }
 
+// 后端自动截图结果（auto-screenshot 事件）
+export interface AutoScreenshot {
+  trigger: 'dwell' | 'interval' | 'app_switch' | 'title_match';
+  app_name: string;
+  filepath: string | null;
+  error: string | null;
+  skipped: string | null;
+  staged: boolean;
+}
+
+export type OcrStatus = 'pending' | 'done' | 'empty' | 'failed' | 'skipped' | 'redacting';
+
+// 截图目录记录
+export interface ScreenshotRecord {
+  id: number;
+  timestamp: string;
+  end_timestamp: string;
+  image_path: string;
+  app_name: string;
+  window_title: string | null;
+  exe_path: string | null;
+  monitor: string | null;
+  width: number;
+  height: number;
+  hash: string | null;
+  ocr_status: OcrStatus;
+  segment_id: string | null;
+  thumbnail_path: string | null;
+  pinned: boolean;
+  note: string | null;
+}
+
+export type TimelapseFormat = 'gif' | 'webp' | 'mp4';
+
+export interface TimelapseOptions {
+  format?: TimelapseFormat;
+  fps?: number;
+  width?: number;
+  overlay?: boolean;
+  app_name?: string | null;
+}
+
+// 延时视频导出进度（timelapse-progress 事件）
+export interface TimelapseProgress {
+  stage: 'render' | 'encode' | 'done';
+  current: number;
+  total: number;
+}
+
+export interface RecompressReport {
+  processed: number;
+  replaced: number;
+  failed: number;
+  bytes_before: number;
+  bytes_after: number;
+}
+
+// 截图占用空间
+export interface ScreenshotUsage {
+  used_bytes: number;
+  pinned_bytes: number;
+  quota_bytes: number;
+  files: number;
+  pinned_files: number;
+}
+
+export interface EvictionReport {
+  deleted: number;
+  freed_bytes: number;
+}
+
+export interface ScreenshotQuery {
+  app_name?: string | null;
+  title_contains?: string | null;
+  ocr_status?: OcrStatus | null;
+  pinned?: boolean | null;
+  start_time?: string | null;
+  end_time?: string | null;
+  order?: 'asc' | 'desc';
+  offset?: number;
+  limit?: number;
+}
+
+export interface ScreenshotPage {
+  total: number;
+  items: ScreenshotRecord[];
+}
+
 export interface DashboardStats {
   total_days: number;
   today_events: number;
@@ -56,11 +232,67 @@ export interface DashboardStats {
   today_diary: string | null;
 }
 
+// OCR队列状态（ocr-queue-status 事件）
+export interface OcrQueueStatus {
+  pending: number;
+  running: string | null;
+  completed: number;
+  failed: number;
+  cancelled: number;
+  retried: number;
+}
+
+export interface OcrBackfillProgress {
+  start_date: string;
+  end_date: string;
+  current_date: string | null;
+  date_total: number;
+  date_done: number;
+  processed: number;
+  succeeded: number;
+  empty: number;
+  failed: number;
+  running: boolean;
+  finished: boolean;
+  error: string | null;
+}
+
+export interface OcrLine {
+  text: string;
+  // 四边形顶点（图片像素坐标，左上起顺时针）
+  box: [number, number][];
+  // 置信度 0-1
+  score: number;
+}
+
 export interface OcrRecord {
+  id?: number;
   timestamp: string;
   image_path: string;
   text: string;
   app_name: string | null;
+  // 旧记录没有逐行结果
+  lines?: OcrLine[];
+  // 增量记录所基于的上一条记录
+  base_id?: number | null;
+}
+
+// 低于该置信度的OCR行视为噪声，不送给AI
+export const OCR_MIN_CONFIDENCE = 0.5;
+
+// 给AI的工作区/显示器使用分布，只有一个工作区或显示器时没有参考价值，省略
+export function usageBreakdown(summary: DailySummaryPack | null) {
+  const breakdown: {
+    workspace_usage?: DailySummaryPack['workspace_usage'];
+    monitor_usage?: DailySummaryPack['monitor_usage'];
+  } = {};
+  if (summary && summary.workspace_usage.length > 1) {
+    breakdown.workspace_usage = summary.workspace_usage;
+  }
+  if (summary && summary.monitor_usage.length > 1) {
+    breakdown.monitor_usage = summary.monitor_usage;
+  }
+  return breakdown;
 }
 
 export const activityApi = {
@@ -69,12 +301,29 @@ export const activityApi = {
   // OCR相关
   ocrImage: (imagePath: string) => invoke<string>('ocr_image', { imagePath }),
   
-  getOcrDataByDate: (date: string) => invoke<OcrRecord[]>('get_ocr_data_by_date', { date }),
+  // deltaOnly 为 true 时连续截图只返回新增的文本，适合拼进AI提示词
+  getOcrDataByDate: (date: string, minConfidence?: number, deltaOnly = false) =>
+    invoke<OcrRecord[]>('get_ocr_data_by_date', { date, minConfidence: minConfidence ?? null, deltaOnly }),
   
   saveOcrRecord: (date: string, record: OcrRecord) => invoke('save_ocr_record', { date, record }),
+
+  getOcrQueueStatus: () => invoke<OcrQueueStatus>('get_ocr_queue_status'),
+
+  cancelOcrQueue: () => invoke<number>('cancel_ocr_queue'),
+
+  startOcrBackfill: (startDate: string, endDate: string) =>
+    invoke<OcrBackfillProgress>('start_ocr_backfill', { startDate, endDate }),
+
+  resumeOcrBackfill: () => invoke<OcrBackfillProgress>('resume_ocr_backfill'),
+
+  stopOcrBackfill: () => invoke<boolean>('stop_ocr_backfill'),
+
+  getOcrBackfillProgress: () => invoke<OcrBackfillProgress | null>('get_ocr_backfill_progress'),
   
   getGroupedEventsByDate: (date: string) => invoke<GroupedEvents>('get_events_grouped_by_date', { date }),
   
+  getDailySummary: (date: string) => invoke<DailySummaryPack>('get_daily_summary', { date }),
+  
   getAllEvents: () => invoke<EventForDisplay[]>('get_today_events'),
   
   getActiveWindow: () => invoke<ActiveWindowInfo>('get_active_window'),
@@ -82,6 +331,10 @@ export const activityApi = {
   recordAppFocus: (app: string, windowTitle: string, exePath: string) => 
     invoke('record_app_focus', { app, windowTitle, exePath }),
   
+  startFocusWatch: () => invoke('start_focus_watch'),
+
+  getCurrentFocus: () => invoke<FocusChange | null>('get_current_focus'),
+  
   recordKeyboard: (keyCount: number, app: string, windowTitle: string, exePath: string) => 
     invoke('record_keyboard_event', { keyCount, app, windowTitle, exePath }),
   
@@ -108,6 +361,8 @@ export const activityApi = {
   // 输入监听相关
   startInputListening: () => invoke('start_input_listening'),
   
+  stopInputListening: () => invoke('stop_input_listening'),
+  
   getInputStats: () => invoke<InputStats>('get_input_stats'),
   
   getIdleSeconds: () => invoke<number>('get_idle_seconds'),
@@ -117,15 +372,7 @@ export const activityApi = {
   // 应用配置相关
   getAppConfig: () => invoke<AppConfig>('get_app_config'),
   
-  saveAppConfig: (config: AppConfig) => invoke('save_app_config', {
-    pollIntervalMs: config.poll_interval_ms,
-    idleThresholdSec: config.idle_threshold_sec,
-    screenshotEnabled: config.screenshot_enabled,
-    screenshotTriggerSec: config.screenshot_trigger_sec,
-    screenshotIntervalSec: config.screenshot_interval_sec,
-    screenshotMode: config.screenshot_mode,
-    screenshotHotkey: config.screenshot_hotkey,
-  }),
+  saveAppConfig: (config: AppConfig) => invoke('save_app_config', { config }),
   
   // 截图相关
   takeScreenshot: (appName: string) => invoke<ScreenshotResponse>('take_screenshot', { appName }),
@@ -136,4 +383,37 @@ export const activityApi = {
   getTodayScreenshots: () => invoke<string[]>('get_today_screenshots'),
   
   getScreenshotsByDate: (date: string) => invoke<string[]>('get_screenshots_by_date', { date }),
+
+  listScreenshots: (date: string, query?: ScreenshotQuery) =>
+    invoke<ScreenshotPage>('list_screenshots', { date, query }),
+
+  getScreenshotApps: (date: string) => invoke<string[]>('get_screenshot_apps', { date }),
+
+  recompressScreenshots: (date: string) =>
+    invoke<RecompressReport>('recompress_screenshots', { date }),
+
+  exportTimelapse: (date: string, options?: TimelapseOptions) =>
+    invoke<string>('export_timelapse', { date, options }),
+
+  pinScreenshot: (date: string, imagePath: string, pinned: boolean, note?: string | null) =>
+    invoke('pin_screenshot', { date, imagePath, pinned, note }),
+
+  getScreenshotUsage: () => invoke<ScreenshotUsage>('get_screenshot_usage'),
+
+  applyScreenshotQuota: () => invoke<EvictionReport>('apply_screenshot_quota'),
+
+  // 全局快捷键与暂停记录
+  getHotkeyStatus: () => invoke<HotkeyStatus[]>('get_hotkey_status'),
+
+  isTrackingPaused: () => invoke<boolean>('is_tracking_paused'),
+
+  setTrackingPaused: (paused: boolean) => invoke('set_tracking_paused', { paused }),
+
+  // 框选截图（坐标为屏幕物理像素）
+  startRegionCapture: () => invoke('start_region_capture'),
+
+  finishRegionCapture: (x: number, y: number, width: number, height: number) =>
+    invoke<ScreenshotResponse>('finish_region_capture', { x, y, width, height }),
+
+  cancelRegionCapture: () => invoke('cancel_region_capture'),
 };
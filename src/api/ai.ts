@@ -4,6 +4,37 @@ export interface AIConfig {
   api_key: string;
   model: string;
   base_url: string;
+  /** 接口格式：openai（兼容接口）/ anthropic / ollama */
+  api_format: AIApiFormat;
+  /** 对话时附带的历史消息上限（估算 token 数） */
+  history_token_budget: number;
+}
+
+export type AIApiFormat = 'openai' | 'anthropic' | 'ollama';
+
+export interface ChatMessage {
+  id: string;
+  role: 'user' | 'assistant';
+  content: string;
+  timestamp: string;
+}
+
+export interface ChatSession {
+  id: string;
+  title: string;
+  date: string;
+  created_at: string;
+  updated_at: string;
+  messages: ChatMessage[];
+}
+
+export interface ChatSessionSummary {
+  id: string;
+  title: string;
+  date: string;
+  created_at: string;
+  updated_at: string;
+  message_count: number;
 }
 
 export interface DiaryState {
@@ -56,5 +87,35 @@ export const aiApi = {
 
   async chatStream(systemPrompt: string, userMessage: string): Promise<void> {
     return invoke('ai_chat_stream', { systemPrompt, userMessage });
+  },
+
+  async createChatSession(date: string, title?: string): Promise<ChatSession> {
+    return invoke('create_chat_session', { date, title: title ?? null });
+  },
+
+  async listChatSessions(): Promise<ChatSessionSummary[]> {
+    return invoke('list_chat_sessions');
+  },
+
+  async getChatSession(id: string): Promise<ChatSession> {
+    return invoke('get_chat_session', { id });
+  },
+
+  async renameChatSession(id: string, title: string): Promise<ChatSessionSummary> {
+    return invoke('rename_chat_session', { id, title });
+  },
+
+  async deleteChatSession(id: string): Promise<void> {
+    return invoke('delete_chat_session', { id });
+  },
+
+  /** 导出会话，返回导出文件路径 */
+  async exportChatSession(id: string, format: 'markdown' | 'json'): Promise<string> {
+    return invoke('export_chat_session', { id, format });
+  },
+
+  /** 在会话中发送消息（附带历史），回复通过 chat-chunk 事件流式推送 */
+  async sendChatMessage(sessionId: string, systemPrompt: string, content: string): Promise<ChatMessage> {
+    return invoke('send_chat_message', { sessionId, systemPrompt, content });
   }
 };